@@ -0,0 +1,125 @@
+// Times parsing and both parts of every day on the inputs in resources/tests, and compares the
+// times with a baseline saved by an earlier run.
+//
+//   cargo bench --bench days
+//   cargo bench --bench days -- --save-baseline
+//   cargo bench --bench days -- --day 12 --threshold 5
+//   cargo bench --bench days -- --baseline other.json
+//
+// The baseline is target/benchmark-baseline.json unless --baseline is given, and only changes of
+// more than the threshold (10% by default) are flagged.
+//
+// With --features alloc-stats, the peak memory allocated and the number of allocations of each
+// stage are shown as well. Counting slows allocation down, so such runs should only be compared
+// with a baseline that was saved with the feature too.
+//
+// Exits with an error if any stage is slower than the baseline by more than the threshold.
+
+use std::fs;
+use std::path::PathBuf;
+use std::process;
+
+use aoc2021::alloc_stats;
+use aoc2021::benchmark::{self, Baseline, Comparison};
+use aoc2021::registry;
+
+const DEFAULT_BASELINE: &str = "target/benchmark-baseline.json";
+const DEFAULT_THRESHOLD: f64 = 10.0;
+
+struct Options {
+    days: Vec<u8>,
+    baseline: PathBuf,
+    save_baseline: bool,
+    threshold: f64,
+}
+
+fn parse_options() -> Result<Options, String> {
+    let mut options = Options {
+        days: Vec::new(),
+        baseline: PathBuf::from(DEFAULT_BASELINE),
+        save_baseline: false,
+        threshold: DEFAULT_THRESHOLD,
+    };
+    let mut args = std::env::args().skip(1);
+    while let Some(arg) = args.next() {
+        let mut value = |name: &str| args.next().ok_or(format!("{} needs a value", name));
+        match arg.as_str() {
+            "--day" => options.days.push(
+                value("--day")?
+                    .parse()
+                    .map_err(|_| "Invalid day".to_string())?,
+            ),
+            "--baseline" => options.baseline = PathBuf::from(value("--baseline")?),
+            "--save-baseline" => options.save_baseline = true,
+            "--threshold" => {
+                options.threshold = value("--threshold")?
+                    .parse()
+                    .map_err(|_| "Invalid threshold".to_string())?
+            }
+            // Passed by cargo bench
+            "--bench" => {}
+            _ => return Err(format!("Unknown argument '{}'", arg)),
+        }
+    }
+    if options.days.is_empty() {
+        options.days = registry::days();
+    }
+    Ok(options)
+}
+
+fn run(options: &Options) -> Result<bool, String> {
+    let baseline = Baseline::load(&options.baseline)?.unwrap_or_default();
+    let mut measurements = Vec::new();
+    let mut regressed = false;
+    for &day in &options.days {
+        let path = PathBuf::from(format!("resources/tests/{}", day));
+        let input = fs::read_to_string(&path)
+            .map_err(|e| format!("Failed to read {}: {}", path.display(), e))?;
+        for measurement in benchmark::measure_day(day, &input)? {
+            let comparison = match benchmark::compare(&baseline, &measurement, options.threshold) {
+                Comparison::New => "new".to_string(),
+                Comparison::Regression(change) => {
+                    regressed = true;
+                    format!("{:+.1}% REGRESSION", change)
+                }
+                Comparison::Improvement(change) => format!("{:+.1}% improvement", change),
+                Comparison::Unchanged(change) => format!("{:+.1}%", change),
+            };
+            let memory = match measurement.memory {
+                Some(memory) => format!(
+                    "{:>12} peak {:>8} allocations  ",
+                    alloc_stats::format_bytes(memory.peak_bytes),
+                    memory.allocations
+                ),
+                None => String::new(),
+            };
+            println!(
+                "{:<16} {:>12.3?}  {}{}",
+                measurement.key(),
+                measurement.median,
+                memory,
+                comparison
+            );
+            measurements.push(measurement);
+        }
+    }
+    if options.save_baseline {
+        Baseline::from_measurements(&measurements).save(&options.baseline)?;
+        println!("Saved the baseline to {}", options.baseline.display());
+    }
+    Ok(regressed)
+}
+
+fn main() {
+    match parse_options().and_then(|options| run(&options)) {
+        Ok(false) => {}
+        Ok(true) => {
+            eprintln!("Some stages are slower than the baseline");
+            process::exit(1);
+        }
+        Err(e) => {
+            eprintln!("{}", e);
+            process::exit(1);
+        }
+    }
+}
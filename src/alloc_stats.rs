@@ -0,0 +1,147 @@
+use std::alloc::{GlobalAlloc, Layout, System};
+use std::cell::Cell;
+
+use serde::Serialize;
+
+// Counts the heap allocations of each thread, on top of the system allocator. It is only installed
+// as the global allocator with the `alloc-stats` feature, as counting slows every allocation down.
+pub struct CountingAllocator;
+
+#[cfg(feature = "alloc-stats")]
+#[global_allocator]
+static ALLOCATOR: CountingAllocator = CountingAllocator;
+
+// Per thread, so that allocations made by other threads (e.g. other tests) aren't counted. Memory
+// freed by a different thread than allocated it makes the current size drift, hence the sign.
+thread_local! {
+    static CURRENT_BYTES: Cell<isize> = const { Cell::new(0) };
+    static PEAK_BYTES: Cell<isize> = const { Cell::new(0) };
+    static ALLOCATIONS: Cell<usize> = const { Cell::new(0) };
+}
+
+// Records that the thread's heap grew (or shrank) by the given number of bytes. The thread locals
+// may already be gone while a thread exits, in which case nothing is recorded.
+fn record(change: isize, allocations: usize) {
+    let _ = CURRENT_BYTES.try_with(|current| {
+        let bytes = current.get() + change;
+        current.set(bytes);
+        let _ = PEAK_BYTES.try_with(|peak| peak.set(peak.get().max(bytes)));
+        let _ = ALLOCATIONS.try_with(|count| count.set(count.get() + allocations));
+    });
+}
+
+unsafe impl GlobalAlloc for CountingAllocator {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        let ptr = System.alloc(layout);
+        if !ptr.is_null() {
+            record(layout.size() as isize, 1);
+        }
+        ptr
+    }
+
+    unsafe fn alloc_zeroed(&self, layout: Layout) -> *mut u8 {
+        let ptr = System.alloc_zeroed(layout);
+        if !ptr.is_null() {
+            record(layout.size() as isize, 1);
+        }
+        ptr
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        System.dealloc(ptr, layout);
+        record(-(layout.size() as isize), 0);
+    }
+
+    unsafe fn realloc(&self, ptr: *mut u8, layout: Layout, new_size: usize) -> *mut u8 {
+        let new_ptr = System.realloc(ptr, layout, new_size);
+        if !new_ptr.is_null() {
+            record(new_size as isize - layout.size() as isize, 1);
+        }
+        new_ptr
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+pub struct AllocationStats {
+    // The most memory that was allocated at once, not counting what was allocated beforehand
+    pub peak_bytes: usize,
+
+    // The number of allocations (including reallocations)
+    pub allocations: usize,
+}
+
+// Runs the function and returns how much it allocated on this thread, or None when allocations
+// aren't being counted (without the `alloc-stats` feature). Calls can be nested.
+pub fn measure<T>(f: impl FnOnce() -> T) -> (T, Option<AllocationStats>) {
+    if !cfg!(feature = "alloc-stats") {
+        return (f(), None);
+    }
+    let start_bytes = CURRENT_BYTES.with(Cell::get);
+    let start_allocations = ALLOCATIONS.with(Cell::get);
+    // The peak is tracked from the start of this call, and afterwards put back to the peak of any
+    // call that this one is nested in
+    let outer_peak = PEAK_BYTES.with(|peak| peak.replace(start_bytes));
+    let result = f();
+    let peak = PEAK_BYTES.with(|peak| peak.replace(peak.get().max(outer_peak)));
+    let stats = AllocationStats {
+        peak_bytes: (peak - start_bytes).max(0) as usize,
+        allocations: ALLOCATIONS.with(Cell::get) - start_allocations,
+    };
+    (result, Some(stats))
+}
+
+// Formats a number of bytes in the largest unit that keeps at least four digits
+pub fn format_bytes(bytes: usize) -> String {
+    match bytes {
+        0..=9_999 => format!("{} B", bytes),
+        10_000..=9_999_999 => format!("{} KiB", bytes / 1024),
+        _ => format!("{} MiB", bytes / (1024 * 1024)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::alloc_stats::{format_bytes, measure};
+
+    #[test]
+    fn test_measure() {
+        let (sum, stats) = measure(|| {
+            let small = (0..100).collect::<Vec<u8>>();
+            let large = (0..10_000).collect::<Vec<u32>>();
+            small.len() + large.len()
+        });
+        assert_eq!(sum, 10_100);
+        assert_eq!(stats.is_some(), cfg!(feature = "alloc-stats"));
+        if let Some(stats) = stats {
+            assert!(stats.peak_bytes >= 40_100);
+            assert!(stats.allocations >= 2);
+        }
+        let (_, stats) = measure(|| 1 + 1);
+        if let Some(stats) = stats {
+            assert_eq!(stats.allocations, 0);
+            assert_eq!(stats.peak_bytes, 0);
+        }
+    }
+
+    #[test]
+    fn test_nested_measure() {
+        let (inner, outer) = measure(|| {
+            drop(std::hint::black_box(vec![0_u8; 100_000]));
+            let (_, inner) = measure(|| vec![0_u8; 10].len());
+            inner
+        });
+        assert_eq!(outer.is_some(), cfg!(feature = "alloc-stats"));
+        if let (Some(inner), Some(outer)) = (inner, outer) {
+            assert!(inner.peak_bytes < 1000);
+            assert!(outer.peak_bytes >= 100_000);
+            assert!(outer.allocations >= 2);
+        }
+    }
+
+    #[test]
+    fn test_format_bytes() {
+        assert_eq!(format_bytes(512), "512 B");
+        assert_eq!(format_bytes(2_000_000), "1953 KiB");
+        assert_eq!(format_bytes(3_000_000_000), "2861 MiB");
+    }
+}
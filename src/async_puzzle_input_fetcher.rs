@@ -0,0 +1,460 @@
+use std::env;
+use std::io::ErrorKind;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
+
+use futures::stream::{self, StreamExt};
+use reqwest::StatusCode;
+use tokio::fs;
+use tokio::sync::OnceCell;
+use tracing::{debug, info, warn};
+
+use crate::config::{Config, SessionTokenSource};
+use crate::puzzle_input_cipher::PuzzleInputCipher;
+use crate::puzzle_input_fetcher::{
+    body_error, check_session_token, decode_local_puzzle_input, encode_local_puzzle_input,
+    local_path, not_cached_error, path_to_str, remote_url_path, status_error, Result,
+};
+
+// An asynchronous version of PuzzleInputFetcher. All of its methods take &self, so a single
+// fetcher can be shared (e.g. via Arc) between tasks. Each puzzle input and the session token are
+// loaded at most once, even when they are requested by several tasks at the same time.
+pub struct AsyncPuzzleInputFetcher {
+    // The base URL for Advent of Code (by default 'https://adventofcode.com/')
+    base_url: String,
+
+    // The event year to fetch puzzle input for (by default 2021)
+    year: u16,
+
+    // The location where puzzle input is stored (by default 'puzzle')
+    input_path: PathBuf,
+
+    // Where the session token comes from (by default the file 'cookie.txt')
+    session_token_source: SessionTokenSource,
+
+    // The input cache that stores our puzzles
+    inputs: Vec<OnceCell<String>>,
+
+    // The session token cache
+    session_token: OnceCell<String>,
+
+    // The cipher used to encrypt puzzle input in the local store (if any)
+    cipher: Option<PuzzleInputCipher>,
+
+    // Whether the remote store may be used at all (it is never used in offline mode)
+    offline: bool,
+
+    // The HTTP client (shared by all requests so that connections are reused)
+    client: reqwest::Client,
+}
+
+impl AsyncPuzzleInputFetcher {
+    // Creates an AsyncPuzzleInputFetcher using the default values. The local store is encrypted if
+    // a cache key is found in the environment or in 'cache.key'.
+    pub fn create() -> Result<AsyncPuzzleInputFetcher> {
+        AsyncPuzzleInputFetcher::create_from_config(&Config::default())
+    }
+
+    // Creates an AsyncPuzzleInputFetcher using the given configuration. The local store is
+    // encrypted if a cache key is found in the environment or in the configured key file.
+    pub fn create_from_config(config: &Config) -> Result<AsyncPuzzleInputFetcher> {
+        let mut fetcher = AsyncPuzzleInputFetcher::create_custom(
+            &config.base_url,
+            &config.cache_dir,
+            Path::new(""),
+        )
+        .with_timeout(config.request_timeout)?;
+        fetcher.year = config.year;
+        fetcher.session_token_source = config.session_token.clone();
+        fetcher.offline = config.offline;
+        match PuzzleInputCipher::load(&config.cache_key_path)? {
+            Some(cipher) => Ok(fetcher.with_cipher(cipher)),
+            None => Ok(fetcher),
+        }
+    }
+
+    // Creates an AsyncPuzzleInputFetcher with a specified base url, puzzle input path, and session
+    // token path.
+    pub fn create_custom(
+        base_url: &str,
+        input_path: &Path,
+        session_token_path: &Path,
+    ) -> AsyncPuzzleInputFetcher {
+        AsyncPuzzleInputFetcher {
+            base_url: base_url.to_string(),
+            year: 2021,
+            input_path: input_path.to_path_buf(),
+            session_token_source: SessionTokenSource::File(session_token_path.to_path_buf()),
+            inputs: (0..25).map(|_| OnceCell::new()).collect(),
+            session_token: OnceCell::new(),
+            cipher: None,
+            offline: false,
+            client: reqwest::Client::builder()
+                .timeout(Duration::from_secs(30))
+                .build()
+                .unwrap(),
+        }
+    }
+
+    // Sets the timeout for requests to the remote store (by default 30 seconds)
+    pub fn with_timeout(mut self, timeout: Duration) -> Result<AsyncPuzzleInputFetcher> {
+        self.client = reqwest::Client::builder()
+            .timeout(timeout)
+            .build()
+            .map_err(|e| format!("Failed to create HTTP client: {}", e))?;
+        Ok(self)
+    }
+
+    // Encrypts puzzle input stored locally from now on, and decrypts encrypted puzzle input found
+    // in the local store.
+    pub fn with_cipher(mut self, cipher: PuzzleInputCipher) -> AsyncPuzzleInputFetcher {
+        self.cipher = Some(cipher);
+        self
+    }
+
+    // In offline mode, puzzle input that isn't in the local store is reported as not cached instead
+    // of being fetched from the remote store.
+    pub fn with_offline(mut self, offline: bool) -> AsyncPuzzleInputFetcher {
+        self.offline = offline;
+        self
+    }
+
+    // Returns the puzzle input for the given day first by fetching it from the in-memory cache,
+    // then by fetching it from the local store, and finally by fetching it from the remote store
+    // (the site itself).
+    pub async fn fetch_puzzle_input(&self, day: u8) -> Result<&str> {
+        if !(1..=25).contains(&day) {
+            return Err(format!("There is no puzzle for day {}", day));
+        }
+        let input = self.inputs[(day - 1) as usize]
+            .get_or_try_init(|| async {
+                if let Some(local_input) = self.fetch_local_puzzle_input(day).await? {
+                    debug!(day, "Puzzle input found in the local store");
+                    return Ok(local_input);
+                }
+                debug!(day, "Puzzle input not found in the local store");
+                if self.offline {
+                    return Err(not_cached_error(day));
+                }
+                let session_token = self
+                    .session_token
+                    .get_or_try_init(|| self.fetch_session_token())
+                    .await?;
+                let remote_input = self.fetch_remote_puzzle_input(day, session_token).await?;
+                self.store_puzzle_input_locally(day, &remote_input).await;
+                Ok::<String, String>(remote_input)
+            })
+            .await?;
+        Ok(input)
+    }
+
+    // Returns the puzzle inputs for the given days, fetching at most `concurrency` of them at the
+    // same time. The results are in the same order as the days.
+    pub async fn fetch_puzzle_inputs(
+        &self,
+        days: &[u8],
+        concurrency: usize,
+    ) -> Vec<(u8, Result<&str>)> {
+        let mut results = stream::iter(days.iter().copied().enumerate())
+            .map(|(i, day)| async move { (i, day, self.fetch_puzzle_input(day).await) })
+            .buffer_unordered(concurrency.max(1))
+            .collect::<Vec<_>>()
+            .await;
+        results.sort_by_key(|&(i, _, _)| i);
+        results
+            .into_iter()
+            .map(|(_, day, result)| (day, result))
+            .collect()
+    }
+
+    async fn fetch_local_puzzle_input(&self, day: u8) -> Result<Option<String>> {
+        match fs::read(local_path(&self.input_path, self.year, day)).await {
+            Ok(data) => decode_local_puzzle_input(day, data, self.cipher.as_ref()).map(Some),
+            Err(e) if e.kind() == ErrorKind::NotFound => Ok(None),
+            Err(e) => Err(format!(
+                "Failed to fetch local puzzle for day {}: {}",
+                day, e
+            )),
+        }
+    }
+
+    async fn store_puzzle_input_locally(&self, day: u8, input: &str) {
+        // As with PuzzleInputFetcher, failing to save to disk shouldn't be a critical error.
+        let Some(data) = encode_local_puzzle_input(input, self.cipher.as_ref()) else {
+            warn!(day, "Failed to encrypt puzzle input for the local store");
+            return;
+        };
+        let path = local_path(&self.input_path, self.year, day);
+        let result = match fs::create_dir_all(path.parent().unwrap()).await {
+            Ok(()) => fs::write(&path, data).await,
+            Err(e) => Err(e),
+        };
+        match result {
+            Ok(()) => debug!(day, path = %path.display(), "Stored puzzle input locally"),
+            Err(e) => {
+                warn!(day, path = %path.display(), error = %e, "Failed to store puzzle input locally")
+            }
+        }
+    }
+
+    async fn fetch_remote_puzzle_input(&self, day: u8, session_token: &str) -> Result<String> {
+        let path = format!("{}{}", self.base_url, remote_url_path(self.year, day));
+        info!(day, url = %path, "Fetching puzzle input from the remote store");
+        let start = Instant::now();
+        let response = self
+            .client
+            .get(&path)
+            .header("Cookie", format!("session={}", session_token))
+            .send()
+            .await
+            .map_err(|e| {
+                warn!(day, error = %e, "Request for puzzle input failed");
+                format!("Failed to fetch remote puzzle input for day {}: {}", day, e)
+            })?;
+        info!(
+            day,
+            status = response.status().as_u16(),
+            elapsed = ?start.elapsed(),
+            "Got a response from the remote store"
+        );
+        if response.status() != StatusCode::OK {
+            Err(status_error(day, response.status()))
+        } else {
+            response.text().await.map_err(|e| body_error(day, e))
+        }
+    }
+
+    // Fetches the session token from the disk or from the environment
+    async fn fetch_session_token(&self) -> Result<String> {
+        let session_token = match &self.session_token_source {
+            SessionTokenSource::File(path) => fs::read_to_string(path).await.map_err(|e| {
+                format!(
+                    "Failed to fetch session token from {}: {}",
+                    path_to_str(path),
+                    e
+                )
+            })?,
+            SessionTokenSource::Env(var) => env::var(var)
+                .map_err(|e| format!("Failed to fetch session token from ${}: {}", var, e))?,
+        };
+        debug!(source = ?self.session_token_source, "Read the session token");
+        check_session_token(session_token.trim().to_string())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::async_puzzle_input_fetcher::AsyncPuzzleInputFetcher;
+    use crate::puzzle_input_fetcher::remote_url_path;
+
+    use httpmock::Method::GET;
+    use httpmock::MockServer;
+    use rand::Rng;
+    use std::fs;
+    use std::sync::Arc;
+    use std::time::Duration;
+    use tempfile::{tempdir, NamedTempFile};
+
+    //noinspection DuplicatedCode
+    #[tokio::test]
+    async fn test_fetch_from_local_store() {
+        let server = MockServer::start_async().await;
+        let puzzle_store_dir = tempdir().unwrap();
+        let session_token_path = NamedTempFile::new().unwrap();
+        let fetcher = AsyncPuzzleInputFetcher::create_custom(
+            &server.base_url(),
+            puzzle_store_dir.path(),
+            session_token_path.path(),
+        );
+        for day in 1..26 {
+            let puzzle_input = random_puzzle();
+            fs::write(puzzle_store_dir.path().join(day.to_string()), &puzzle_input).unwrap();
+            let mock = server
+                .mock_async(|when, then| {
+                    when.method(GET).path(remote_url_path(2021, day).as_str());
+                    then.status(501);
+                })
+                .await;
+            assert_eq!(fetcher.fetch_puzzle_input(day).await.unwrap(), puzzle_input);
+            mock.assert_hits_async(0).await;
+        }
+    }
+
+    //noinspection DuplicatedCode
+    #[tokio::test(flavor = "multi_thread", worker_threads = 4)]
+    async fn test_fetch_from_remote_store_concurrently() {
+        let server = MockServer::start_async().await;
+        let puzzle_store_dir = tempdir().unwrap();
+        let session_token_path = NamedTempFile::new().unwrap();
+        let session_token = random_session_token();
+        fs::write(session_token_path.path(), &session_token).unwrap();
+        let fetcher = Arc::new(AsyncPuzzleInputFetcher::create_custom(
+            &server.base_url(),
+            puzzle_store_dir.path(),
+            session_token_path.path(),
+        ));
+        let mut puzzle_inputs = Vec::new();
+        let mut mocks = Vec::new();
+        for day in 1..26 {
+            let puzzle_input = random_puzzle();
+            mocks.push(
+                server
+                    .mock_async(|when, then| {
+                        when.method(GET)
+                            .path(remote_url_path(2021, day).as_str())
+                            .header("Cookie", format!("session={}", session_token).as_str());
+                        then.status(200)
+                            .body(&puzzle_input)
+                            .delay(Duration::from_millis(20));
+                    })
+                    .await,
+            );
+            puzzle_inputs.push(puzzle_input);
+        }
+        // Every day is requested twice by separate tasks, but each is fetched only once
+        let days = (1..26).chain(1..26).collect::<Vec<u8>>();
+        let tasks = [0, 1].map(|_| {
+            let fetcher = Arc::clone(&fetcher);
+            let days = days.clone();
+            tokio::spawn(async move {
+                fetcher
+                    .fetch_puzzle_inputs(&days, 8)
+                    .await
+                    .into_iter()
+                    .map(|(day, result)| (day, result.map(|s| s.to_string())))
+                    .collect::<Vec<_>>()
+            })
+        });
+        for task in tasks {
+            let results = task.await.unwrap();
+            assert_eq!(results.len(), days.len());
+            for ((day, result), &expected_day) in results.into_iter().zip(days.iter()) {
+                assert_eq!(day, expected_day);
+                assert_eq!(result.unwrap(), puzzle_inputs[(day - 1) as usize]);
+            }
+        }
+        for mock in mocks {
+            mock.assert_hits_async(1).await;
+        }
+        for day in 1..26 {
+            let stored = fs::read_to_string(puzzle_store_dir.path().join(day.to_string()));
+            assert_eq!(stored.unwrap(), puzzle_inputs[day - 1]);
+        }
+    }
+
+    //noinspection DuplicatedCode
+    #[tokio::test]
+    async fn test_error_returned_when_all_sources_unavailable() {
+        let server = MockServer::start_async().await;
+        let puzzle_store_dir = tempdir().unwrap();
+        let session_token_path = NamedTempFile::new().unwrap();
+        let session_token = random_session_token();
+        fs::write(session_token_path.path(), &session_token).unwrap();
+        let fetcher = AsyncPuzzleInputFetcher::create_custom(
+            &server.base_url(),
+            puzzle_store_dir.path(),
+            session_token_path.path(),
+        );
+        let mock = server
+            .mock_async(|when, then| {
+                when.method(GET)
+                    .header("Cookie", format!("session={}", session_token).as_str());
+                then.status(501);
+            })
+            .await;
+        let days = (1..26).collect::<Vec<u8>>();
+        for (_, result) in fetcher.fetch_puzzle_inputs(&days, 4).await {
+            assert!(result.is_err());
+        }
+        mock.assert_hits_async(25).await;
+    }
+
+    //noinspection DuplicatedCode
+    #[tokio::test]
+    async fn test_error_when_fetching_from_remote_if_missing_session_token() {
+        let server = MockServer::start_async().await;
+        let puzzle_store_dir = tempdir().unwrap();
+        let session_token_path = NamedTempFile::new().unwrap();
+        let fetcher = AsyncPuzzleInputFetcher::create_custom(
+            &server.base_url(),
+            puzzle_store_dir.path(),
+            session_token_path.path(),
+        );
+        let mock = server
+            .mock_async(|when, then| {
+                when.method(GET);
+                then.status(400)
+                    .body("Puzzle inputs differ by user.  Please log in to get your puzzle input.");
+            })
+            .await;
+        let days = (1..26).collect::<Vec<u8>>();
+        for (_, result) in fetcher.fetch_puzzle_inputs(&days, 4).await {
+            assert!(result.is_err());
+        }
+        mock.assert_hits_async(0).await;
+    }
+
+    //noinspection DuplicatedCode
+    #[tokio::test]
+    async fn test_error_when_not_cached_in_offline_mode() {
+        let server = MockServer::start_async().await;
+        let puzzle_store_dir = tempdir().unwrap();
+        let session_token_path = NamedTempFile::new().unwrap();
+        fs::write(session_token_path.path(), random_session_token()).unwrap();
+        let fetcher = AsyncPuzzleInputFetcher::create_custom(
+            &server.base_url(),
+            puzzle_store_dir.path(),
+            session_token_path.path(),
+        )
+        .with_offline(true);
+        let puzzle_input = random_puzzle();
+        fs::write(puzzle_store_dir.path().join("1"), &puzzle_input).unwrap();
+        let mock = server
+            .mock_async(|when, then| {
+                when.any_request();
+                then.status(200).body(random_puzzle());
+            })
+            .await;
+        let days = (1..26).collect::<Vec<u8>>();
+        for (day, result) in fetcher.fetch_puzzle_inputs(&days, 4).await {
+            if day == 1 {
+                assert_eq!(result.unwrap(), puzzle_input);
+            } else {
+                assert!(result.unwrap_err().contains("not cached"));
+            }
+        }
+        mock.assert_hits_async(0).await;
+    }
+
+    #[tokio::test]
+    async fn test_error_when_fetching_invalid_day() {
+        let puzzle_store_dir = tempdir().unwrap();
+        let session_token_path = NamedTempFile::new().unwrap();
+        let fetcher = AsyncPuzzleInputFetcher::create_custom(
+            "http://localhost",
+            puzzle_store_dir.path(),
+            session_token_path.path(),
+        );
+        assert!(fetcher.fetch_puzzle_input(0).await.is_err());
+        assert!(fetcher.fetch_puzzle_input(26).await.is_err());
+    }
+
+    fn random_puzzle() -> String {
+        let charset = "\n ,-0123456789ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz";
+        random_string(charset, 4096)
+    }
+
+    fn random_session_token() -> String {
+        random_string("0123456789abcdef", 96)
+    }
+
+    fn random_string(charset: &str, length: usize) -> String {
+        let mut rng = rand::thread_rng();
+        (0..length)
+            .map(|_| {
+                let i = rng.gen_range(0..charset.len());
+                charset.chars().nth(i).unwrap()
+            })
+            .collect()
+    }
+}
@@ -0,0 +1,231 @@
+use std::collections::BTreeMap;
+use std::fmt;
+use std::fs;
+use std::path::Path;
+use std::time::{Duration, Instant};
+
+use crate::alloc_stats::{self, AllocationStats};
+use crate::registry;
+
+// How long each stage of a day is run for, and how often at least and at most
+const TARGET_TIME: Duration = Duration::from_millis(500);
+const MIN_ITERATIONS: usize = 5;
+const MAX_ITERATIONS: usize = 1000;
+
+// The stages of solving a day that are timed
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Stage {
+    Parse,
+    Part1,
+    Part2,
+}
+
+impl fmt::Display for Stage {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Stage::Parse => write!(f, "parse"),
+            Stage::Part1 => write!(f, "part 1"),
+            Stage::Part2 => write!(f, "part 2"),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Measurement {
+    pub day: u8,
+    pub stage: Stage,
+
+    // The median time of a run
+    pub median: Duration,
+
+    // What a run allocated, when allocations are being counted
+    pub memory: Option<AllocationStats>,
+}
+
+impl Measurement {
+    // The name that the measurement is stored under in a baseline
+    pub fn key(&self) -> String {
+        format!("day{:02} {}", self.day, self.stage)
+    }
+}
+
+// Times parsing and solving both parts of a day's puzzle, and counts what one run of each allocates
+pub fn measure_day(day: u8, input: &str) -> Result<Vec<Measurement>> {
+    let puzzle = registry::create_puzzle(day, input)?;
+    let measure = |stage, run: &dyn Fn()| Measurement {
+        day,
+        stage,
+        median: median_time(run),
+        memory: alloc_stats::measure(run).1,
+    };
+    Ok(vec![
+        measure(Stage::Parse, &|| {
+            registry::create_puzzle(day, input).unwrap();
+        }),
+        measure(Stage::Part1, &|| {
+            puzzle.solve_part_1();
+        }),
+        measure(Stage::Part2, &|| {
+            puzzle.solve_part_2();
+        }),
+    ])
+}
+
+fn median_time(run: &dyn Fn()) -> Duration {
+    let start = Instant::now();
+    let mut times = Vec::new();
+    while times.len() < MIN_ITERATIONS
+        || (times.len() < MAX_ITERATIONS && start.elapsed() < TARGET_TIME)
+    {
+        let iteration_start = Instant::now();
+        run();
+        times.push(iteration_start.elapsed());
+    }
+    times.sort();
+    times[times.len() / 2]
+}
+
+// The times from an earlier run (in nanoseconds), to compare later runs against
+#[derive(Debug, Default, PartialEq)]
+pub struct Baseline {
+    times: BTreeMap<String, u64>,
+}
+
+impl Baseline {
+    pub fn from_measurements(measurements: &[Measurement]) -> Baseline {
+        Baseline {
+            times: measurements
+                .iter()
+                .map(|m| (m.key(), m.median.as_nanos() as u64))
+                .collect(),
+        }
+    }
+
+    // Loads a baseline, or returns None if there is no baseline at the path yet
+    pub fn load(path: &Path) -> Result<Option<Baseline>> {
+        if !path.exists() {
+            return Ok(None);
+        }
+        let contents = fs::read_to_string(path)
+            .map_err(|e| format!("Failed to read baseline {}: {}", path.display(), e))?;
+        let times = serde_json::from_str(&contents)
+            .map_err(|e| format!("Invalid baseline {}: {}", path.display(), e))?;
+        Ok(Some(Baseline { times }))
+    }
+
+    // Saves the baseline, keeping the times of any days that were not measured this time
+    pub fn save(&self, path: &Path) -> Result<()> {
+        let mut times = Baseline::load(path)?.unwrap_or_default().times;
+        times.extend(self.times.clone());
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir).map_err(|e| e.to_string())?;
+        }
+        let json = serde_json::to_string_pretty(&times).map_err(|e| e.to_string())?;
+        fs::write(path, json + "\n")
+            .map_err(|e| format!("Failed to write baseline {}: {}", path.display(), e))
+    }
+
+    pub fn get(&self, measurement: &Measurement) -> Option<Duration> {
+        self.times
+            .get(&measurement.key())
+            .map(|&nanos| Duration::from_nanos(nanos))
+    }
+}
+
+// How a measurement compares with the baseline
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Comparison {
+    // The stage is not in the baseline
+    New,
+
+    // The stage is slower than the baseline by more than the threshold
+    Regression(f64),
+
+    // The stage is faster than the baseline by more than the threshold
+    Improvement(f64),
+
+    // The change (as a percentage) is within the threshold
+    Unchanged(f64),
+}
+
+// Compares a measurement with the baseline, where changes of less than the threshold percentage
+// count as noise
+pub fn compare(baseline: &Baseline, measurement: &Measurement, threshold: f64) -> Comparison {
+    let Some(before) = baseline.get(measurement) else {
+        return Comparison::New;
+    };
+    let before = before.as_nanos().max(1) as f64;
+    let change = (measurement.median.as_nanos() as f64 - before) * 100.0 / before;
+    if change > threshold {
+        Comparison::Regression(change)
+    } else if change < -threshold {
+        Comparison::Improvement(change)
+    } else {
+        Comparison::Unchanged(change)
+    }
+}
+
+type Result<T> = std::result::Result<T, String>;
+
+#[cfg(test)]
+mod tests {
+    use crate::benchmark::{compare, measure_day, Baseline, Comparison, Measurement, Stage};
+    use std::time::Duration;
+    use tempfile::tempdir;
+
+    fn measurement(day: u8, stage: Stage, millis: u64) -> Measurement {
+        Measurement {
+            day,
+            stage,
+            median: Duration::from_millis(millis),
+            memory: None,
+        }
+    }
+
+    #[test]
+    fn test_measure_day() {
+        let measurements = measure_day(6, "3,4,3,1,2").unwrap();
+        let stages = measurements.iter().map(|m| m.stage).collect::<Vec<_>>();
+        assert_eq!(stages, vec![Stage::Parse, Stage::Part1, Stage::Part2]);
+        assert_eq!(measurements[1].key(), "day06 part 1");
+        assert!(measure_day(6, "x").is_err());
+    }
+
+    #[test]
+    fn test_compare() {
+        let baseline = Baseline::from_measurements(&[measurement(1, Stage::Part1, 100)]);
+        let compare = |millis| compare(&baseline, &measurement(1, Stage::Part1, millis), 10.0);
+        assert_eq!(compare(120), Comparison::Regression(20.0));
+        assert_eq!(compare(80), Comparison::Improvement(-20.0));
+        assert_eq!(compare(105), Comparison::Unchanged(5.0));
+        assert_eq!(
+            super::compare(&baseline, &measurement(1, Stage::Part2, 100), 10.0),
+            Comparison::New
+        );
+    }
+
+    #[test]
+    fn test_save_and_load() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("baseline.json");
+        assert_eq!(Baseline::load(&path).unwrap(), None);
+        Baseline::from_measurements(&[
+            measurement(1, Stage::Part1, 100),
+            measurement(2, Stage::Part1, 100),
+        ])
+        .save(&path)
+        .unwrap();
+        Baseline::from_measurements(&[measurement(2, Stage::Part1, 50)])
+            .save(&path)
+            .unwrap();
+        let baseline = Baseline::load(&path).unwrap().unwrap();
+        assert_eq!(
+            baseline.get(&measurement(1, Stage::Part1, 0)),
+            Some(Duration::from_millis(100))
+        );
+        assert_eq!(
+            baseline.get(&measurement(2, Stage::Part1, 0)),
+            Some(Duration::from_millis(50))
+        );
+    }
+}
@@ -0,0 +1,333 @@
+use std::env;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+use std::time::Duration;
+
+use serde::Deserialize;
+use tracing::debug;
+
+// The project-local configuration file, looked up in the working directory
+pub const PROJECT_CONFIG_FILE: &str = "aoc2021.toml";
+
+// Settings for the runner and the puzzle input fetchers. Values are taken (from lowest to highest
+// priority) from the defaults, the user-level configuration file, the project-local configuration
+// file, and finally the command line.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Config {
+    // The base URL for Advent of Code (or a proxy/mirror of it)
+    pub base_url: String,
+
+    // The event year used when fetching puzzle input
+    pub year: u16,
+
+    // The location where puzzle input is stored
+    pub cache_dir: PathBuf,
+
+    // Where the session token comes from
+    pub session_token: SessionTokenSource,
+
+    // The location of the key used to encrypt the local store
+    pub cache_key_path: PathBuf,
+
+    // How the runner prints answers
+    pub output_format: OutputFormat,
+
+    // The days the runner solves when no days are given (all days if empty)
+    pub days: Vec<u8>,
+
+    // The timeout for requests to the remote store
+    pub request_timeout: Duration,
+
+    // Whether network access is forbidden
+    pub offline: bool,
+
+    // How long the runner lets each part of a puzzle run before giving up (no limit if None)
+    pub solve_timeout: Option<Duration>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum SessionTokenSource {
+    // The session token is read from a file
+    File(PathBuf),
+
+    // The session token is read from an environment variable
+    Env(String),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum OutputFormat {
+    Text,
+    Json,
+}
+
+impl FromStr for OutputFormat {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<OutputFormat> {
+        match s {
+            "text" => Ok(OutputFormat::Text),
+            "json" => Ok(OutputFormat::Json),
+            _ => Err(format!(
+                "Unknown output format '{}'. Expected 'text' or 'json'",
+                s
+            )),
+        }
+    }
+}
+
+impl Default for Config {
+    fn default() -> Config {
+        Config {
+            base_url: "https://adventofcode.com".to_string(),
+            year: 2021,
+            cache_dir: PathBuf::from("puzzle"),
+            session_token: SessionTokenSource::File(PathBuf::from("cookie.txt")),
+            cache_key_path: PathBuf::from("cache.key"),
+            output_format: OutputFormat::Text,
+            days: Vec::new(),
+            request_timeout: Duration::from_secs(30),
+            offline: false,
+            solve_timeout: None,
+        }
+    }
+}
+
+// The contents of a configuration file. Every setting is optional so that files only need to
+// contain the settings they change.
+#[derive(Debug, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct ConfigFile {
+    base_url: Option<String>,
+    year: Option<u16>,
+    cache_dir: Option<PathBuf>,
+    session_token_path: Option<PathBuf>,
+    session_token_env: Option<String>,
+    cache_key_path: Option<PathBuf>,
+    output_format: Option<String>,
+    days: Option<Vec<u8>>,
+    request_timeout: Option<String>,
+    offline: Option<bool>,
+    solve_timeout: Option<String>,
+}
+
+impl Config {
+    // Loads the configuration from the user-level configuration file and then from the given
+    // configuration file, or the project-local configuration file if none is given.
+    pub fn load(config_path: Option<&Path>) -> Result<Config> {
+        let mut config = Config::default();
+        if let Some(path) = user_config_path() {
+            if path.exists() {
+                config.merge_file(&path)?;
+            }
+        }
+        match config_path {
+            Some(path) => config.merge_file(path)?,
+            None => {
+                let path = Path::new(PROJECT_CONFIG_FILE);
+                if path.exists() {
+                    config.merge_file(path)?;
+                }
+            }
+        }
+        Ok(config)
+    }
+
+    fn merge_file(&mut self, path: &Path) -> Result<()> {
+        debug!(path = %path.display(), "Reading config file");
+        let contents = fs::read_to_string(path)
+            .map_err(|e| format!("Failed to read config file {}: {}", path.display(), e))?;
+        self.merge_str(&contents)
+            .map_err(|e| format!("Invalid config file {}: {}", path.display(), e))
+    }
+
+    fn merge_str(&mut self, contents: &str) -> Result<()> {
+        let file: ConfigFile = toml::from_str(contents).map_err(|e| e.to_string())?;
+        if file.session_token_path.is_some() && file.session_token_env.is_some() {
+            return Err(
+                "Only one of session_token_path and session_token_env may be set".to_string(),
+            );
+        }
+        if let Some(base_url) = file.base_url {
+            self.base_url = base_url.trim_end_matches('/').to_string();
+        }
+        if let Some(year) = file.year {
+            self.year = year;
+        }
+        if let Some(cache_dir) = file.cache_dir {
+            self.cache_dir = cache_dir;
+        }
+        if let Some(path) = file.session_token_path {
+            self.session_token = SessionTokenSource::File(path);
+        }
+        if let Some(var) = file.session_token_env {
+            self.session_token = SessionTokenSource::Env(var);
+        }
+        if let Some(path) = file.cache_key_path {
+            self.cache_key_path = path;
+        }
+        if let Some(format) = file.output_format {
+            self.output_format = format.parse()?;
+        }
+        if let Some(days) = file.days {
+            self.days = check_days(days)?;
+        }
+        if let Some(timeout) = file.request_timeout {
+            self.request_timeout = parse_duration(&timeout)?;
+        }
+        if let Some(offline) = file.offline {
+            self.offline = offline;
+        }
+        if let Some(timeout) = file.solve_timeout {
+            self.solve_timeout = Some(parse_duration(&timeout)?);
+        }
+        Ok(())
+    }
+}
+
+// Returns the location of the user-level configuration file
+pub fn user_config_path() -> Option<PathBuf> {
+    let config_dir = env::var_os("XDG_CONFIG_HOME")
+        .map(PathBuf::from)
+        .or_else(|| env::var_os("HOME").map(|home| PathBuf::from(home).join(".config")))
+        .or_else(|| env::var_os("APPDATA").map(PathBuf::from))?;
+    Some(config_dir.join("aoc2021").join("config.toml"))
+}
+
+pub fn check_days(days: Vec<u8>) -> Result<Vec<u8>> {
+    match days.iter().find(|&&day| !(1..=25).contains(&day)) {
+        Some(day) => Err(format!("There is no puzzle for day {}", day)),
+        None => Ok(days),
+    }
+}
+
+// Parses durations such as '500ms', '5s', '2m' or '1.5s'. A number without a unit is in seconds.
+pub fn parse_duration(s: &str) -> Result<Duration> {
+    let s = s.trim();
+    let split = s
+        .find(|c: char| !c.is_ascii_digit() && c != '.')
+        .unwrap_or(s.len());
+    let (value, unit) = s.split_at(split);
+    let value = value
+        .parse::<f64>()
+        .map_err(|_| format!("Invalid duration '{}'", s))?;
+    let seconds = match unit.trim() {
+        "ms" => value / 1000.0,
+        "" | "s" => value,
+        "m" => value * 60.0,
+        _ => {
+            return Err(format!(
+                "Invalid duration '{}'. Expected a unit of ms, s or m",
+                s
+            ))
+        }
+    };
+    // Durations that are too long to represent are rejected rather than panicking
+    Duration::try_from_secs_f64(seconds).map_err(|_| format!("Invalid duration '{}'", s))
+}
+
+type Result<T> = std::result::Result<T, String>;
+
+#[cfg(test)]
+mod tests {
+    use crate::config::{parse_duration, Config, OutputFormat, SessionTokenSource};
+    use std::io::Write;
+    use std::path::PathBuf;
+    use std::time::Duration;
+    use tempfile::NamedTempFile;
+
+    #[test]
+    fn test_empty_file_keeps_defaults() {
+        let mut config = Config::default();
+        config.merge_str("").unwrap();
+        assert_eq!(config, Config::default());
+    }
+
+    #[test]
+    fn test_all_settings() {
+        let mut config = Config::default();
+        config
+            .merge_str(
+                "base_url = \"http://mirror.local:8080/\"\n\
+                 year = 2020\n\
+                 cache_dir = \"inputs\"\n\
+                 session_token_env = \"AOC_SESSION\"\n\
+                 cache_key_path = \"keys/aoc.key\"\n\
+                 output_format = \"json\"\n\
+                 days = [1, 5, 17]\n\
+                 request_timeout = \"1500ms\"\n\
+                 offline = true\n\
+                 solve_timeout = \"5s\"\n",
+            )
+            .unwrap();
+        assert_eq!(config.base_url, "http://mirror.local:8080");
+        assert_eq!(config.year, 2020);
+        assert_eq!(config.cache_dir, PathBuf::from("inputs"));
+        assert_eq!(
+            config.session_token,
+            SessionTokenSource::Env("AOC_SESSION".to_string())
+        );
+        assert_eq!(config.cache_key_path, PathBuf::from("keys/aoc.key"));
+        assert_eq!(config.output_format, OutputFormat::Json);
+        assert_eq!(config.days, vec![1, 5, 17]);
+        assert_eq!(config.request_timeout, Duration::from_millis(1500));
+        assert!(config.offline);
+        assert_eq!(config.solve_timeout, Some(Duration::from_secs(5)));
+    }
+
+    #[test]
+    fn test_later_files_override_earlier_files() {
+        let mut config = Config::default();
+        config
+            .merge_str("year = 2020\ncache_dir = \"user\"\n")
+            .unwrap();
+        config.merge_str("cache_dir = \"project\"\n").unwrap();
+        assert_eq!(config.year, 2020);
+        assert_eq!(config.cache_dir, PathBuf::from("project"));
+    }
+
+    #[test]
+    fn test_invalid_settings_rejected() {
+        assert!(Config::default().merge_str("unknown = 1").is_err());
+        assert!(Config::default().merge_str("year = \"2021\"").is_err());
+        assert!(Config::default().merge_str("days = [0]").is_err());
+        assert!(Config::default().merge_str("days = [26]").is_err());
+        assert!(Config::default()
+            .merge_str("output_format = \"xml\"")
+            .is_err());
+        assert!(Config::default()
+            .merge_str("request_timeout = \"soon\"")
+            .is_err());
+        assert!(Config::default()
+            .merge_str("solve_timeout = \"5 days\"")
+            .is_err());
+        assert!(Config::default()
+            .merge_str("session_token_path = \"a\"\nsession_token_env = \"B\"")
+            .is_err());
+    }
+
+    #[test]
+    fn test_load_explicit_file() {
+        let mut file = NamedTempFile::new().unwrap();
+        file.write_all(b"year = 2019\n").unwrap();
+        let config = Config::load(Some(file.path())).unwrap();
+        assert_eq!(config.year, 2019);
+        assert!(Config::load(Some(&file.path().join("missing"))).is_err());
+    }
+
+    #[test]
+    fn test_parse_duration() {
+        assert_eq!(parse_duration("5s").unwrap(), Duration::from_secs(5));
+        assert_eq!(parse_duration("5").unwrap(), Duration::from_secs(5));
+        assert_eq!(parse_duration("250ms").unwrap(), Duration::from_millis(250));
+        assert_eq!(parse_duration("1.5s").unwrap(), Duration::from_millis(1500));
+        assert_eq!(parse_duration("2m").unwrap(), Duration::from_secs(120));
+        assert!(parse_duration("").is_err());
+        assert!(parse_duration("5h").is_err());
+        assert!(parse_duration("s").is_err());
+        assert!(parse_duration("99999999999999999999m").is_err());
+        assert!(parse_duration(&"9".repeat(400)).is_err());
+        assert!(parse_duration("inf").is_err());
+        assert!(parse_duration("NaN").is_err());
+    }
+}
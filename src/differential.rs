@@ -0,0 +1,41 @@
+// Differential testing: a puzzle's solution is run alongside a straightforward (and slow) reference
+// solution on generated input, so that shortcuts in the solution are checked on input other than
+// the fixed test input.
+
+use rand::rngs::StdRng;
+use rand::SeedableRng;
+
+use crate::generators;
+use crate::registry;
+
+// The number of inputs generated for each size
+const SEEDS: u64 = 5;
+
+// Checks that a puzzle gives the same answers as its reference solution on generated input of each
+// of the given sizes
+pub fn check_against_reference<F>(day: u8, sizes: &[usize], reference: F)
+where
+    F: Fn(&str) -> [String; 2],
+{
+    for &size in sizes {
+        for seed in 0..SEEDS {
+            let input =
+                generators::generate(day, Some(size), &mut StdRng::seed_from_u64(seed)).unwrap();
+            let puzzle = registry::create_puzzle(day, &input).unwrap();
+            let answers = [puzzle.solve_part_1(), puzzle.solve_part_2()];
+            for (part, (answer, expected)) in answers.iter().zip(reference(&input)).enumerate() {
+                assert_eq!(
+                    *answer,
+                    expected,
+                    "Day {} part {} disagrees with the reference solution for the input from \
+                     'generate --day {} --size {} --seed {}'",
+                    day,
+                    part + 1,
+                    day,
+                    size,
+                    seed
+                );
+            }
+        }
+    }
+}
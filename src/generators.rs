@@ -0,0 +1,759 @@
+use std::collections::HashSet;
+
+use rand::seq::SliceRandom;
+use rand::{Rng, RngCore};
+
+use crate::grid::Grid;
+use crate::letter_ocr::{self, FontSize};
+
+// Makes random puzzle input for a day. What the size means differs from day to day.
+struct Generator {
+    day: u8,
+
+    // What the size counts
+    size: &'static str,
+
+    // The size of the real puzzle input (roughly)
+    default_size: usize,
+
+    // The smallest size that gives valid input
+    min_size: usize,
+
+    generate: fn(usize, &mut dyn RngCore) -> String,
+}
+
+const GENERATORS: [Generator; 17] = [
+    Generator {
+        day: 1,
+        size: "depths",
+        default_size: 2000,
+        min_size: 1,
+        generate: depths,
+    },
+    Generator {
+        day: 2,
+        size: "instructions",
+        default_size: 1000,
+        min_size: 1,
+        generate: instructions,
+    },
+    Generator {
+        day: 3,
+        size: "binary numbers",
+        default_size: 1000,
+        min_size: 1,
+        generate: binary_numbers,
+    },
+    Generator {
+        day: 4,
+        size: "bingo boards",
+        default_size: 100,
+        min_size: 1,
+        generate: bingo,
+    },
+    Generator {
+        day: 5,
+        size: "lines of vents",
+        default_size: 500,
+        min_size: 1,
+        generate: vents,
+    },
+    Generator {
+        day: 6,
+        size: "lanternfish",
+        default_size: 300,
+        min_size: 1,
+        generate: lanternfish,
+    },
+    Generator {
+        day: 7,
+        size: "crabs",
+        default_size: 1000,
+        min_size: 1,
+        generate: crabs,
+    },
+    Generator {
+        day: 8,
+        size: "displays",
+        default_size: 200,
+        min_size: 1,
+        generate: displays,
+    },
+    Generator {
+        day: 9,
+        size: "rows and columns of the heightmap",
+        default_size: 100,
+        min_size: 1,
+        generate: heightmap,
+    },
+    Generator {
+        day: 10,
+        size: "lines of brackets",
+        default_size: 100,
+        min_size: 1,
+        generate: brackets,
+    },
+    Generator {
+        day: 11,
+        size: "rows and columns of octopuses",
+        default_size: 10,
+        min_size: 1,
+        generate: octopuses,
+    },
+    Generator {
+        day: 12,
+        size: "caves",
+        default_size: 12,
+        min_size: 2,
+        generate: caves,
+    },
+    Generator {
+        day: 13,
+        size: "letters of the code",
+        default_size: 8,
+        min_size: 1,
+        generate: manual,
+    },
+    Generator {
+        day: 14,
+        size: "elements in the template",
+        default_size: 20,
+        min_size: 2,
+        generate: polymer,
+    },
+    Generator {
+        day: 15,
+        size: "rows and columns of the cave",
+        default_size: 100,
+        min_size: 1,
+        generate: chitons,
+    },
+    Generator {
+        day: 16,
+        size: "packets",
+        default_size: 60,
+        min_size: 1,
+        generate: transmission,
+    },
+    Generator {
+        day: 17,
+        size: "distance to the target area",
+        default_size: 100,
+        min_size: 10,
+        generate: target_area,
+    },
+];
+
+fn generator(day: u8) -> Result<&'static Generator> {
+    GENERATORS
+        .iter()
+        .find(|generator| generator.day == day)
+        .ok_or(format!("There is no input generator for day {}", day))
+}
+
+pub fn default_size(day: u8) -> Result<usize> {
+    Ok(generator(day)?.default_size)
+}
+
+// Generates valid puzzle input for a day, of the real input's size if no size is given
+pub fn generate(day: u8, size: Option<usize>, rng: &mut dyn RngCore) -> Result<String> {
+    let generator = generator(day)?;
+    let size = size.unwrap_or(generator.default_size);
+    if size < generator.min_size {
+        return Err(format!(
+            "The input for day {} must have at least {} {}",
+            day, generator.min_size, generator.size
+        ));
+    }
+    Ok((generator.generate)(size, rng))
+}
+
+fn lines(lines: impl Iterator<Item = String>) -> String {
+    lines.map(|line| line + "\n").collect()
+}
+
+fn comma_separated<T: ToString>(values: impl Iterator<Item = T>) -> String {
+    values
+        .map(|value| value.to_string())
+        .collect::<Vec<String>>()
+        .join(",")
+}
+
+fn digit_grid(size: usize, digits: std::ops::RangeInclusive<u32>, rng: &mut dyn RngCore) -> String {
+    lines((0..size).map(|_| {
+        (0..size)
+            .map(|_| char::from_digit(rng.gen_range(digits.clone()), 10).unwrap())
+            .collect()
+    }))
+}
+
+// A sonar sweep that drifts up and down
+fn depths(size: usize, rng: &mut dyn RngCore) -> String {
+    let mut depth: u32 = rng.gen_range(100..200);
+    lines((0..size).map(|_| {
+        depth = depth.saturating_add_signed(rng.gen_range(-10..=20));
+        depth.to_string()
+    }))
+}
+
+// Instructions that never take the submarine above the surface
+fn instructions(size: usize, rng: &mut dyn RngCore) -> String {
+    let mut depth = 0;
+    lines((0..size).map(|_| {
+        let value = rng.gen_range(1..10);
+        let command = match ["forward", "down", "up"].choose(rng).unwrap() {
+            &"up" if value > depth => "down",
+            command => command,
+        };
+        match command {
+            "down" => depth += value,
+            "up" => depth -= value,
+            _ => {}
+        }
+        format!("{} {}", command, value)
+    }))
+}
+
+// Distinct binary numbers, wide enough that there are plenty to choose from
+fn binary_numbers(size: usize, rng: &mut dyn RngCore) -> String {
+    let width = (usize::BITS - size.leading_zeros() + 2).max(12) as usize;
+    let mut numbers = HashSet::new();
+    while numbers.len() < size {
+        numbers.insert(rng.gen_range(0..1_u64 << width));
+    }
+    lines(
+        numbers
+            .into_iter()
+            .map(|number| format!("{:0width$b}", number, width = width)),
+    )
+}
+
+// Every number from 0 to 99 is drawn, so that every board wins
+fn bingo(size: usize, rng: &mut dyn RngCore) -> String {
+    let mut numbers = (0..100).collect::<Vec<u32>>();
+    numbers.shuffle(rng);
+    let mut input = comma_separated(numbers.iter()) + "\n";
+    for _ in 0..size {
+        input.push('\n');
+        let board = numbers.choose_multiple(rng, 25).collect::<Vec<&u32>>();
+        for row in board.chunks(5) {
+            let row = row
+                .iter()
+                .map(|number| format!("{:2}", number))
+                .collect::<Vec<String>>();
+            input += &(row.join(" ") + "\n");
+        }
+    }
+    input
+}
+
+// Horizontal, vertical and diagonal lines of vents on the 1000x1000 ocean floor
+fn vents(size: usize, rng: &mut dyn RngCore) -> String {
+    lines((0..size).map(|_| {
+        let (x1, y1) = (rng.gen_range(0..1000), rng.gen_range(0..1000));
+        let length = rng.gen_range(0..1000);
+        let (dx, dy) = [(1, 0), (0, 1), (1, 1), (1, -1)].choose(rng).unwrap();
+        let steps = (0..length)
+            .take_while(|&i| {
+                let (x, y) = (x1 + dx * i, y1 + dy * i);
+                (0..1000).contains(&x) && (0..1000).contains(&y)
+            })
+            .last()
+            .unwrap_or(0);
+        format!("{},{} -> {},{}", x1, y1, x1 + dx * steps, y1 + dy * steps)
+    }))
+}
+
+fn lanternfish(size: usize, rng: &mut dyn RngCore) -> String {
+    comma_separated((0..size).map(|_| rng.gen_range(1..=5))) + "\n"
+}
+
+fn crabs(size: usize, rng: &mut dyn RngCore) -> String {
+    comma_separated((0..size).map(|_| rng.gen_range(0..2000))) + "\n"
+}
+
+// The segments that are lit for each digit on a correctly wired display
+const DIGIT_SEGMENTS: [&str; 10] = [
+    "abcefg", "cf", "acdeg", "acdfg", "bcdf", "abdfg", "abdefg", "acf", "abcdefg", "abcdfg",
+];
+
+// Displays whose wires are randomly mixed up
+fn displays(size: usize, rng: &mut dyn RngCore) -> String {
+    lines((0..size).map(|_| {
+        let mut wiring = "abcdefg".chars().collect::<Vec<char>>();
+        wiring.shuffle(rng);
+        let output_digits = (0..4).map(|_| rng.gen_range(0..10)).collect::<Vec<usize>>();
+        let mut scramble = |segments: &str| {
+            let mut wires = segments
+                .chars()
+                .map(|segment| wiring[(segment as u8 - b'a') as usize])
+                .collect::<Vec<char>>();
+            wires.shuffle(rng);
+            wires.into_iter().collect::<String>()
+        };
+        let mut patterns = DIGIT_SEGMENTS
+            .iter()
+            .map(|s| scramble(s))
+            .collect::<Vec<_>>();
+        let outputs = output_digits
+            .iter()
+            .map(|&digit| scramble(DIGIT_SEGMENTS[digit]))
+            .collect::<Vec<_>>();
+        patterns.shuffle(rng);
+        format!("{} | {}", patterns.join(" "), outputs.join(" "))
+    }))
+}
+
+fn heightmap(size: usize, rng: &mut dyn RngCore) -> String {
+    digit_grid(size, 0..=9, rng)
+}
+
+// Lines of brackets that are either corrupted or incomplete. There is an odd number of incomplete
+// lines, so that they have a middle score.
+fn brackets(size: usize, rng: &mut dyn RngCore) -> String {
+    let incomplete = (size / 2) | 1;
+    let mut bracket_lines = (0..size)
+        .map(|i| bracket_line(i >= incomplete, rng))
+        .collect::<Vec<String>>();
+    bracket_lines.shuffle(rng);
+    lines(bracket_lines.into_iter())
+}
+
+fn bracket_line(corrupted: bool, rng: &mut dyn RngCore) -> String {
+    const PAIRS: [(char, char); 4] = [('(', ')'), ('[', ']'), ('{', '}'), ('<', '>')];
+    // Deeper nesting would overflow the completion score
+    const MAX_DEPTH: usize = 20;
+    let length = rng.gen_range(20..110);
+    let mut line = String::new();
+    let mut stack = Vec::new();
+    while line.len() < length || stack.is_empty() {
+        if stack.is_empty() || (stack.len() < MAX_DEPTH && rng.gen_bool(0.55)) {
+            let &(open, close) = PAIRS.choose(rng).unwrap();
+            line.push(open);
+            stack.push(close);
+        } else {
+            line.push(stack.pop().unwrap());
+        }
+    }
+    if corrupted {
+        let expected = stack.pop().unwrap();
+        let (_, wrong) = PAIRS
+            .iter()
+            .filter(|&&(_, close)| close != expected)
+            .copied()
+            .collect::<Vec<(char, char)>>()
+            .choose(rng)
+            .copied()
+            .unwrap();
+        line.push(wrong);
+    }
+    line
+}
+
+// A grid of octopuses that all flash at once, which random grids rarely ever do. Every octopus
+// starts at the same energy level, or one below it next to an octopus at that level, so the first
+// ones to flash set off all of the others in the same step.
+fn octopuses(size: usize, rng: &mut dyn RngCore) -> String {
+    let energy = rng.gen_range(1..=9);
+    let mut below = Grid::from_fn(size, size, |_, _| rng.gen_bool(0.5));
+    for (x, y) in below.positions() {
+        // Octopuses are only ever raised to the level, so those that are already at it stay there
+        if below.neighbours_8(x, y).all(|(x, y)| below[(x, y)]) {
+            below[(x, y)] = false;
+        }
+    }
+    lines(below.rows().map(|row| {
+        row.iter()
+            .map(|&below| char::from_digit(energy - below as u32, 10).unwrap())
+            .collect()
+    }))
+}
+
+// A cave system with the given number of caves. Big caves are never connected to each other,
+// since there would be infinitely many paths otherwise.
+fn caves(size: usize, rng: &mut dyn RngCore) -> String {
+    // Names are long enough that there are at least twice as many as there are caves, so that
+    // picking unused ones at random is quick
+    let length = (2..)
+        .find(|&length| 26_f64.powi(length) >= 2.0 * size as f64)
+        .unwrap();
+    let mut names = vec!["start".to_string(), "end".to_string()];
+    let mut used = names.iter().cloned().collect::<HashSet<String>>();
+    let mut big = vec![false, false];
+    while names.len() < size {
+        let is_big = rng.gen_bool(0.25);
+        let name = (0..length)
+            .map(|_| {
+                let letter = rng.gen_range(b'a'..=b'z') as char;
+                if is_big {
+                    letter.to_ascii_uppercase()
+                } else {
+                    letter
+                }
+            })
+            .collect::<String>();
+        if used.insert(name.clone()) {
+            names.push(name);
+            big.push(is_big);
+        }
+    }
+    // Passages go both ways, so each is stored with the lower cave first to avoid duplicates
+    let passage = |a: usize, b: usize| (a.min(b), a.max(b));
+    let mut edges = HashSet::new();
+    // A chain from the start to the end through every cave, so that they are all reachable. A big
+    // cave that would follow another one is reached from the last small cave instead.
+    let mut order = (2..size).collect::<Vec<usize>>();
+    order.shuffle(rng);
+    let mut previous = 0;
+    let mut last_small = 0;
+    for &cave in order.iter().chain([1].iter()) {
+        let from = if big[previous] && big[cave] {
+            last_small
+        } else {
+            previous
+        };
+        edges.insert(passage(from, cave));
+        previous = cave;
+        if !big[cave] {
+            last_small = cave;
+        }
+    }
+    for _ in 0..size {
+        let a = rng.gen_range(0..size);
+        let b = rng.gen_range(0..size);
+        if a != b && !(big[a] && big[b]) {
+            edges.insert(passage(a, b));
+        }
+    }
+    let mut edges = edges.into_iter().collect::<Vec<(usize, usize)>>();
+    edges.sort();
+    edges.shuffle(rng);
+    lines(edges.into_iter().map(|(a, b)| {
+        let (a, b) = if rng.gen_bool(0.5) { (a, b) } else { (b, a) };
+        format!("{}-{}", names[a], names[b])
+    }))
+}
+
+// A transparent sheet that folds up into a code of random letters
+fn manual(size: usize, rng: &mut dyn RngCore) -> String {
+    let code = random_code(size, rng);
+    manual_with_code(&code, rng)
+}
+
+fn random_code(size: usize, rng: &mut dyn RngCore) -> String {
+    (0..size)
+        .map(|_| rng.gen_range(b'A'..=b'Z') as char)
+        .collect()
+}
+
+fn manual_with_code(code: &str, rng: &mut dyn RngCore) -> String {
+    let image = letter_ocr::render_text(code, FontSize::Small).unwrap();
+    let (mut width, mut height) = (image[0].len(), image.len());
+    // The folds are made from the largest sheet to the smallest, so they are found from the
+    // smallest to the largest
+    let mut folds = Vec::new();
+    for _ in 0..2 {
+        folds.push(('y', height));
+        height = 2 * height + 1;
+        folds.push(('x', width));
+        width = 2 * width + 1;
+    }
+    let mut points = HashSet::new();
+    for (y, row) in image.iter().enumerate() {
+        for (x, _) in row.iter().enumerate().filter(|(_, &on)| on) {
+            // Some dots end up on top of each other after folding
+            for _ in 0..rng.gen_range(1..=2) {
+                let (mut x, mut y) = (x, y);
+                for &(axis, location) in &folds {
+                    if rng.gen_bool(0.5) {
+                        match axis {
+                            'x' => x = 2 * location - x,
+                            _ => y = 2 * location - y,
+                        }
+                    }
+                }
+                points.insert((x, y));
+            }
+        }
+    }
+    let mut points = points.into_iter().collect::<Vec<(usize, usize)>>();
+    points.sort();
+    points.shuffle(rng);
+    let mut input = lines(points.iter().map(|(x, y)| format!("{},{}", x, y))) + "\n";
+    for (axis, location) in folds.iter().rev() {
+        input += &format!("fold along {}={}\n", axis, location);
+    }
+    input
+}
+
+// A polymer template made of ten elements, with an insertion rule for every pair of them
+fn polymer(size: usize, rng: &mut dyn RngCore) -> String {
+    let mut elements = ('A'..='Z').collect::<Vec<char>>();
+    elements.shuffle(rng);
+    elements.truncate(10);
+    let template = (0..size)
+        .map(|_| *elements.choose(rng).unwrap())
+        .collect::<String>();
+    let mut rules = Vec::new();
+    for &a in &elements {
+        for &b in &elements {
+            rules.push(format!("{}{} -> {}", a, b, elements.choose(rng).unwrap()));
+        }
+    }
+    format!("{}\n\n{}", template, lines(rules.into_iter()))
+}
+
+fn chitons(size: usize, rng: &mut dyn RngCore) -> String {
+    digit_grid(size, 1..=9, rng)
+}
+
+// A BITS expression
+#[derive(Debug)]
+enum Expression {
+    Literal(u64),
+    Operator(u8, Vec<Expression>),
+}
+
+impl Expression {
+    // Builds an expression with roughly the given number of packets whose value fits in a u64
+    fn random(size: usize, rng: &mut dyn RngCore) -> Expression {
+        if size <= 1 {
+            return Expression::Literal(rng.gen_range(0..100));
+        }
+        let type_id = [0, 1, 2, 3, 5, 6, 7].choose(rng).copied().unwrap();
+        let count = match type_id {
+            5..=7 => 2,
+            _ => rng.gen_range(1..=(size - 1).min(4)),
+        };
+        let sub_packets = split(size - 1, count, rng)
+            .into_iter()
+            .map(|size| Expression::random(size, rng))
+            .collect::<Vec<Expression>>();
+        let expression = Expression::Operator(type_id, sub_packets);
+        match expression.value() {
+            Some(_) => expression,
+            None => match expression {
+                // A product that overflows is turned into a minimum instead
+                Expression::Operator(_, sub_packets) => Expression::Operator(2, sub_packets),
+                literal => literal,
+            },
+        }
+    }
+
+    fn value(&self) -> Option<u64> {
+        match self {
+            Expression::Literal(value) => Some(*value),
+            Expression::Operator(type_id, sub_packets) => {
+                let values = sub_packets
+                    .iter()
+                    .map(Expression::value)
+                    .collect::<Option<Vec<u64>>>()?;
+                match type_id {
+                    0 => values
+                        .iter()
+                        .try_fold(0_u64, |sum, &value| sum.checked_add(value)),
+                    1 => values
+                        .iter()
+                        .try_fold(1_u64, |product, &value| product.checked_mul(value)),
+                    2 => values.iter().min().copied(),
+                    3 => values.iter().max().copied(),
+                    5 => Some((values[0] > values[1]) as u64),
+                    6 => Some((values[0] < values[1]) as u64),
+                    _ => Some((values[0] == values[1]) as u64),
+                }
+            }
+        }
+    }
+
+    fn encode(&self, bits: &mut Vec<bool>, rng: &mut dyn RngCore) {
+        push_bits(bits, rng.gen_range(0..8), 3);
+        match self {
+            Expression::Literal(value) => {
+                push_bits(bits, 4, 3);
+                let groups = (64 - value.leading_zeros() as usize).div_ceil(4).max(1);
+                for group in (0..groups).rev() {
+                    bits.push(group > 0);
+                    push_bits(bits, (value >> (4 * group)) & 15, 4);
+                }
+            }
+            Expression::Operator(type_id, sub_packets) => {
+                push_bits(bits, *type_id as u64, 3);
+                let mut sub_bits = Vec::new();
+                for sub_packet in sub_packets {
+                    sub_packet.encode(&mut sub_bits, rng);
+                }
+                if sub_bits.len() < 1 << 15 && rng.gen_bool(0.5) {
+                    bits.push(false);
+                    push_bits(bits, sub_bits.len() as u64, 15);
+                } else {
+                    bits.push(true);
+                    push_bits(bits, sub_packets.len() as u64, 11);
+                }
+                bits.extend(sub_bits);
+            }
+        }
+    }
+}
+
+fn push_bits(bits: &mut Vec<bool>, value: u64, count: usize) {
+    bits.extend((0..count).rev().map(|i| (value >> i) & 1 == 1));
+}
+
+// Splits a total into the given number of positive parts
+fn split(total: usize, parts: usize, rng: &mut dyn RngCore) -> Vec<usize> {
+    let mut sizes = vec![1; parts];
+    for _ in parts..total {
+        sizes[rng.gen_range(0..parts)] += 1;
+    }
+    sizes
+}
+
+fn transmission(size: usize, rng: &mut dyn RngCore) -> String {
+    encode_transmission(&Expression::random(size, rng), rng)
+}
+
+fn encode_transmission(expression: &Expression, rng: &mut dyn RngCore) -> String {
+    let mut bits = Vec::new();
+    expression.encode(&mut bits, rng);
+    let hex = bits
+        .chunks(4)
+        .map(|chunk| {
+            let digit = (0..4).fold(0, |digit, i| {
+                2 * digit + *chunk.get(i).unwrap_or(&false) as u32
+            });
+            char::from_digit(digit, 16).unwrap().to_ascii_uppercase()
+        })
+        .collect::<String>();
+    hex + "\n"
+}
+
+// A target area to the right of and below the probe
+fn target_area(size: usize, rng: &mut dyn RngCore) -> String {
+    let x1 = rng.gen_range(size / 2..size) as i32;
+    let x2 = x1 + rng.gen_range(5..=5 + size / 3) as i32;
+    let y1 = -(rng.gen_range(size / 2..size) as i32);
+    let y2 = (y1 + rng.gen_range(5..=5 + size / 4) as i32).min(-1);
+    format!("target area: x={}..{}, y={}..{}\n", x1, x2, y1, y2)
+}
+
+type Result<T> = std::result::Result<T, String>;
+
+#[cfg(test)]
+mod tests {
+    use crate::generators::{
+        default_size, encode_transmission, generate, manual_with_code, Expression,
+    };
+    use crate::grid::Grid;
+    use crate::{puzzle11, registry};
+    use rand::rngs::StdRng;
+    use rand::SeedableRng;
+    use std::collections::{HashMap, HashSet};
+
+    #[test]
+    fn test_generated_input_is_valid() {
+        for day in registry::days() {
+            for seed in 0..3 {
+                let mut rng = StdRng::seed_from_u64(seed);
+                // Small inputs keep the slower days quick
+                let size = match day {
+                    12 => 8,
+                    17 => 30,
+                    _ => default_size(day).unwrap().min(20),
+                };
+                let input = generate(day, Some(size), &mut rng).unwrap();
+                let puzzle = registry::create_puzzle(day, &input)
+                    .unwrap_or_else(|e| panic!("day {} seed {}: {}\n{}", day, seed, e, input));
+                puzzle.solve_part_1();
+                puzzle.solve_part_2();
+            }
+        }
+    }
+
+    #[test]
+    fn test_same_seed_same_input() {
+        let generate_with_seed = |seed| generate(4, Some(3), &mut StdRng::seed_from_u64(seed));
+        assert_eq!(generate_with_seed(7), generate_with_seed(7));
+        assert_ne!(generate_with_seed(7), generate_with_seed(8));
+    }
+
+    #[test]
+    fn test_sizes() {
+        let mut rng = StdRng::seed_from_u64(0);
+        let input = generate(1, Some(50), &mut rng).unwrap();
+        assert_eq!(input.lines().count(), 50);
+        let input = generate(4, Some(3), &mut rng).unwrap();
+        assert_eq!(input.split("\n\n").count(), 4);
+        assert!(generate(12, Some(1), &mut rng).is_err());
+        assert!(generate(25, None, &mut rng).is_err());
+    }
+
+    #[test]
+    fn test_octopuses_synchronize() {
+        for seed in 0..20 {
+            let mut rng = StdRng::seed_from_u64(seed);
+            let input = generate(11, Some(1 + seed as usize), &mut rng).unwrap();
+            let mut grid = Grid::parse_digits(&input)
+                .unwrap()
+                .map(|&energy| energy as u8);
+            assert!(
+                (0..10).any(|_| puzzle11::do_step(&mut grid) == grid.len()),
+                "{}",
+                input
+            );
+        }
+    }
+
+    #[test]
+    fn test_caves_are_connected() {
+        for (seed, size) in [(0, 2), (1, 12), (2, 50), (3, 2000)] {
+            let mut rng = StdRng::seed_from_u64(seed);
+            let input = generate(12, Some(size), &mut rng).unwrap();
+            let mut passages = HashSet::new();
+            let mut connected = HashMap::<&str, Vec<&str>>::new();
+            for line in input.lines() {
+                let (a, b) = line.split_once('-').unwrap();
+                assert!(
+                    passages.insert((a.min(b), a.max(b))),
+                    "{} is repeated",
+                    line
+                );
+                let is_big = |cave: &str| cave.chars().all(|c| c.is_ascii_uppercase());
+                assert!(!(is_big(a) && is_big(b)), "{} joins big caves", line);
+                connected.entry(a).or_default().push(b);
+                connected.entry(b).or_default().push(a);
+            }
+            let mut reached = HashSet::from(["start"]);
+            let mut pending = vec!["start"];
+            while let Some(cave) = pending.pop() {
+                for &next in &connected[cave] {
+                    if reached.insert(next) {
+                        pending.push(next);
+                    }
+                }
+            }
+            assert_eq!(reached.len(), size);
+        }
+    }
+
+    #[test]
+    fn test_manual_folds_into_code() {
+        let mut rng = StdRng::seed_from_u64(0);
+        let input = manual_with_code("HELLOWORLD", &mut rng);
+        let puzzle = registry::create_puzzle(13, &input).unwrap();
+        assert_eq!(puzzle.solve_part_2(), "HELLOWORLD");
+    }
+
+    #[test]
+    fn test_transmission_evaluates_to_expression_value() {
+        for seed in 0..20 {
+            let mut rng = StdRng::seed_from_u64(seed);
+            let expression = Expression::random(40, &mut rng);
+            let input = encode_transmission(&expression, &mut rng);
+            let puzzle = registry::create_puzzle(16, &input).unwrap();
+            assert_eq!(
+                puzzle.solve_part_2(),
+                expression.value().unwrap().to_string()
+            );
+        }
+    }
+}
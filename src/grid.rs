@@ -0,0 +1,304 @@
+use std::fmt;
+use std::ops::{Index, IndexMut};
+
+use crate::parsing::Input;
+
+// The offsets of the neighbours that share an edge with a cell
+const NEIGHBOURS_4: [(isize, isize); 4] = [(0, -1), (-1, 0), (1, 0), (0, 1)];
+
+// The offsets of the neighbours that share an edge or a corner with a cell
+const NEIGHBOURS_8: [(isize, isize); 8] = [
+    (-1, -1),
+    (0, -1),
+    (1, -1),
+    (-1, 0),
+    (1, 0),
+    (-1, 1),
+    (0, 1),
+    (1, 1),
+];
+
+// A rectangular grid of cells, indexed by (x, y) with (0, 0) at the top left
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Grid<T> {
+    cells: Vec<T>,
+    width: usize,
+    height: usize,
+}
+
+impl<T> Grid<T> {
+    pub fn new(width: usize, height: usize, value: T) -> Grid<T>
+    where
+        T: Clone,
+    {
+        Grid {
+            cells: vec![value; width * height],
+            width,
+            height,
+        }
+    }
+
+    pub fn from_fn<F>(width: usize, height: usize, mut f: F) -> Grid<T>
+    where
+        F: FnMut(usize, usize) -> T,
+    {
+        let mut cells = Vec::with_capacity(width * height);
+        for y in 0..height {
+            for x in 0..width {
+                cells.push(f(x, y));
+            }
+        }
+        Grid {
+            cells,
+            width,
+            height,
+        }
+    }
+
+    // Builds a grid from its rows, which must not be empty and must all have the same length
+    pub fn from_rows(rows: Vec<Vec<T>>) -> Result<Grid<T>> {
+        let height = rows.len();
+        let width = rows.first().ok_or("The grid is empty")?.len();
+        if width == 0 {
+            return Err("The grid is empty".to_string());
+        }
+        if rows.iter().any(|row| row.len() != width) {
+            return Err("The rows of the grid must have the same length".to_string());
+        }
+        Ok(Grid {
+            cells: rows.into_iter().flatten().collect(),
+            width,
+            height,
+        })
+    }
+
+    // Parses a grid with one line per row and one character per cell. Errors say where in the
+    // input the problem is.
+    pub fn parse<F>(input: &str, parse_cell: F) -> Result<Grid<T>>
+    where
+        F: Fn(char) -> Result<T>,
+    {
+        let input = Input::new(input);
+        let mut rows: Vec<Vec<T>> = Vec::new();
+        for line in input.lines() {
+            let row = line
+                .char_indices()
+                .map(|(i, c)| {
+                    parse_cell(c).map_err(|e| input.error(&line[i..i + c.len_utf8()], e).into())
+                })
+                .collect::<Result<Vec<T>>>()?;
+            if rows.first().is_some_and(|first| first.len() != row.len()) {
+                return Err(input
+                    .error(line, "The rows of the grid must have the same length")
+                    .into());
+            }
+            rows.push(row);
+        }
+        Grid::from_rows(rows)
+    }
+
+    pub fn width(&self) -> usize {
+        self.width
+    }
+
+    pub fn height(&self) -> usize {
+        self.height
+    }
+
+    pub fn len(&self) -> usize {
+        self.cells.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.cells.is_empty()
+    }
+
+    pub fn contains(&self, x: usize, y: usize) -> bool {
+        x < self.width && y < self.height
+    }
+
+    pub fn get(&self, x: usize, y: usize) -> Option<&T> {
+        if self.contains(x, y) {
+            Some(&self.cells[y * self.width + x])
+        } else {
+            None
+        }
+    }
+
+    pub fn get_mut(&mut self, x: usize, y: usize) -> Option<&mut T> {
+        if self.contains(x, y) {
+            Some(&mut self.cells[y * self.width + x])
+        } else {
+            None
+        }
+    }
+
+    // The positions of the neighbours that share an edge with (x, y) and are inside the grid
+    pub fn neighbours_4(&self, x: usize, y: usize) -> impl Iterator<Item = (usize, usize)> + '_ {
+        self.neighbours(x, y, &NEIGHBOURS_4)
+    }
+
+    // The positions of the neighbours that share an edge or a corner with (x, y) and are inside
+    // the grid
+    pub fn neighbours_8(&self, x: usize, y: usize) -> impl Iterator<Item = (usize, usize)> + '_ {
+        self.neighbours(x, y, &NEIGHBOURS_8)
+    }
+
+    fn neighbours(
+        &self,
+        x: usize,
+        y: usize,
+        offsets: &'static [(isize, isize)],
+    ) -> impl Iterator<Item = (usize, usize)> + '_ {
+        offsets.iter().filter_map(move |&(dx, dy)| {
+            let x = x.checked_add_signed(dx)?;
+            let y = y.checked_add_signed(dy)?;
+            self.contains(x, y).then_some((x, y))
+        })
+    }
+
+    // Every position in the grid, row by row
+    pub fn positions(&self) -> impl Iterator<Item = (usize, usize)> {
+        let width = self.width;
+        (0..self.height).flat_map(move |y| (0..width).map(move |x| (x, y)))
+    }
+
+    // Every cell in the grid along with its position, row by row
+    pub fn iter(&self) -> impl Iterator<Item = ((usize, usize), &T)> {
+        self.positions().zip(&self.cells)
+    }
+
+    pub fn iter_mut(&mut self) -> impl Iterator<Item = &mut T> {
+        self.cells.iter_mut()
+    }
+
+    pub fn rows(&self) -> impl Iterator<Item = &[T]> {
+        self.cells.chunks(self.width.max(1))
+    }
+
+    pub fn column(&self, x: usize) -> impl Iterator<Item = &T> {
+        self.cells.iter().skip(x).step_by(self.width.max(1))
+    }
+
+    pub fn columns(&self) -> impl Iterator<Item = impl Iterator<Item = &T>> {
+        (0..self.width).map(move |x| self.column(x))
+    }
+
+    pub fn map<U, F>(&self, f: F) -> Grid<U>
+    where
+        F: FnMut(&T) -> U,
+    {
+        Grid {
+            cells: self.cells.iter().map(f).collect(),
+            width: self.width,
+            height: self.height,
+        }
+    }
+
+    pub fn to_rows(&self) -> Vec<Vec<T>>
+    where
+        T: Clone,
+    {
+        self.rows().map(|row| row.to_vec()).collect()
+    }
+}
+
+impl Grid<u32> {
+    // Parses a grid of single digits, such as a heightmap
+    pub fn parse_digits(input: &str) -> Result<Grid<u32>> {
+        Grid::parse(input, |c| {
+            c.to_digit(10).ok_or(format!("Invalid digit '{}'", c))
+        })
+    }
+}
+
+impl<T> Index<(usize, usize)> for Grid<T> {
+    type Output = T;
+
+    fn index(&self, (x, y): (usize, usize)) -> &T {
+        self.get(x, y)
+            .unwrap_or_else(|| panic!("({}, {}) is outside the grid", x, y))
+    }
+}
+
+impl<T> IndexMut<(usize, usize)> for Grid<T> {
+    fn index_mut(&mut self, (x, y): (usize, usize)) -> &mut T {
+        self.get_mut(x, y)
+            .unwrap_or_else(|| panic!("({}, {}) is outside the grid", x, y))
+    }
+}
+
+impl<T: fmt::Display> fmt::Display for Grid<T> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for row in self.rows() {
+            for cell in row {
+                write!(f, "{}", cell)?;
+            }
+            writeln!(f)?;
+        }
+        Ok(())
+    }
+}
+
+type Result<T> = std::result::Result<T, String>;
+
+#[cfg(test)]
+mod tests {
+    use crate::grid::Grid;
+
+    #[test]
+    fn test_parse_digits() {
+        let grid = Grid::parse_digits("123\n456\n").unwrap();
+        assert_eq!(grid.width(), 3);
+        assert_eq!(grid.height(), 2);
+        assert_eq!(grid[(2, 1)], 6);
+        assert_eq!(grid.get(3, 0), None);
+        assert_eq!(grid.get(0, 2), None);
+        assert_eq!(grid.to_string(), "123\n456\n");
+        assert_eq!(
+            Grid::parse_digits("12\r\n3x\r\n").unwrap_err(),
+            "Invalid digit 'x' at line 2, column 2"
+        );
+        assert_eq!(
+            Grid::parse_digits("12\n3").unwrap_err(),
+            "The rows of the grid must have the same length at line 2, column 1"
+        );
+        assert_eq!(Grid::parse_digits("").unwrap_err(), "The grid is empty");
+    }
+
+    #[test]
+    fn test_neighbours() {
+        let grid = Grid::new(3, 2, 0);
+        assert_eq!(
+            grid.neighbours_4(0, 0).collect::<Vec<_>>(),
+            vec![(1, 0), (0, 1)]
+        );
+        assert_eq!(
+            grid.neighbours_4(1, 1).collect::<Vec<_>>(),
+            vec![(1, 0), (0, 1), (2, 1)]
+        );
+        assert_eq!(
+            grid.neighbours_8(2, 0).collect::<Vec<_>>(),
+            vec![(1, 0), (1, 1), (2, 1)]
+        );
+        assert_eq!(grid.neighbours_8(1, 0).count(), 5);
+    }
+
+    #[test]
+    fn test_rows_and_columns() {
+        let mut grid = Grid::from_fn(3, 2, |x, y| x + 10 * y);
+        assert_eq!(
+            grid.rows().collect::<Vec<_>>(),
+            vec![&[0, 1, 2][..], &[10, 11, 12][..]]
+        );
+        assert_eq!(grid.column(1).copied().collect::<Vec<_>>(), vec![1, 11]);
+        assert_eq!(grid.columns().count(), 3);
+        grid[(0, 1)] = 5;
+        assert_eq!(grid.to_rows(), vec![vec![0, 1, 2], vec![5, 11, 12]]);
+        assert_eq!(grid.map(|&cell| cell * 2)[(2, 1)], 24);
+        assert_eq!(
+            grid.iter().find(|&(_, &cell)| cell == 11).unwrap().0,
+            (1, 1)
+        );
+    }
+}
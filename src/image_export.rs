@@ -0,0 +1,300 @@
+use std::fmt::Write;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+
+// The size of a pixel in exported SVG images
+const SVG_PIXEL_SIZE: usize = 10;
+
+// Characters for drawing graymaps as text, from black to white
+const TEXT_SHADES: [char; 5] = [' ', '░', '▒', '▓', '█'];
+
+// A picture of a puzzle's data
+#[derive(Debug, Clone, PartialEq)]
+pub enum Image {
+    // Black and white pixels, where true is black
+    Bitmap(Vec<Vec<bool>>),
+
+    // Grey pixels, where 0 is black and the largest value is white
+    Graymap(Vec<Vec<u32>>),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ImageFormat {
+    // PBM for bitmaps and PGM for graymaps
+    Pnm,
+    Svg,
+}
+
+impl FromStr for ImageFormat {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<ImageFormat> {
+        match s {
+            "pnm" => Ok(ImageFormat::Pnm),
+            "svg" => Ok(ImageFormat::Svg),
+            _ => Err(format!(
+                "Unknown image format '{}'. Expected 'pnm' or 'svg'",
+                s
+            )),
+        }
+    }
+}
+
+impl Image {
+    pub fn width(&self) -> usize {
+        match self {
+            Image::Bitmap(pixels) => pixels.first().map_or(0, |row| row.len()),
+            Image::Graymap(pixels) => pixels.first().map_or(0, |row| row.len()),
+        }
+    }
+
+    pub fn height(&self) -> usize {
+        match self {
+            Image::Bitmap(pixels) => pixels.len(),
+            Image::Graymap(pixels) => pixels.len(),
+        }
+    }
+
+    pub fn extension(&self, format: ImageFormat) -> &'static str {
+        match (format, self) {
+            (ImageFormat::Pnm, Image::Bitmap(_)) => "pbm",
+            (ImageFormat::Pnm, Image::Graymap(_)) => "pgm",
+            (ImageFormat::Svg, _) => "svg",
+        }
+    }
+
+    pub fn encode(&self, format: ImageFormat) -> String {
+        match format {
+            ImageFormat::Pnm => self.to_pnm(),
+            ImageFormat::Svg => self.to_svg(),
+        }
+    }
+
+    // Encodes the image as a plain (ASCII) PBM or PGM file
+    pub fn to_pnm(&self) -> String {
+        let mut result = String::new();
+        match self {
+            Image::Bitmap(pixels) => {
+                writeln!(result, "P1\n{} {}", self.width(), self.height()).unwrap();
+                for row in pixels {
+                    let row = row
+                        .iter()
+                        .map(|&pixel| if pixel { "1" } else { "0" })
+                        .collect::<Vec<_>>();
+                    writeln!(result, "{}", row.join(" ")).unwrap();
+                }
+            }
+            Image::Graymap(pixels) => {
+                // Readers only accept levels up to PGM_MAX, so brighter images are scaled down
+                let max = max_value(pixels);
+                let white = max.min(PGM_MAX);
+                writeln!(result, "P2\n{} {}\n{}", self.width(), self.height(), white).unwrap();
+                for row in pixels {
+                    let row = row
+                        .iter()
+                        .map(|&pixel| (pixel as u64 * white as u64 / max as u64).to_string())
+                        .collect::<Vec<_>>();
+                    writeln!(result, "{}", row.join(" ")).unwrap();
+                }
+            }
+        }
+        result
+    }
+
+    // Encodes the image as an SVG file. Only the pixels that differ from the background (white for
+    // bitmaps, black for graymaps) are drawn, so that sparse images stay small.
+    pub fn to_svg(&self) -> String {
+        let (width, height) = (self.width(), self.height());
+        let mut result = String::new();
+        writeln!(
+            result,
+            "<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"{}\" height=\"{}\" \
+             viewBox=\"0 0 {} {}\" shape-rendering=\"crispEdges\">",
+            width * SVG_PIXEL_SIZE,
+            height * SVG_PIXEL_SIZE,
+            width,
+            height
+        )
+        .unwrap();
+        let (background, pixels) = match self {
+            Image::Bitmap(pixels) => (
+                "#ffffff",
+                pixels
+                    .iter()
+                    .map(|row| {
+                        row.iter()
+                            .map(|&pixel| pixel.then(|| "#000000".to_string()))
+                            .collect::<Vec<_>>()
+                    })
+                    .collect::<Vec<_>>(),
+            ),
+            Image::Graymap(pixels) => {
+                let max = max_value(pixels);
+                (
+                    "#000000",
+                    pixels
+                        .iter()
+                        .map(|row| {
+                            row.iter()
+                                .map(|&pixel| {
+                                    let level = (pixel as u64 * 255 / max as u64) as u8;
+                                    (level > 0).then(|| format!("#{0:02x}{0:02x}{0:02x}", level))
+                                })
+                                .collect::<Vec<_>>()
+                        })
+                        .collect::<Vec<_>>(),
+                )
+            }
+        };
+        writeln!(
+            result,
+            "<rect width=\"{}\" height=\"{}\" fill=\"{}\"/>",
+            width, height, background
+        )
+        .unwrap();
+        for (y, row) in pixels.iter().enumerate() {
+            for (x, fill) in row.iter().enumerate() {
+                if let Some(fill) = fill {
+                    writeln!(
+                        result,
+                        "<rect x=\"{}\" y=\"{}\" width=\"1\" height=\"1\" fill=\"{}\"/>",
+                        x, y, fill
+                    )
+                    .unwrap();
+                }
+            }
+        }
+        result.push_str("</svg>\n");
+        result
+    }
+
+    // Draws the image as text with one character per pixel, for showing it in a terminal. Black
+    // pixels of bitmaps are drawn as '█', and graymaps are drawn in shades.
+    pub fn to_text(&self) -> String {
+        let mut result = String::new();
+        match self {
+            Image::Bitmap(pixels) => {
+                for row in pixels {
+                    result.extend(row.iter().map(|&pixel| if pixel { '█' } else { ' ' }));
+                    result.push('\n');
+                }
+            }
+            Image::Graymap(pixels) => {
+                let max = max_value(pixels) as u64;
+                let levels = TEXT_SHADES.len() as u64 - 1;
+                for row in pixels {
+                    result.extend(row.iter().map(|&pixel| {
+                        TEXT_SHADES[((pixel as u64 * levels + max / 2) / max) as usize]
+                    }));
+                    result.push('\n');
+                }
+            }
+        }
+        result
+    }
+}
+
+// The highest white level that a PGM file can have
+const PGM_MAX: u32 = 65535;
+
+// The white level of a graymap, which is at least 1 so that an all-black image is valid
+fn max_value(pixels: &[Vec<u32>]) -> u32 {
+    pixels.iter().flatten().copied().max().unwrap_or(0).max(1)
+}
+
+// Writes the named images for a day to the given directory, returning the paths that were written
+pub fn export_images(
+    day: u8,
+    images: &[(String, Image)],
+    dir: &Path,
+    format: ImageFormat,
+) -> Result<Vec<PathBuf>> {
+    fs::create_dir_all(dir)
+        .map_err(|e| format!("Failed to create directory {}: {}", dir.display(), e))?;
+    images
+        .iter()
+        .map(|(name, image)| {
+            let path = dir.join(format!(
+                "day{:02}-{}.{}",
+                day,
+                name,
+                image.extension(format)
+            ));
+            fs::write(&path, image.encode(format))
+                .map_err(|e| format!("Failed to write image {}: {}", path.display(), e))?;
+            Ok(path)
+        })
+        .collect()
+}
+
+type Result<T> = std::result::Result<T, String>;
+
+#[cfg(test)]
+mod tests {
+    use crate::image_export::{export_images, Image, ImageFormat};
+    use std::fs;
+    use tempfile::tempdir;
+
+    #[test]
+    fn test_pbm() {
+        let image = Image::Bitmap(vec![vec![true, false, true], vec![false, true, false]]);
+        assert_eq!(image.to_pnm(), "P1\n3 2\n1 0 1\n0 1 0\n");
+    }
+
+    #[test]
+    fn test_pgm() {
+        let image = Image::Graymap(vec![vec![0, 4, 9], vec![1, 2, 3]]);
+        assert_eq!(image.to_pnm(), "P2\n3 2\n9\n0 4 9\n1 2 3\n");
+        let image = Image::Graymap(vec![vec![0, 0]]);
+        assert_eq!(image.to_pnm(), "P2\n2 1\n1\n0 0\n");
+        let image = Image::Graymap(vec![vec![0, 100_000, 200_000]]);
+        assert_eq!(image.to_pnm(), "P2\n3 1\n65535\n0 32767 65535\n");
+    }
+
+    #[test]
+    fn test_text() {
+        let image = Image::Bitmap(vec![vec![true, false], vec![false, true]]);
+        assert_eq!(image.to_text(), "█ \n █\n");
+        let image = Image::Graymap(vec![vec![0, 1, 2, 3, 4], vec![8, 8, 8, 8, 8]]);
+        assert_eq!(image.to_text(), " ░░▒▒\n█████\n");
+    }
+
+    #[test]
+    fn test_svg() {
+        let image = Image::Bitmap(vec![vec![true, false], vec![false, false]]);
+        let svg = image.to_svg();
+        assert!(svg.starts_with("<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"20\""));
+        assert!(svg.contains("<rect width=\"2\" height=\"2\" fill=\"#ffffff\"/>"));
+        assert!(svg.contains("<rect x=\"0\" y=\"0\" width=\"1\" height=\"1\" fill=\"#000000\"/>"));
+        assert_eq!(svg.matches("<rect").count(), 2);
+        assert!(svg.ends_with("</svg>\n"));
+        let image = Image::Graymap(vec![vec![0, 1, 2]]);
+        let svg = image.to_svg();
+        assert!(svg.contains("<rect x=\"1\" y=\"0\" width=\"1\" height=\"1\" fill=\"#7f7f7f\"/>"));
+        assert!(svg.contains("<rect x=\"2\" y=\"0\" width=\"1\" height=\"1\" fill=\"#ffffff\"/>"));
+        assert_eq!(svg.matches("<rect").count(), 3);
+    }
+
+    #[test]
+    fn test_export_images() {
+        let dir = tempdir().unwrap();
+        let images = vec![
+            ("paper".to_string(), Image::Bitmap(vec![vec![true]])),
+            ("heights".to_string(), Image::Graymap(vec![vec![3]])),
+        ];
+        let paths = export_images(7, &images, &dir.path().join("out"), ImageFormat::Pnm).unwrap();
+        assert_eq!(
+            paths,
+            vec![
+                dir.path().join("out/day07-paper.pbm"),
+                dir.path().join("out/day07-heights.pgm")
+            ]
+        );
+        assert_eq!(fs::read_to_string(&paths[0]).unwrap(), "P1\n1 1\n1\n");
+        let paths = export_images(7, &images, dir.path(), ImageFormat::Svg).unwrap();
+        assert_eq!(paths[1], dir.path().join("day07-heights.svg"));
+        assert!("svg".parse::<ImageFormat>().is_ok());
+        assert!("png".parse::<ImageFormat>().is_err());
+    }
+}
@@ -1,213 +1,1030 @@
 use lazy_static::lazy_static;
 use std::collections::HashMap;
+use std::fmt;
 
-const LETTER_WIDTH: usize = 4;
-const LETTER_HEIGHT: usize = 6;
+// A fixed-height font. Glyphs may differ in width.
+struct Font {
+    height: usize,
+    letters: HashMap<char, Vec<Vec<bool>>>,
+}
 
 lazy_static! {
-    static ref LETTERS: HashMap<char, Vec<Vec<bool>>> = vec![
-        ('A', vec![
-            vec![false, true, true, false],
-            vec![true, false, false, true],
-            vec![true, false, false, true],
-            vec![true, true, true, true],
-            vec![true, false, false, true],
-            vec![true, false, false, true]]),
-        ('B', vec![
-            vec![true, true, true, false],
-            vec![true, false, false, true],
-            vec![true, true, true, false],
-            vec![true, false, false, true],
-            vec![true, false, false, true],
-            vec![true, true, true, false]]),
-        ('C', vec![
-            vec![false, true, true, false],
-            vec![true, false, false, true],
-            vec![true, false, false, false],
-            vec![true, false, false, false],
-            vec![true, false, false, true],
-            vec![false, true, true, false]]),
-        // Letter D is unknown
-        ('E', vec![
-            vec![true, true, true, true],
-            vec![true, false, false, false],
-            vec![true, true, true, false],
-            vec![true, false, false, false],
-            vec![true, false, false, false],
-            vec![true, true, true, true]]),
-        ('F', vec![
-            vec![true, true, true, true],
-            vec![true, false, false, false],
-            vec![true, true, true, false],
-            vec![true, false, false, false],
-            vec![true, false, false, false],
-            vec![true, false, false, false]]),
-        ('G', vec![
-            vec![false, true, true, false],
-            vec![true, false, false, true],
-            vec![true, false, false, false],
-            vec![true, false, true, true],
-            vec![true, false, false, true],
-            vec![false, true, true, true]]),
-        ('H', vec![
-            vec![true, false, false, true],
-            vec![true, false, false, true],
-            vec![true, true, true, true],
-            vec![true, false, false, true],
-            vec![true, false, false, true],
-            vec![true, false, false, true]
-        ]),
-        ('I', vec![
-            vec![false, true, true, true],
-            vec![false, false, true, false],
-            vec![false, false, true, false],
-            vec![false, false, true, false],
-            vec![false, false, true, false],
-            vec![false, true, true, true]
-        ]),
-        ('J', vec![
-            vec![false, false, true, true],
-            vec![false, false, false, true],
-            vec![false, false, false, true],
-            vec![false, false, false, true],
-            vec![true, false, false, true],
-            vec![false, true, true, false]
-        ]),
-        ('K', vec![
-            vec![true, false, false, true],
-            vec![true, false, true, false],
-            vec![true, true, false, false],
-            vec![true, false, true, false],
-            vec![true, false, true, false],
-            vec![true, false, false, true]
-        ]),
-        ('L', vec![
-            vec![true, false, false, false],
-            vec![true, false, false, false],
-            vec![true, false, false, false],
-            vec![true, false, false, false],
-            vec![true, false, false, false],
-            vec![true, true, true, true]
-        ]),
-        // Letter M is unknown
-        // Letter N is unknown
-        ('O', vec![
-            vec![false, true, true, false],
-            vec![true, false, false, true],
-            vec![true, false, false, true],
-            vec![true, false, false, true],
-            vec![true, false, false, true],
-            vec![false, true, true, false]]),
-        ('P', vec![
-            vec![true, true, true, false],
-            vec![true, false, false, true],
-            vec![true, false, false, true],
-            vec![true, true, true, false],
-            vec![true, false, false, false],
-            vec![true, false, false, false]]),
-        // Letter Q is unknown
-        ('R', vec![
-            vec![true, true, true, false],
-            vec![true, false, false, true],
-            vec![true, false, false, true],
-            vec![true, true, true, false],
-            vec![true, false, true, false],
-            vec![true, false, false, true]]),
-        ('S', vec![
-            vec![false, true, true, true],
-            vec![true, false, false, false],
-            vec![true, false, false, false],
-            vec![false, true, true, false],
-            vec![false, false, false, true],
-            vec![true, true, true, false]
-        ]),
-        // Letter T is unknown
-        ('U', vec![
-            vec![true, false, false, true],
-            vec![true, false, false, true],
-            vec![true, false, false, true],
-            vec![true, false, false, true],
-            vec![true, false, false, true],
-            vec![false, true, true, false]
-        ]),
-        // Letter V is unknown
-        // Letter W is unknown
-        // Letter X is unknown
-        ('Y', vec![
-            vec![true, false, false, false],
-            vec![true, false, false, false],
-            vec![false, true, false, true],
-            vec![false, false, true, false],
-            vec![false, false, true, false],
-            vec![false, false, true, false]]),
-        ('Z', vec![
-            vec![true, true, true, true],
-            vec![false, false, false, true],
-            vec![false, false, true, false],
-            vec![false, true, false, false],
-            vec![true, false, false, false],
-            vec![true, true, true, true]])
+    // The 4x6 font used by most puzzles (e.g. 2021 day 13)
+    static ref SMALL_FONT: Font = Font {
+        height: 6,
+        letters: vec![
+        (
+            'A',
+            vec![
+                vec![false, true, true, false],
+                vec![true, false, false, true],
+                vec![true, false, false, true],
+                vec![true, true, true, true],
+                vec![true, false, false, true],
+                vec![true, false, false, true]
+            ]
+        ),
+        (
+            'B',
+            vec![
+                vec![true, true, true, false],
+                vec![true, false, false, true],
+                vec![true, true, true, false],
+                vec![true, false, false, true],
+                vec![true, false, false, true],
+                vec![true, true, true, false]
+            ]
+        ),
+        (
+            'C',
+            vec![
+                vec![false, true, true, false],
+                vec![true, false, false, true],
+                vec![true, false, false, false],
+                vec![true, false, false, false],
+                vec![true, false, false, true],
+                vec![false, true, true, false]
+            ]
+        ),
+        (
+            'D',
+            vec![
+                vec![true, true, true, false],
+                vec![true, false, false, true],
+                vec![true, false, false, true],
+                vec![true, false, false, true],
+                vec![true, false, false, true],
+                vec![true, true, true, false]
+            ]
+        ),
+        (
+            'E',
+            vec![
+                vec![true, true, true, true],
+                vec![true, false, false, false],
+                vec![true, true, true, false],
+                vec![true, false, false, false],
+                vec![true, false, false, false],
+                vec![true, true, true, true]
+            ]
+        ),
+        (
+            'F',
+            vec![
+                vec![true, true, true, true],
+                vec![true, false, false, false],
+                vec![true, true, true, false],
+                vec![true, false, false, false],
+                vec![true, false, false, false],
+                vec![true, false, false, false]
+            ]
+        ),
+        (
+            'G',
+            vec![
+                vec![false, true, true, false],
+                vec![true, false, false, true],
+                vec![true, false, false, false],
+                vec![true, false, true, true],
+                vec![true, false, false, true],
+                vec![false, true, true, true]
+            ]
+        ),
+        (
+            'H',
+            vec![
+                vec![true, false, false, true],
+                vec![true, false, false, true],
+                vec![true, true, true, true],
+                vec![true, false, false, true],
+                vec![true, false, false, true],
+                vec![true, false, false, true]
+            ]
+        ),
+        (
+            'I',
+            vec![
+                vec![false, true, true, true],
+                vec![false, false, true, false],
+                vec![false, false, true, false],
+                vec![false, false, true, false],
+                vec![false, false, true, false],
+                vec![false, true, true, true]
+            ]
+        ),
+        (
+            'J',
+            vec![
+                vec![false, false, true, true],
+                vec![false, false, false, true],
+                vec![false, false, false, true],
+                vec![false, false, false, true],
+                vec![true, false, false, true],
+                vec![false, true, true, false]
+            ]
+        ),
+        (
+            'K',
+            vec![
+                vec![true, false, false, true],
+                vec![true, false, true, false],
+                vec![true, true, false, false],
+                vec![true, false, true, false],
+                vec![true, false, true, false],
+                vec![true, false, false, true]
+            ]
+        ),
+        (
+            'L',
+            vec![
+                vec![true, false, false, false],
+                vec![true, false, false, false],
+                vec![true, false, false, false],
+                vec![true, false, false, false],
+                vec![true, false, false, false],
+                vec![true, true, true, true]
+            ]
+        ),
+        (
+            'M',
+            vec![
+                vec![true, false, false, false, true],
+                vec![true, true, false, true, true],
+                vec![true, false, true, false, true],
+                vec![true, false, false, false, true],
+                vec![true, false, false, false, true],
+                vec![true, false, false, false, true]
+            ]
+        ),
+        (
+            'N',
+            vec![
+                vec![true, false, false, true],
+                vec![true, true, false, true],
+                vec![true, false, true, true],
+                vec![true, false, false, true],
+                vec![true, false, false, true],
+                vec![true, false, false, true]
+            ]
+        ),
+        (
+            'O',
+            vec![
+                vec![false, true, true, false],
+                vec![true, false, false, true],
+                vec![true, false, false, true],
+                vec![true, false, false, true],
+                vec![true, false, false, true],
+                vec![false, true, true, false]
+            ]
+        ),
+        (
+            'P',
+            vec![
+                vec![true, true, true, false],
+                vec![true, false, false, true],
+                vec![true, false, false, true],
+                vec![true, true, true, false],
+                vec![true, false, false, false],
+                vec![true, false, false, false]
+            ]
+        ),
+        (
+            'Q',
+            vec![
+                vec![false, true, true, false],
+                vec![true, false, false, true],
+                vec![true, false, false, true],
+                vec![true, false, false, true],
+                vec![true, false, true, false],
+                vec![false, true, false, true]
+            ]
+        ),
+        (
+            'R',
+            vec![
+                vec![true, true, true, false],
+                vec![true, false, false, true],
+                vec![true, false, false, true],
+                vec![true, true, true, false],
+                vec![true, false, true, false],
+                vec![true, false, false, true]
+            ]
+        ),
+        (
+            'S',
+            vec![
+                vec![false, true, true, true],
+                vec![true, false, false, false],
+                vec![true, false, false, false],
+                vec![false, true, true, false],
+                vec![false, false, false, true],
+                vec![true, true, true, false]
+            ]
+        ),
+        (
+            'T',
+            vec![
+                vec![true, true, true],
+                vec![false, true, false],
+                vec![false, true, false],
+                vec![false, true, false],
+                vec![false, true, false],
+                vec![false, true, false]
+            ]
+        ),
+        (
+            'U',
+            vec![
+                vec![true, false, false, true],
+                vec![true, false, false, true],
+                vec![true, false, false, true],
+                vec![true, false, false, true],
+                vec![true, false, false, true],
+                vec![false, true, true, false]
+            ]
+        ),
+        (
+            'V',
+            vec![
+                vec![true, false, false, false, true],
+                vec![true, false, false, false, true],
+                vec![true, false, false, false, true],
+                vec![false, true, false, true, false],
+                vec![false, true, false, true, false],
+                vec![false, false, true, false, false]
+            ]
+        ),
+        (
+            'W',
+            vec![
+                vec![true, false, false, false, true],
+                vec![true, false, false, false, true],
+                vec![true, false, false, false, true],
+                vec![true, false, true, false, true],
+                vec![true, true, false, true, true],
+                vec![true, false, false, false, true]
+            ]
+        ),
+        (
+            'X',
+            vec![
+                vec![true, false, false, true],
+                vec![true, false, false, true],
+                vec![false, true, true, false],
+                vec![false, true, true, false],
+                vec![true, false, false, true],
+                vec![true, false, false, true]
+            ]
+        ),
+        (
+            'Y',
+            vec![
+                vec![true, false, false, false, true],
+                vec![true, false, false, false, true],
+                vec![false, true, false, true, false],
+                vec![false, false, true, false, false],
+                vec![false, false, true, false, false],
+                vec![false, false, true, false, false]
+            ]
+        ),
+        (
+            'Z',
+            vec![
+                vec![true, true, true, true],
+                vec![false, false, false, true],
+                vec![false, false, true, false],
+                vec![false, true, false, false],
+                vec![true, false, false, false],
+                vec![true, true, true, true]
+            ]
+        )
     ]
     .into_iter()
-    .collect();
-}
-
-fn ocr_letter(
-    image: &[Vec<bool>],
-    row_offset: usize,
-    col_offset: usize,
-) -> Result<char, &'static str> {
-    for (letter, pattern) in LETTERS.iter() {
-        let mut all_match = true;
-        'outer: for (row, pattern_row) in pattern.iter().enumerate().take(LETTER_HEIGHT) {
-            for (col, point) in pattern_row.iter().enumerate().take(LETTER_WIDTH) {
-                let image_row = row + row_offset;
-                let image_col = col + col_offset;
-                if image_row >= image.len() || image_col >= image[image_row].len() {
-                    return Err("Image is too small to contain a letter");
-                }
-                if image[image_row][image_col] != *point {
-                    all_match = false;
-                    break 'outer;
-                }
+    .collect(),
+    };
+
+    // The 6x10 font used by some puzzles (e.g. 2018 day 10)
+    static ref LARGE_FONT: Font = Font {
+        height: 10,
+        letters: vec![
+        (
+            'A',
+            vec![
+                vec![false, false, true, true, false, false],
+                vec![false, true, false, false, true, false],
+                vec![true, false, false, false, false, true],
+                vec![true, false, false, false, false, true],
+                vec![true, false, false, false, false, true],
+                vec![true, true, true, true, true, true],
+                vec![true, false, false, false, false, true],
+                vec![true, false, false, false, false, true],
+                vec![true, false, false, false, false, true],
+                vec![true, false, false, false, false, true]
+            ]
+        ),
+        (
+            'B',
+            vec![
+                vec![true, true, true, true, true, false],
+                vec![true, false, false, false, false, true],
+                vec![true, false, false, false, false, true],
+                vec![true, false, false, false, false, true],
+                vec![true, true, true, true, true, false],
+                vec![true, false, false, false, false, true],
+                vec![true, false, false, false, false, true],
+                vec![true, false, false, false, false, true],
+                vec![true, false, false, false, false, true],
+                vec![true, true, true, true, true, false]
+            ]
+        ),
+        (
+            'C',
+            vec![
+                vec![false, true, true, true, true, false],
+                vec![true, false, false, false, false, true],
+                vec![true, false, false, false, false, false],
+                vec![true, false, false, false, false, false],
+                vec![true, false, false, false, false, false],
+                vec![true, false, false, false, false, false],
+                vec![true, false, false, false, false, false],
+                vec![true, false, false, false, false, false],
+                vec![true, false, false, false, false, true],
+                vec![false, true, true, true, true, false]
+            ]
+        ),
+        (
+            'E',
+            vec![
+                vec![true, true, true, true, true, true],
+                vec![true, false, false, false, false, false],
+                vec![true, false, false, false, false, false],
+                vec![true, false, false, false, false, false],
+                vec![true, true, true, true, true, false],
+                vec![true, false, false, false, false, false],
+                vec![true, false, false, false, false, false],
+                vec![true, false, false, false, false, false],
+                vec![true, false, false, false, false, false],
+                vec![true, true, true, true, true, true]
+            ]
+        ),
+        (
+            'F',
+            vec![
+                vec![true, true, true, true, true, true],
+                vec![true, false, false, false, false, false],
+                vec![true, false, false, false, false, false],
+                vec![true, false, false, false, false, false],
+                vec![true, true, true, true, true, false],
+                vec![true, false, false, false, false, false],
+                vec![true, false, false, false, false, false],
+                vec![true, false, false, false, false, false],
+                vec![true, false, false, false, false, false],
+                vec![true, false, false, false, false, false]
+            ]
+        ),
+        (
+            'G',
+            vec![
+                vec![false, true, true, true, true, false],
+                vec![true, false, false, false, false, true],
+                vec![true, false, false, false, false, false],
+                vec![true, false, false, false, false, false],
+                vec![true, false, false, false, false, false],
+                vec![true, false, false, true, true, true],
+                vec![true, false, false, false, false, true],
+                vec![true, false, false, false, false, true],
+                vec![true, false, false, false, true, true],
+                vec![false, true, true, true, false, true]
+            ]
+        ),
+        (
+            'H',
+            vec![
+                vec![true, false, false, false, false, true],
+                vec![true, false, false, false, false, true],
+                vec![true, false, false, false, false, true],
+                vec![true, false, false, false, false, true],
+                vec![true, true, true, true, true, true],
+                vec![true, false, false, false, false, true],
+                vec![true, false, false, false, false, true],
+                vec![true, false, false, false, false, true],
+                vec![true, false, false, false, false, true],
+                vec![true, false, false, false, false, true]
+            ]
+        ),
+        (
+            'J',
+            vec![
+                vec![false, false, false, true, true, true],
+                vec![false, false, false, false, true, false],
+                vec![false, false, false, false, true, false],
+                vec![false, false, false, false, true, false],
+                vec![false, false, false, false, true, false],
+                vec![false, false, false, false, true, false],
+                vec![false, false, false, false, true, false],
+                vec![true, false, false, false, true, false],
+                vec![true, false, false, false, true, false],
+                vec![false, true, true, true, false, false]
+            ]
+        ),
+        (
+            'K',
+            vec![
+                vec![true, false, false, false, false, true],
+                vec![true, false, false, false, true, false],
+                vec![true, false, false, true, false, false],
+                vec![true, false, true, false, false, false],
+                vec![true, true, false, false, false, false],
+                vec![true, true, false, false, false, false],
+                vec![true, false, true, false, false, false],
+                vec![true, false, false, true, false, false],
+                vec![true, false, false, false, true, false],
+                vec![true, false, false, false, false, true]
+            ]
+        ),
+        (
+            'L',
+            vec![
+                vec![true, false, false, false, false, false],
+                vec![true, false, false, false, false, false],
+                vec![true, false, false, false, false, false],
+                vec![true, false, false, false, false, false],
+                vec![true, false, false, false, false, false],
+                vec![true, false, false, false, false, false],
+                vec![true, false, false, false, false, false],
+                vec![true, false, false, false, false, false],
+                vec![true, false, false, false, false, false],
+                vec![true, true, true, true, true, true]
+            ]
+        ),
+        (
+            'N',
+            vec![
+                vec![true, false, false, false, false, true],
+                vec![true, true, false, false, false, true],
+                vec![true, true, false, false, false, true],
+                vec![true, false, true, false, false, true],
+                vec![true, false, true, false, false, true],
+                vec![true, false, false, true, false, true],
+                vec![true, false, false, true, false, true],
+                vec![true, false, false, false, true, true],
+                vec![true, false, false, false, true, true],
+                vec![true, false, false, false, false, true]
+            ]
+        ),
+        (
+            'P',
+            vec![
+                vec![true, true, true, true, true, false],
+                vec![true, false, false, false, false, true],
+                vec![true, false, false, false, false, true],
+                vec![true, false, false, false, false, true],
+                vec![true, true, true, true, true, false],
+                vec![true, false, false, false, false, false],
+                vec![true, false, false, false, false, false],
+                vec![true, false, false, false, false, false],
+                vec![true, false, false, false, false, false],
+                vec![true, false, false, false, false, false]
+            ]
+        ),
+        (
+            'R',
+            vec![
+                vec![true, true, true, true, true, false],
+                vec![true, false, false, false, false, true],
+                vec![true, false, false, false, false, true],
+                vec![true, false, false, false, false, true],
+                vec![true, true, true, true, true, false],
+                vec![true, false, false, true, false, false],
+                vec![true, false, false, false, true, false],
+                vec![true, false, false, false, true, false],
+                vec![true, false, false, false, false, true],
+                vec![true, false, false, false, false, true]
+            ]
+        ),
+        (
+            'X',
+            vec![
+                vec![true, false, false, false, false, true],
+                vec![true, false, false, false, false, true],
+                vec![false, true, false, false, true, false],
+                vec![false, true, false, false, true, false],
+                vec![false, false, true, true, false, false],
+                vec![false, false, true, true, false, false],
+                vec![false, true, false, false, true, false],
+                vec![false, true, false, false, true, false],
+                vec![true, false, false, false, false, true],
+                vec![true, false, false, false, false, true]
+            ]
+        ),
+        (
+            'Z',
+            vec![
+                vec![true, true, true, true, true, true],
+                vec![false, false, false, false, false, true],
+                vec![false, false, false, false, false, true],
+                vec![false, false, false, false, true, false],
+                vec![false, false, false, true, false, false],
+                vec![false, false, true, false, false, false],
+                vec![false, true, false, false, false, false],
+                vec![true, false, false, false, false, false],
+                vec![true, false, false, false, false, false],
+                vec![true, true, true, true, true, true]
+            ]
+        )
+    ]
+    .into_iter()
+    .collect(),
+    };
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum FontSize {
+    // The 4x6 font
+    Small,
+
+    // The 6x10 font
+    Large,
+}
+
+fn font(size: FontSize) -> &'static Font {
+    match size {
+        FontSize::Small => &SMALL_FONT,
+        FontSize::Large => &LARGE_FONT,
+    }
+}
+
+// Returns the font that the text in the image is written in, based on the height of the image
+fn detect_font(image: &[Vec<bool>]) -> Option<&'static Font> {
+    [FontSize::Small, FontSize::Large]
+        .into_iter()
+        .map(font)
+        .find(|font| font.height == image.len())
+}
+
+// The lowest confidence at which a letter is accepted
+const MIN_CONFIDENCE: f64 = 0.9;
+
+// How many more wrong pixels the next nearest letter must have than the nearest one, so that a glyph
+// halfway between two letters (e.g. an O with a corner filled in, which is as close to a D) is
+// left unrecognised rather than read as whichever letter comes first
+const MIN_MARGIN: usize = 2;
+
+// A letter recognised in an image, along with the fraction of its pixels that match the glyph
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct LetterMatch {
+    pub letter: char,
+    pub confidence: f64,
+}
+
+// A glyph found in an image, and the letter it was recognised as (if any)
+#[derive(Debug, Clone, PartialEq)]
+pub struct Glyph {
+    // The position of the top left corner of the glyph in the image
+    pub row: usize,
+    pub col: usize,
+
+    pub image: Vec<Vec<bool>>,
+    pub letter: Option<LetterMatch>,
+}
+
+// The glyphs found in an image, from left to right
+#[derive(Debug, Clone, PartialEq)]
+pub struct OcrResult {
+    pub glyphs: Vec<Glyph>,
+}
+
+impl OcrResult {
+    // Whether the image contains text and every glyph in it was recognised
+    pub fn is_complete(&self) -> bool {
+        !self.glyphs.is_empty() && self.glyphs.iter().all(|glyph| glyph.letter.is_some())
+    }
+
+    // The recognised text, with '?' in place of each unrecognised glyph
+    pub fn text(&self) -> String {
+        self.glyphs
+            .iter()
+            .map(|glyph| glyph.letter.map_or('?', |letter| letter.letter))
+            .collect()
+    }
+
+    pub fn unrecognized(&self) -> impl Iterator<Item = &Glyph> {
+        self.glyphs.iter().filter(|glyph| glyph.letter.is_none())
+    }
+
+    // Draws each unrecognised glyph along with its position, so that it can be added to the font
+    pub fn render_unrecognized(&self) -> String {
+        self.unrecognized()
+            .map(|glyph| {
+                format!(
+                    "Unrecognized glyph at row {}, column {}:\n{}",
+                    glyph.row,
+                    glyph.col,
+                    draw(&glyph.image)
+                )
+            })
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+}
+
+impl fmt::Display for OcrResult {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.text())
+    }
+}
+
+// Recognises the letters in the image. Blank margins are cropped, glyphs are separated by blank
+// columns (of any width), and each glyph is matched to the nearest letter of the font by Hamming
+// distance, so that a few wrong pixels don't stop the letter being recognised. Glyphs that can't
+// be recognised (including all glyphs when no font has the height of the text), or that are about
+// as close to two letters, are kept in the result without a letter.
+pub fn ocr(image: &[Vec<bool>]) -> OcrResult {
+    let (row_offset, col_offset, image) = crop(image);
+    let font = detect_font(&image);
+    let glyphs = segment(&image)
+        .into_iter()
+        .map(|(col, glyph_image)| Glyph {
+            row: row_offset,
+            col: col_offset + col,
+            letter: font.and_then(|font| ocr_letter(font, &glyph_image)),
+            image: glyph_image,
+        })
+        .collect();
+    OcrResult { glyphs }
+}
+
+fn ocr_letter(font: &Font, image: &[Vec<bool>]) -> Option<LetterMatch> {
+    let mut matches = font
+        .letters
+        .iter()
+        .map(|(&letter, pattern)| {
+            let pattern = trim_columns(pattern);
+            let width = pattern[0].len().max(image[0].len());
+            let distance = (0..font.height)
+                .flat_map(|row| (0..width).map(move |col| (row, col)))
+                .filter(|&(row, col)| pixel(&image[row], col) != pixel(&pattern[row], col))
+                .count();
+            (distance, letter, font.height * width)
+        })
+        .collect::<Vec<_>>();
+    matches.sort_by_key(|&(distance, letter, _)| (distance, letter));
+    let (distance, letter, size) = *matches.first()?;
+    if matches
+        .get(1)
+        .is_some_and(|&(next_distance, _, _)| next_distance < distance + MIN_MARGIN)
+    {
+        return None;
+    }
+    let confidence = 1.0 - distance as f64 / size as f64;
+    if confidence < MIN_CONFIDENCE {
+        return None;
+    }
+    Some(LetterMatch { letter, confidence })
+}
+
+fn pixel(row: &[bool], col: usize) -> bool {
+    row.get(col).copied().unwrap_or(false)
+}
+
+fn is_blank_column(image: &[Vec<bool>], col: usize) -> bool {
+    image.iter().all(|row| !pixel(row, col))
+}
+
+// Removes the blank rows and columns around the text, returning the position of the text in the
+// image along with the text
+fn crop(image: &[Vec<bool>]) -> (usize, usize, Vec<Vec<bool>>) {
+    let rows = image
+        .iter()
+        .enumerate()
+        .filter(|(_, row)| row.iter().any(|&point| point))
+        .map(|(row, _)| row)
+        .collect::<Vec<_>>();
+    let (first_row, last_row) = match (rows.first(), rows.last()) {
+        (Some(&first_row), Some(&last_row)) => (first_row, last_row),
+        _ => return (0, 0, Vec::new()),
+    };
+    let image = &image[first_row..=last_row];
+    let (first_col, _) = column_bounds(image).unwrap();
+    (first_row, first_col, trim_columns(image))
+}
+
+// Returns the first and last columns of the image that aren't blank
+fn column_bounds(image: &[Vec<bool>]) -> Option<(usize, usize)> {
+    let width = image.iter().map(|row| row.len()).max().unwrap_or(0);
+    let first_col = (0..width).find(|&col| !is_blank_column(image, col))?;
+    let last_col = (0..width).rfind(|&col| !is_blank_column(image, col))?;
+    Some((first_col, last_col))
+}
+
+// Removes the blank columns on either side of the image
+fn trim_columns(image: &[Vec<bool>]) -> Vec<Vec<bool>> {
+    match column_bounds(image) {
+        Some((first_col, last_col)) => image
+            .iter()
+            .map(|row| (first_col..=last_col).map(|col| pixel(row, col)).collect())
+            .collect(),
+        None => vec![Vec::new(); image.len()],
+    }
+}
+
+// Splits the image into the runs of columns between blank columns, along with the column that
+// each run starts at
+fn segment(image: &[Vec<bool>]) -> Vec<(usize, Vec<Vec<bool>>)> {
+    let width = image.first().map_or(0, |row| row.len());
+    let mut glyphs = Vec::new();
+    let mut start = None;
+    for col in 0..=width {
+        match (start, col == width || is_blank_column(image, col)) {
+            (None, false) => start = Some(col),
+            (Some(first_col), true) => {
+                let glyph_image = image
+                    .iter()
+                    .map(|row| row[first_col..col].to_vec())
+                    .collect();
+                glyphs.push((first_col, glyph_image));
+                start = None;
             }
-        }
-        if all_match {
-            return Ok(*letter);
+            _ => (),
         }
     }
-    Err("No match for letter OCR")
+    glyphs
+}
+
+fn draw(image: &[Vec<bool>]) -> String {
+    image
+        .iter()
+        .map(|row| {
+            row.iter()
+                .map(|&point| if point { '▌' } else { ' ' })
+                .collect::<String>()
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
 }
 
+// Returns the text in the image, or a drawing of the image if it can't be read entirely
 pub fn ocr_image(image: &[Vec<bool>]) -> String {
-    let mut success = true;
-    let mut result = String::new();
-    let mut col = 0;
-    while col <= image[0].len() - LETTER_WIDTH {
-        match ocr_letter(image, 0, col) {
-            Ok(letter) => result.push(letter),
-            Err(_) => {
-                success = false;
-                break;
+    let result = ocr(image);
+    if result.is_complete() {
+        return result.to_string();
+    }
+    format!("\n{}", draw(image))
+}
+
+// Draws the text in the given font, with one blank column between letters. This is the inverse of
+// `ocr_image`.
+pub fn render_text(text: &str, size: FontSize) -> Result<Vec<Vec<bool>>, String> {
+    let font = font(size);
+    let mut image = vec![Vec::new(); font.height];
+    for (i, letter) in text.chars().enumerate() {
+        let pattern = font
+            .letters
+            .get(&letter)
+            .ok_or(format!("The font has no glyph for '{}'", letter))?;
+        for (image_row, pattern_row) in image.iter_mut().zip(pattern) {
+            if i > 0 {
+                image_row.push(false);
+            }
+            image_row.extend(pattern_row);
+        }
+    }
+    Ok(image)
+}
+
+// Converts ASCII art (such as '#' and '.', or '█' and ' ') into an image. Every character must be
+// either an on or an off character. Short lines are padded with off pixels, since trailing spaces
+// are often stripped.
+pub fn parse_ascii_art(text: &str, on: &str, off: &str) -> Result<Vec<Vec<bool>>, String> {
+    let mut image = text
+        .lines()
+        .enumerate()
+        .map(|(row, line)| {
+            line.chars()
+                .enumerate()
+                .map(|(col, c)| match (on.contains(c), off.contains(c)) {
+                    (true, _) => Ok(true),
+                    (false, true) => Ok(false),
+                    (false, false) => Err(format!(
+                        "Unexpected character '{}' at line {}, column {}",
+                        c,
+                        row + 1,
+                        col + 1
+                    )),
+                })
+                .collect::<Result<Vec<_>, _>>()
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+    let width = image.iter().map(|row| row.len()).max().unwrap_or(0);
+    for row in image.iter_mut() {
+        row.resize(width, false);
+    }
+    Ok(image)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::letter_ocr::{
+        ocr, ocr_image, parse_ascii_art, render_text, Font, FontSize, LARGE_FONT, SMALL_FONT,
+    };
+
+    // Lays out the glyphs for the given letters with one blank column between them
+    fn image_of(font: &Font, letters: &str) -> Vec<Vec<bool>> {
+        let mut image = vec![Vec::new(); font.height];
+        for (i, letter) in letters.chars().enumerate() {
+            for (row, pattern_row) in font.letters[&letter].iter().enumerate() {
+                if i > 0 {
+                    image[row].push(false);
+                }
+                image[row].extend(pattern_row);
             }
         }
-        col += 5;
-    }
-    if success {
-        return result;
-    }
-    let mut result = String::new();
-    result.push('\n');
-    for row in 0..image.len() {
-        for col in 0..image[0].len() {
-            if image[row][col] {
-                result.push('▌');
-            } else {
-                result.push(' ');
+        image
+    }
+
+    #[test]
+    fn test_glyphs_have_the_font_height() {
+        for font in [&*SMALL_FONT, &*LARGE_FONT] {
+            for pattern in font.letters.values() {
+                assert_eq!(pattern.len(), font.height);
+                assert!(pattern.iter().all(|row| row.len() == pattern[0].len()));
+            }
+        }
+    }
+
+    #[test]
+    fn test_every_letter() {
+        for font in [&*SMALL_FONT, &*LARGE_FONT] {
+            for &letter in font.letters.keys() {
+                assert_eq!(
+                    ocr_image(&image_of(font, &letter.to_string())),
+                    letter.to_string()
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn test_alphabet() {
+        let alphabet = "ABCDEFGHIJKLMNOPQRSTUVWXYZ";
+        assert_eq!(SMALL_FONT.letters.len(), alphabet.len());
+        assert_eq!(ocr_image(&image_of(&SMALL_FONT, alphabet)), alphabet);
+        let alphabet = "ABCEFGHJKLNPRXZ";
+        assert_eq!(LARGE_FONT.letters.len(), alphabet.len());
+        assert_eq!(ocr_image(&image_of(&LARGE_FONT, alphabet)), alphabet);
+    }
+
+    #[test]
+    fn test_variable_width_letters() {
+        assert_eq!(ocr_image(&image_of(&SMALL_FONT, "TWIN")), "TWIN");
+        assert_eq!(ocr_image(&image_of(&SMALL_FONT, "MAXY")), "MAXY");
+        assert_eq!(ocr_image(&image_of(&SMALL_FONT, "VTQD")), "VTQD");
+    }
+
+    #[test]
+    fn test_large_font() {
+        assert_eq!(ocr_image(&image_of(&LARGE_FONT, "HJNXZ")), "HJNXZ");
+        assert_eq!(ocr_image(&image_of(&LARGE_FONT, "ZAEHGCJE")), "ZAEHGCJE");
+    }
+
+    #[test]
+    fn test_margins_and_spacing() {
+        let image = image_of(&SMALL_FONT, "HI");
+        let mut padded = vec![vec![false; 14]; 9];
+        for (row, image_row) in image.iter().enumerate() {
+            for (col, &point) in image_row.iter().enumerate() {
+                // Widen the gap between the letters to three columns
+                let col = if col > 4 { col + 2 } else { col };
+                padded[row + 2][col + 3] = point;
             }
         }
-        result.push('\n');
+        assert_eq!(ocr_image(&padded), "HI");
+    }
+
+    #[test]
+    fn test_wrong_pixels() {
+        let mut image = image_of(&SMALL_FONT, "AB");
+        image[0][0] = true;
+        image[3][8] = false;
+        let result = ocr(&image);
+        assert!(result.is_complete());
+        assert_eq!(result.to_string(), "AB");
+        for glyph in result.glyphs {
+            assert_eq!(glyph.letter.unwrap().confidence, 1.0 - 1.0 / 24.0);
+        }
+        assert!(ocr(&image_of(&SMALL_FONT, "AB"))
+            .glyphs
+            .iter()
+            .all(|glyph| glyph.letter.unwrap().confidence == 1.0));
+    }
+
+    #[test]
+    fn test_ambiguous_glyph() {
+        // An O and a D only differ in their left corners
+        let mut image = image_of(&SMALL_FONT, "O");
+        image[2][1] = true;
+        assert_eq!(ocr(&image).to_string(), "O");
+        let mut image = image_of(&SMALL_FONT, "O");
+        image[0][0] = true;
+        let result = ocr(&image);
+        assert!(!result.is_complete());
+        assert_eq!(result.to_string(), "?");
+    }
+
+    #[test]
+    fn test_partial_recognition() {
+        let mut image = vec![vec![false; 21]; 8];
+        for (row, image_row) in image_of(&SMALL_FONT, "HELP").iter().enumerate() {
+            image[row + 1][1..20].copy_from_slice(image_row);
+        }
+        for row in image.iter_mut().skip(1).take(6) {
+            row[6..10].fill(true);
+        }
+        let result = ocr(&image);
+        assert!(!result.is_complete());
+        assert_eq!(result.to_string(), "H?LP");
+        let unrecognized = result.unrecognized().collect::<Vec<_>>();
+        assert_eq!(unrecognized.len(), 1);
+        assert_eq!((unrecognized[0].row, unrecognized[0].col), (1, 6));
+        assert_eq!(
+            result.render_unrecognized(),
+            "Unrecognized glyph at row 1, column 6:\n▌▌▌▌\n▌▌▌▌\n▌▌▌▌\n▌▌▌▌\n▌▌▌▌\n▌▌▌▌"
+        );
+    }
+
+    #[test]
+    fn test_render_text() {
+        for (size, alphabet) in [
+            (FontSize::Small, "ABCDEFGHIJKLMNOPQRSTUVWXYZ"),
+            (FontSize::Large, "ABCEFGHJKLNPRXZ"),
+        ] {
+            let image = render_text(alphabet, size).unwrap();
+            assert_eq!(ocr_image(&image), alphabet);
+        }
+        assert_eq!(
+            render_text("HI", FontSize::Small).unwrap(),
+            image_of(&SMALL_FONT, "HI")
+        );
+        assert!(render_text("", FontSize::Small)
+            .unwrap()
+            .iter()
+            .all(|row| row.is_empty()));
+        assert!(render_text("hi", FontSize::Small).is_err());
+        assert!(render_text("MY", FontSize::Large).is_err());
+    }
+
+    #[test]
+    fn test_parse_ascii_art() {
+        let image = parse_ascii_art("#..#\n.#\n", "#", ".").unwrap();
+        assert_eq!(
+            image,
+            vec![
+                vec![true, false, false, true],
+                vec![false, true, false, false]
+            ]
+        );
+        assert_eq!(
+            parse_ascii_art("#.x", "#", ".").unwrap_err(),
+            "Unexpected character 'x' at line 1, column 3"
+        );
+        let text = "\
+.##..###...##.
+#..#.#..#.#..#
+#..#.###..#...
+####.#..#.#...
+#..#.#..#.#..#
+#..#.###...##.";
+        assert_eq!(
+            ocr(&parse_ascii_art(text, "#", ".").unwrap()).to_string(),
+            "ABC"
+        );
+        let text = ocr_image(&render_text("HELL", FontSize::Large).unwrap()[..9]);
+        assert!(!ocr(&parse_ascii_art(&text, "▌", " ").unwrap()).is_complete());
+        let text = "█  █ ███\n█  █  █\n████  █\n█  █  █\n█  █  █\n█  █ ███";
+        assert_eq!(
+            ocr(&parse_ascii_art(text, "█", " ").unwrap()).to_string(),
+            "HI"
+        );
+    }
+
+    #[test]
+    fn test_unknown_glyph_drawn() {
+        let mut image = image_of(&SMALL_FONT, "L");
+        for row in image.iter_mut() {
+            row.fill(true);
+        }
+        assert!(!ocr(&image).is_complete());
+        assert_eq!(ocr_image(&image), "\n▌▌▌▌\n▌▌▌▌\n▌▌▌▌\n▌▌▌▌\n▌▌▌▌\n▌▌▌▌");
+    }
+
+    #[test]
+    fn test_unknown_height_drawn() {
+        let mut image = image_of(&SMALL_FONT, "L");
+        image.pop();
+        assert_eq!(ocr(&image).to_string(), "?");
+        assert_eq!(ocr_image(&image), "\n▌   \n▌   \n▌   \n▌   \n▌   ");
+        assert!(ocr(&vec![vec![false; 4]; 6]).glyphs.is_empty());
+        assert!(!ocr(&vec![vec![false; 4]; 6]).is_complete());
     }
-    result.pop();
-    result
 }
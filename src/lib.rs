@@ -0,0 +1,37 @@
+pub mod alloc_stats;
+pub mod async_puzzle_input_fetcher;
+pub mod benchmark;
+pub mod config;
+#[cfg(test)]
+mod differential;
+pub mod generators;
+pub mod grid;
+pub mod image_export;
+pub mod letter_ocr;
+pub mod parsing;
+pub mod puzzle;
+pub mod puzzle01;
+pub mod puzzle02;
+pub mod puzzle03;
+pub mod puzzle04;
+pub mod puzzle05;
+pub mod puzzle06;
+pub mod puzzle07;
+pub mod puzzle08;
+pub mod puzzle09;
+pub mod puzzle10;
+pub mod puzzle11;
+pub mod puzzle12;
+pub mod puzzle13;
+pub mod puzzle14;
+pub mod puzzle15;
+pub mod puzzle16;
+pub mod puzzle17;
+pub mod puzzle_input_cipher;
+pub mod puzzle_input_fetcher;
+pub mod registry;
+pub mod runner;
+pub mod server;
+pub mod solve_context;
+pub mod tui;
+pub mod watch;
@@ -1,68 +1,307 @@
-use crate::puzzle::AbstractPuzzle;
-use crate::puzzle01::Puzzle01;
-use crate::puzzle02::Puzzle02;
-use crate::puzzle03::Puzzle03;
-use crate::puzzle04::Puzzle04;
-use crate::puzzle05::Puzzle05;
-use crate::puzzle06::Puzzle06;
-use crate::puzzle07::Puzzle07;
-use crate::puzzle08::Puzzle08;
-use crate::puzzle09::Puzzle09;
-use crate::puzzle10::Puzzle10;
-use crate::puzzle11::Puzzle11;
-use crate::puzzle12::Puzzle12;
-use crate::puzzle13::Puzzle13;
-use crate::puzzle14::Puzzle14;
-use crate::puzzle15::Puzzle15;
-use crate::puzzle16::Puzzle16;
-use crate::puzzle17::Puzzle17;
-use crate::puzzle_input_fetcher::PuzzleInputFetcher;
-
-mod letter_ocr;
-mod puzzle;
-mod puzzle01;
-mod puzzle02;
-mod puzzle03;
-mod puzzle04;
-mod puzzle05;
-mod puzzle06;
-mod puzzle07;
-mod puzzle08;
-mod puzzle09;
-mod puzzle10;
-mod puzzle11;
-mod puzzle12;
-mod puzzle13;
-mod puzzle14;
-mod puzzle15;
-mod puzzle16;
-mod puzzle17;
-mod puzzle_input_fetcher;
+use std::io::{self, IsTerminal, Read};
+use std::path::PathBuf;
+use std::process;
+
+use clap::{ArgAction, Args, Parser, Subcommand};
+use rand::rngs::StdRng;
+use rand::SeedableRng;
+use tracing::level_filters::LevelFilter;
+use tracing_subscriber::fmt::format::FmtSpan;
+use tracing_subscriber::EnvFilter;
+
+use aoc2021::config::{check_days, parse_duration, Config, OutputFormat, SessionTokenSource};
+use aoc2021::image_export::ImageFormat;
+use aoc2021::puzzle_input_fetcher::PuzzleInputFetcher;
+use aoc2021::{generators, letter_ocr};
+use aoc2021::{runner, server, tui, watch};
+
+#[derive(Parser)]
+#[command(about = "Advent of Code 2021 solutions")]
+struct Cli {
+    #[command(flatten)]
+    config: ConfigArgs,
+
+    /// Log what is going on to standard error (-v for requests and timings, -vv for more detail)
+    #[arg(short, long, action = ArgAction::Count, global = true)]
+    verbose: u8,
+
+    /// Only solve the given day (may be repeated)
+    #[arg(short, long = "day", value_name = "DAY", global = true)]
+    days: Vec<u8>,
+
+    /// Also write pictures of the puzzle data to this directory
+    #[arg(long, value_name = "DIR")]
+    images: Option<PathBuf>,
+
+    /// Time every day instead of printing the answers, and rank the days by how much of this
+    /// total runtime (e.g. '1s') they take
+    #[arg(long, value_name = "DURATION", value_parser = parse_duration)]
+    budget: Option<std::time::Duration>,
+
+    /// The format of the pictures ('pnm' or 'svg')
+    #[arg(
+        long,
+        value_name = "FORMAT",
+        default_value = "pnm",
+        requires = "images"
+    )]
+    image_format: ImageFormat,
+
+    #[command(subcommand)]
+    command: Option<Command>,
+}
+
+// Command line settings that override the configuration files
+#[derive(Args)]
+struct ConfigArgs {
+    /// Read the configuration from this file instead of ./aoc2021.toml
+    #[arg(long, value_name = "PATH", global = true)]
+    config: Option<PathBuf>,
+
+    /// The base URL for Advent of Code (or a proxy/mirror of it)
+    #[arg(long, value_name = "URL", global = true)]
+    base_url: Option<String>,
+
+    /// The event year to fetch puzzle input for
+    #[arg(long, global = true)]
+    year: Option<u16>,
+
+    /// The directory where puzzle input is stored
+    #[arg(long, value_name = "PATH", global = true)]
+    cache_dir: Option<PathBuf>,
+
+    /// Read the session token from this file
+    #[arg(
+        long,
+        value_name = "PATH",
+        global = true,
+        conflicts_with = "session_token_env"
+    )]
+    session_token_path: Option<PathBuf>,
+
+    /// Read the session token from this environment variable
+    #[arg(long, value_name = "VAR", global = true)]
+    session_token_env: Option<String>,
+
+    /// Read the key for the encrypted local store from this file
+    #[arg(long, value_name = "PATH", global = true)]
+    cache_key_path: Option<PathBuf>,
+
+    /// How to print answers ('text' or 'json')
+    #[arg(long, value_name = "FORMAT", global = true)]
+    format: Option<OutputFormat>,
+
+    /// The timeout for requests to Advent of Code (e.g. '30s')
+    #[arg(long, value_name = "DURATION", global = true, value_parser = parse_duration)]
+    request_timeout: Option<std::time::Duration>,
+
+    /// Give up on a part of a puzzle that takes longer than this (e.g. '5s')
+    #[arg(long, value_name = "DURATION", global = true, value_parser = parse_duration)]
+    timeout: Option<std::time::Duration>,
+
+    /// Never access the network; fail if a puzzle input is not cached locally
+    #[arg(long, global = true, conflicts_with = "online")]
+    offline: bool,
+
+    /// Fetch puzzle input that is not cached locally, even if the config file sets 'offline'
+    #[arg(long, global = true)]
+    online: bool,
+}
+
+impl ConfigArgs {
+    fn load(&self) -> Result<Config, String> {
+        let mut config = Config::load(self.config.as_deref())?;
+        if let Some(base_url) = &self.base_url {
+            config.base_url = base_url.trim_end_matches('/').to_string();
+        }
+        if let Some(year) = self.year {
+            config.year = year;
+        }
+        if let Some(cache_dir) = &self.cache_dir {
+            config.cache_dir = cache_dir.clone();
+        }
+        if let Some(path) = &self.session_token_path {
+            config.session_token = SessionTokenSource::File(path.clone());
+        }
+        if let Some(var) = &self.session_token_env {
+            config.session_token = SessionTokenSource::Env(var.clone());
+        }
+        if let Some(path) = &self.cache_key_path {
+            config.cache_key_path = path.clone();
+        }
+        if let Some(format) = self.format {
+            config.output_format = format;
+        }
+        if let Some(timeout) = self.request_timeout {
+            config.request_timeout = timeout;
+        }
+        if let Some(timeout) = self.timeout {
+            config.solve_timeout = Some(timeout);
+        }
+        if let Some(offline) = self.offline() {
+            config.offline = offline;
+        }
+        Ok(config)
+    }
+
+    // Whether --offline or --online was given, as a setting that overrides the config files
+    fn offline(&self) -> Option<bool> {
+        match (self.offline, self.online) {
+            (true, _) => Some(true),
+            (_, true) => Some(false),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Subcommand)]
+enum Command {
+    /// Encrypt the plain text puzzle inputs in the local store using the cache key
+    EncryptCache,
+
+    /// List the puzzles that have been solved
+    List,
+
+    /// Start an HTTP server that solves puzzles on request
+    Serve {
+        /// The address to listen on
+        #[arg(long, default_value = "127.0.0.1:8021")]
+        address: String,
+    },
+
+    /// Print random puzzle input for the day given with --day
+    Generate {
+        /// How big the input is, e.g. the number of bingo boards for day 4 (defaults to the size
+        /// of real puzzle input)
+        #[arg(long)]
+        size: Option<usize>,
+
+        /// Seed the random number generator, to generate the same input again
+        #[arg(long)]
+        seed: Option<u64>,
+    },
+
+    /// Solve the day given with --day again whenever its input changes
+    Watch {
+        /// Also solve the day with the input in this file, watching it for changes too
+        #[arg(long, value_name = "PATH")]
+        input: Option<PathBuf>,
+    },
+
+    /// Browse the days in an interactive terminal UI, solving them and viewing their input
+    Tui,
+
+    /// Read the letters in ASCII art from standard input
+    Ocr {
+        /// The characters that are on (part of a letter)
+        #[arg(long, default_value = "#█▌")]
+        on: String,
+
+        /// The characters that are off (background)
+        #[arg(long, default_value = ". ")]
+        off: String,
+    },
+}
 
 fn main() {
-    let mut fetcher = PuzzleInputFetcher::create();
-    let puzzles: Vec<Box<dyn AbstractPuzzle>> = vec![
-        Puzzle01::create(fetcher.fetch_puzzle_input(1).unwrap()),
-        Puzzle02::create(fetcher.fetch_puzzle_input(2).unwrap()),
-        Puzzle03::create(fetcher.fetch_puzzle_input(3).unwrap()),
-        Puzzle04::create(fetcher.fetch_puzzle_input(4).unwrap()),
-        Puzzle05::create(fetcher.fetch_puzzle_input(5).unwrap()),
-        Puzzle06::create(fetcher.fetch_puzzle_input(6).unwrap()),
-        Puzzle07::create(fetcher.fetch_puzzle_input(7).unwrap()),
-        Puzzle08::create(fetcher.fetch_puzzle_input(8).unwrap()),
-        Puzzle09::create(fetcher.fetch_puzzle_input(9).unwrap()),
-        Puzzle10::create(fetcher.fetch_puzzle_input(10).unwrap()),
-        Puzzle11::create(fetcher.fetch_puzzle_input(11).unwrap()),
-        Puzzle12::create(fetcher.fetch_puzzle_input(12).unwrap()),
-        Puzzle13::create(fetcher.fetch_puzzle_input(13).unwrap()),
-        Puzzle14::create(fetcher.fetch_puzzle_input(14).unwrap()),
-        Puzzle15::create(fetcher.fetch_puzzle_input(15).unwrap()),
-        Puzzle16::create(fetcher.fetch_puzzle_input(16).unwrap()),
-        Puzzle17::create(fetcher.fetch_puzzle_input(17).unwrap()),
-    ];
-    for puzzle in puzzles.iter() {
-        let day = format!("{:02}", puzzle.get_day());
-        println!("Day {} Part 1: {}", day, puzzle.solve_part_1());
-        println!("Day {} Part 2: {}", day, puzzle.solve_part_2());
+    let cli = Cli::parse();
+    init_logging(cli.verbose);
+    let config = cli.config.load().unwrap_or_else(|e| exit_with_error(&e));
+    // Only the commands that use the puzzle input store set it up, so that the others keep working
+    // when the session token or cache key can't be read
+    let fetcher = || PuzzleInputFetcher::create_from_config(&config);
+    let result = match cli.command {
+        None => check_days(cli.days)
+            .and_then(|days| runner::select_days(&days, &config.days))
+            .and_then(|days| {
+                let mut fetcher = fetcher()?;
+                match cli.budget {
+                    Some(budget) => {
+                        runner::run_budget(&mut fetcher, &days, budget, config.output_format)?
+                    }
+                    None => runner::run(&mut fetcher, &days, &config)?,
+                }
+                match &cli.images {
+                    Some(dir) => runner::export_images(&mut fetcher, &days, dir, cli.image_format),
+                    None => Ok(()),
+                }
+            }),
+        Some(Command::EncryptCache) => fetcher()
+            .and_then(|fetcher| fetcher.encrypt_local_store())
+            .map(|count| println!("Encrypted {} puzzle inputs", count)),
+        Some(Command::List) => runner::list(config.output_format),
+        Some(Command::Serve { address }) => server::serve(
+            &address,
+            config.solve_timeout.unwrap_or(server::DEFAULT_TIMEOUT),
+        ),
+        Some(Command::Ocr { on, off }) => ocr(&on, &off),
+        Some(Command::Generate { size, seed }) => generate(&cli.days, size, seed),
+        Some(Command::Tui) => {
+            fetcher().and_then(|mut fetcher| tui::run(&mut fetcher, config.solve_timeout))
+        }
+        Some(Command::Watch { input }) => single_day(&cli.days).and_then(|day| {
+            watch::watch(&mut fetcher()?, day, input.as_deref(), config.solve_timeout)
+        }),
+    };
+    if let Err(e) = result {
+        exit_with_error(&e);
     }
 }
+
+// Logs warnings by default, and more with each -v. RUST_LOG takes precedence when it is set, e.g.
+// RUST_LOG=aoc2021::puzzle_input_fetcher=trace.
+fn init_logging(verbose: u8) {
+    let level = match verbose {
+        0 => LevelFilter::WARN,
+        1 => LevelFilter::INFO,
+        2 => LevelFilter::DEBUG,
+        _ => LevelFilter::TRACE,
+    };
+    let filter = EnvFilter::try_from_default_env()
+        .unwrap_or_else(|_| EnvFilter::new(format!("warn,aoc2021={}", level)));
+    tracing_subscriber::fmt()
+        .with_env_filter(filter)
+        .with_span_events(FmtSpan::CLOSE)
+        .with_writer(io::stderr)
+        .with_ansi(io::stderr().is_terminal())
+        .init();
+}
+
+fn ocr(on: &str, off: &str) -> Result<(), String> {
+    let mut text = String::new();
+    io::stdin()
+        .read_to_string(&mut text)
+        .map_err(|e| format!("Failed to read standard input: {}", e))?;
+    let result = letter_ocr::ocr(&letter_ocr::parse_ascii_art(&text, on, off)?);
+    if !result.is_complete() {
+        return Err(format!(
+            "Failed to read every letter (got '{}')\n{}",
+            result,
+            result.render_unrecognized()
+        ));
+    }
+    println!("{}", result);
+    Ok(())
+}
+
+fn generate(days: &[u8], size: Option<usize>, seed: Option<u64>) -> Result<(), String> {
+    let day = single_day(days)?;
+    let mut rng = match seed {
+        Some(seed) => StdRng::seed_from_u64(seed),
+        None => StdRng::from_entropy(),
+    };
+    print!("{}", generators::generate(day, size, &mut rng)?);
+    Ok(())
+}
+
+fn single_day(days: &[u8]) -> Result<u8, String> {
+    match check_days(days.to_vec())?[..] {
+        [day] => Ok(day),
+        _ => Err("Exactly one day must be given with --day".to_string()),
+    }
+}
+
+fn exit_with_error(error: &str) -> ! {
+    eprintln!("{}", error);
+    process::exit(1);
+}
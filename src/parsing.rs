@@ -0,0 +1,262 @@
+use std::fmt;
+use std::str::FromStr;
+
+use regex::{Captures, Regex};
+
+// A position in the puzzle input, counting lines and columns (in characters) from 1
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Location {
+    pub line: usize,
+    pub column: usize,
+}
+
+// An error in the puzzle input, along with where it is when that is known
+#[derive(Debug, Clone, PartialEq)]
+pub struct ParseError {
+    pub message: String,
+    pub location: Option<Location>,
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self.location {
+            Some(location) => write!(
+                f,
+                "{} at line {}, column {}",
+                self.message, location.line, location.column
+            ),
+            None => write!(f, "{}", self.message),
+        }
+    }
+}
+
+impl From<ParseError> for String {
+    fn from(error: ParseError) -> String {
+        error.to_string()
+    }
+}
+
+// Puzzle input that is being parsed. Everything handed out is a slice of the input, so that errors
+// about any part of it can say where that part is. Windows line endings and trailing whitespace
+// are ignored.
+#[derive(Clone, Copy)]
+pub struct Input<'a> {
+    text: &'a str,
+}
+
+impl<'a> Input<'a> {
+    pub fn new(text: &'a str) -> Input<'a> {
+        Input { text }
+    }
+
+    // The lines of the input without trailing whitespace, leaving out blank lines at the end
+    pub fn lines(&self) -> impl Iterator<Item = &'a str> {
+        let mut lines = self.text.lines().map(str::trim_end).collect::<Vec<&str>>();
+        while lines.last() == Some(&"") {
+            lines.pop();
+        }
+        lines.into_iter()
+    }
+
+    // The groups of lines that are separated by blank lines
+    pub fn sections(&self) -> Vec<Vec<&'a str>> {
+        let mut sections = vec![Vec::new()];
+        for line in self.lines() {
+            match sections.last_mut() {
+                Some(section) if !line.is_empty() => section.push(line),
+                Some(section) if section.is_empty() => {}
+                _ => sections.push(Vec::new()),
+            }
+        }
+        sections.retain(|section| !section.is_empty());
+        sections
+    }
+
+    fn offset(&self, part: &str) -> Option<usize> {
+        let start = self.text.as_ptr() as usize;
+        let offset = (part.as_ptr() as usize).checked_sub(start)?;
+        (offset + part.len() <= self.text.len()).then_some(offset)
+    }
+
+    // Where a slice of the input starts, or None if it isn't part of the input
+    pub fn locate(&self, part: &str) -> Option<Location> {
+        let before = &self.text[..self.offset(part)?];
+        let line_start = before.rfind('\n').map_or(0, |i| i + 1);
+        Some(Location {
+            line: before.matches('\n').count() + 1,
+            column: before[line_start..].chars().count() + 1,
+        })
+    }
+
+    // An error about a slice of the input
+    pub fn error(&self, part: &str, message: impl Into<String>) -> ParseError {
+        ParseError {
+            message: message.into(),
+            location: self.locate(part),
+        }
+    }
+
+    // Parses a token, describing it as `what` if it is invalid
+    pub fn parse<T: FromStr>(&self, token: &'a str, what: &str) -> Result<T> {
+        token
+            .parse()
+            .map_err(|_| self.error(token, format!("Invalid {} '{}'", what, token)))
+    }
+
+    // Parses every line of the input with the given function
+    pub fn parse_lines<T, F>(&self, parse_line: F) -> Result<Vec<T>>
+    where
+        F: FnMut(&'a str) -> Result<T>,
+    {
+        self.lines().map(parse_line).collect()
+    }
+
+    // Parses a list such as '3,4,3,1,2'
+    pub fn comma_separated<T: FromStr>(&self, list: &'a str, what: &str) -> Result<Vec<T>> {
+        list.trim()
+            .split(',')
+            .map(|token| self.parse(token.trim(), what))
+            .collect()
+    }
+
+    // Splits a line in two around the separator, describing the line as `what` if it has no
+    // separator or either side is empty
+    pub fn split_pair(
+        &self,
+        line: &'a str,
+        separator: &str,
+        what: &str,
+    ) -> Result<(&'a str, &'a str)> {
+        match line.split_once(separator) {
+            Some((a, b)) if !a.trim().is_empty() && !b.trim().is_empty() => {
+                Ok((a.trim(), b.trim()))
+            }
+            _ => Err(self.error(line, format!("Invalid {} '{}'", what, line))),
+        }
+    }
+
+    // Splits a rule such as 'CH -> B' into its two sides
+    pub fn rule(&self, line: &'a str, what: &str) -> Result<(&'a str, &'a str)> {
+        self.split_pair(line, "->", what)
+    }
+
+    // Matches a regex against a slice of the input and parses its capture groups
+    pub fn captures<T: FromCaptures>(&self, re: &Regex, part: &'a str, what: &str) -> Result<T> {
+        let captures = re
+            .captures(part)
+            .ok_or_else(|| self.error(part, format!("Invalid {} '{}'", what, part.trim())))?;
+        T::from_captures(self, &captures, what)
+    }
+}
+
+// Values that can be parsed from the capture groups of a regex, in order
+pub trait FromCaptures: Sized {
+    fn from_captures<'a>(input: &Input<'a>, captures: &Captures<'a>, what: &str) -> Result<Self>;
+}
+
+macro_rules! impl_from_captures {
+    ($($t:ident $i:tt),+) => {
+        impl<$($t: FromStr),+> FromCaptures for ($($t,)+) {
+            fn from_captures<'a>(
+                input: &Input<'a>,
+                captures: &Captures<'a>,
+                what: &str,
+            ) -> Result<Self> {
+                Ok(($(capture::<$t>(input, captures, $i + 1, what)?,)+))
+            }
+        }
+    };
+}
+
+impl_from_captures!(A 0);
+impl_from_captures!(A 0, B 1);
+impl_from_captures!(A 0, B 1, C 2);
+impl_from_captures!(A 0, B 1, C 2, D 3);
+
+fn capture<'a, T: FromStr>(
+    input: &Input<'a>,
+    captures: &Captures<'a>,
+    group: usize,
+    what: &str,
+) -> Result<T> {
+    let matched = captures.get(group).map_or("", |m| m.as_str());
+    matched.parse().map_err(|_| {
+        let message = format!("Invalid {} '{}' in '{}'", what, matched, &captures[0]);
+        input.error(matched, message)
+    })
+}
+
+pub type Result<T> = std::result::Result<T, ParseError>;
+
+#[cfg(test)]
+mod tests {
+    use crate::parsing::{Input, Location};
+    use regex::Regex;
+
+    #[test]
+    fn test_lines_ignore_line_endings_and_trailing_whitespace() {
+        let input = Input::new("1 \r\n2\r\n\r\n3\t\r\n\r\n \n");
+        assert_eq!(input.lines().collect::<Vec<_>>(), vec!["1", "2", "", "3"]);
+    }
+
+    #[test]
+    fn test_sections() {
+        let input = Input::new("a\r\nb \r\n\r\n\r\nc \r\n\r\n");
+        assert_eq!(input.sections(), vec![vec!["a", "b"], vec!["c"]]);
+        assert_eq!(
+            input.locate(input.sections()[1][0]),
+            Some(Location { line: 5, column: 1 })
+        );
+        assert!(Input::new("").sections().is_empty());
+    }
+
+    #[test]
+    fn test_comma_separated() {
+        let input = Input::new("3,4, 3,1,2\n");
+        assert_eq!(
+            input.comma_separated::<u8>(input.lines().next().unwrap(), "timer"),
+            Ok(vec![3, 4, 3, 1, 2])
+        );
+        let input = Input::new("7,x\n1,2,-3");
+        let error = input
+            .comma_separated::<u32>(input.lines().nth(1).unwrap(), "number")
+            .unwrap_err();
+        assert_eq!(error.to_string(), "Invalid number '-3' at line 2, column 5");
+    }
+
+    #[test]
+    fn test_rule() {
+        let input = Input::new("NNCB\n\nCH -> B\nHH ->\n");
+        let lines = input.lines().collect::<Vec<_>>();
+        assert_eq!(input.rule(lines[2], "rule"), Ok(("CH", "B")));
+        assert_eq!(
+            input.rule(lines[3], "rule").unwrap_err().to_string(),
+            "Invalid rule 'HH ->' at line 4, column 1"
+        );
+    }
+
+    #[test]
+    fn test_captures() {
+        let re = Regex::new(r"^(\d+),(\d+) -> (\w+)$").unwrap();
+        let input = Input::new("1,2 -> a\n3,400 -> b");
+        let lines = input.lines().collect::<Vec<_>>();
+        assert_eq!(
+            input.captures::<(u8, u8, String)>(&re, lines[0], "line"),
+            Ok((1, 2, "a".to_string()))
+        );
+        assert_eq!(
+            input
+                .captures::<(u8, u8, String)>(&re, lines[1], "coordinate")
+                .unwrap_err()
+                .to_string(),
+            "Invalid coordinate '400' in '3,400 -> b' at line 2, column 3"
+        );
+        assert_eq!(
+            input
+                .captures::<(u8, u8, String)>(&re, "nope", "line")
+                .unwrap_err()
+                .to_string(),
+            "Invalid line 'nope'"
+        );
+    }
+}
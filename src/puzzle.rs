@@ -1,5 +1,157 @@
+use serde::Serialize;
+use tracing::{info_span, Span};
+
+use crate::image_export::Image;
+use crate::solve_context::SolveContext;
+
 pub trait AbstractPuzzle {
+    // The puzzle input in the form that both parts are solved from
+    type Parsed;
+
     fn get_day(&self) -> u8;
+    fn get_title(&self) -> &'static str;
+
+    // The year of the event that the puzzle is from
+    fn get_year(&self) -> u16 {
+        2021
+    }
+
+    // Short descriptions of what each part asks for
+    fn get_part_descriptions(&self) -> [&'static str; 2];
+
+    fn parse(&self, input: &str) -> Result<Self::Parsed>;
+    fn solve_part_1(&self, parsed: &Self::Parsed) -> String;
+    fn solve_part_2(&self, parsed: &Self::Parsed) -> String;
+
+    // Solves part 1, reporting progress to the context and stopping early when the context says
+    // so. Puzzles that can take a long time override these, and the rest only check the context
+    // before starting.
+    fn solve_part_1_with(&self, parsed: &Self::Parsed, ctx: &SolveContext) -> Result<String> {
+        ctx.check()?;
+        Ok(self.solve_part_1(parsed))
+    }
+
+    fn solve_part_2_with(&self, parsed: &Self::Parsed, ctx: &SolveContext) -> Result<String> {
+        ctx.check()?;
+        Ok(self.solve_part_2(parsed))
+    }
+
+    // Named pictures of the puzzle's data, for puzzles where looking at the data helps
+    fn images(&self, _parsed: &Self::Parsed) -> Vec<(String, Image)> {
+        Vec::new()
+    }
+}
+
+// Everything about a puzzle other than how to solve it
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+pub struct Metadata {
+    pub day: u8,
+    pub year: u16,
+    pub title: &'static str,
+    pub part_1_description: &'static str,
+    pub part_2_description: &'static str,
+}
+
+impl Metadata {
+    fn of<P: AbstractPuzzle>(puzzle: &P) -> Metadata {
+        let [part_1_description, part_2_description] = puzzle.get_part_descriptions();
+        Metadata {
+            day: puzzle.get_day(),
+            year: puzzle.get_year(),
+            title: puzzle.get_title(),
+            part_1_description,
+            part_2_description,
+        }
+    }
+}
+
+// A puzzle along with its parsed input, so that it can be solved without knowing the type of the
+// parsed input
+pub trait ParsedPuzzle: Send + Sync {
+    fn get_day(&self) -> u8;
+    fn metadata(&self) -> Metadata;
     fn solve_part_1(&self) -> String;
     fn solve_part_2(&self) -> String;
+    fn solve_part_1_with(&self, ctx: &SolveContext) -> Result<String>;
+    fn solve_part_2_with(&self, ctx: &SolveContext) -> Result<String>;
+    fn images(&self) -> Vec<(String, Image)>;
+}
+
+// A puzzle whose parsed input type isn't known, so that puzzles for different days can be kept
+// together
+pub trait DynamicPuzzle: Sync {
+    fn metadata(&self) -> Metadata;
+
+    // Parses the puzzle input, returning the puzzle ready to be solved
+    fn create(&self, input: &str) -> Result<Box<dyn ParsedPuzzle>>;
+}
+
+struct PuzzleWithInput<P: AbstractPuzzle> {
+    puzzle: P,
+    parsed: P::Parsed,
+}
+
+impl<P> ParsedPuzzle for PuzzleWithInput<P>
+where
+    P: AbstractPuzzle + Send + Sync,
+    P::Parsed: Send + Sync,
+{
+    fn get_day(&self) -> u8 {
+        self.puzzle.get_day()
+    }
+
+    fn metadata(&self) -> Metadata {
+        Metadata::of(&self.puzzle)
+    }
+
+    fn solve_part_1(&self) -> String {
+        self.solve_span(1)
+            .in_scope(|| self.puzzle.solve_part_1(&self.parsed))
+    }
+
+    fn solve_part_2(&self) -> String {
+        self.solve_span(2)
+            .in_scope(|| self.puzzle.solve_part_2(&self.parsed))
+    }
+
+    fn solve_part_1_with(&self, ctx: &SolveContext) -> Result<String> {
+        self.solve_span(1)
+            .in_scope(|| self.puzzle.solve_part_1_with(&self.parsed, ctx))
+    }
+
+    fn solve_part_2_with(&self, ctx: &SolveContext) -> Result<String> {
+        self.solve_span(2)
+            .in_scope(|| self.puzzle.solve_part_2_with(&self.parsed, ctx))
+    }
+
+    fn images(&self) -> Vec<(String, Image)> {
+        self.puzzle.images(&self.parsed)
+    }
+}
+
+impl<P: AbstractPuzzle> PuzzleWithInput<P> {
+    fn solve_span(&self, part: u8) -> Span {
+        info_span!("solve", day = self.puzzle.get_day(), part)
+    }
+}
+
+impl<P> DynamicPuzzle for P
+where
+    P: AbstractPuzzle + Copy + Send + Sync + 'static,
+    P::Parsed: Send + Sync + 'static,
+{
+    fn metadata(&self) -> Metadata {
+        Metadata::of(self)
+    }
+
+    fn create(&self, input: &str) -> Result<Box<dyn ParsedPuzzle>> {
+        let _span = info_span!("parse", day = self.get_day(), bytes = input.len()).entered();
+        let parsed = self.parse(input)?;
+        Ok(Box::new(PuzzleWithInput {
+            puzzle: *self,
+            parsed,
+        }))
+    }
 }
+
+pub type Result<T> = std::result::Result<T, String>;
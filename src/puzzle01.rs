@@ -1,52 +1,60 @@
-use crate::puzzle::AbstractPuzzle;
+use crate::parsing::Input;
+use crate::puzzle::{AbstractPuzzle, Result};
 
-pub struct Puzzle01 {
-    depths: Vec<u32>,
-}
+#[derive(Clone, Copy)]
+pub struct Puzzle01;
 
 impl AbstractPuzzle for Puzzle01 {
+    type Parsed = Vec<u32>;
+
     fn get_day(&self) -> u8 {
         1
     }
 
-    fn solve_part_1(&self) -> String {
-        self.count_increases(1).to_string()
+    fn get_title(&self) -> &'static str {
+        "Sonar Sweep"
     }
 
-    fn solve_part_2(&self) -> String {
-        self.count_increases(3).to_string()
+    fn get_part_descriptions(&self) -> [&'static str; 2] {
+        [
+            "Count the depth measurements that increase",
+            "Count the sums of three measurements that increase",
+        ]
     }
-}
 
-impl Puzzle01 {
-    pub fn create(input: &str) -> Box<dyn AbstractPuzzle> {
-        Box::new(Puzzle01 {
-            depths: input
-                .lines()
-                .map(|line| line.parse::<u32>().unwrap())
-                .collect(),
-        })
+    fn parse(&self, input: &str) -> Result<Vec<u32>> {
+        let input = Input::new(input);
+        Ok(input.parse_lines(|line| input.parse(line, "depth"))?)
     }
 
-    fn count_increases(&self, window_size: usize) -> u32 {
-        let mut count = 0;
-        let mut prev_sum = 0;
-        for i in 0..window_size {
-            prev_sum += self.depths[i];
-        }
-        for i in window_size..self.depths.len() {
-            let sum = prev_sum + self.depths[i] - self.depths[i - window_size];
-            if sum > prev_sum {
-                count += 1;
-            }
-            prev_sum = sum;
+    fn solve_part_1(&self, depths: &Vec<u32>) -> String {
+        count_increases(depths, 1).to_string()
+    }
+
+    fn solve_part_2(&self, depths: &Vec<u32>) -> String {
+        count_increases(depths, 3).to_string()
+    }
+}
+
+fn count_increases(depths: &[u32], window_size: usize) -> u32 {
+    let mut count = 0;
+    let mut prev_sum = 0;
+    for depth in depths.iter().take(window_size) {
+        prev_sum += depth;
+    }
+    for i in window_size..depths.len() {
+        let sum = prev_sum + depths[i] - depths[i - window_size];
+        if sum > prev_sum {
+            count += 1;
         }
-        count
+        prev_sum = sum;
     }
+    count
 }
 
 #[cfg(test)]
 mod tests {
+    use crate::puzzle::DynamicPuzzle;
     use crate::puzzle01::Puzzle01;
     use std::fs;
     use std::path::PathBuf;
@@ -56,14 +64,14 @@ mod tests {
         let input = vec![
             "199", "200", "208", "210", "200", "207", "240", "269", "260", "263",
         ];
-        let puzzle = Puzzle01::create(&input.join("\n"));
+        let puzzle = Puzzle01.create(&input.join("\n")).unwrap();
         assert_eq!(puzzle.solve_part_1(), "7");
     }
 
     #[test]
     fn test_solve_part_1() {
         let input = fs::read_to_string(PathBuf::from("resources/tests/1")).unwrap();
-        let puzzle = Puzzle01::create(&input);
+        let puzzle = Puzzle01.create(&input).unwrap();
         assert_eq!(puzzle.solve_part_1(), "1532");
     }
 
@@ -72,14 +80,14 @@ mod tests {
         let input = vec![
             "199", "200", "208", "210", "200", "207", "240", "269", "260", "263",
         ];
-        let puzzle = Puzzle01::create(&input.join("\n"));
+        let puzzle = Puzzle01.create(&input.join("\n")).unwrap();
         assert_eq!(puzzle.solve_part_2(), "5");
     }
 
     #[test]
     fn test_solve_part_2() {
         let input = fs::read_to_string(PathBuf::from("resources/tests/1")).unwrap();
-        let puzzle = Puzzle01::create(&input);
+        let puzzle = Puzzle01.create(&input).unwrap();
         assert_eq!(puzzle.solve_part_2(), "1571");
     }
 }
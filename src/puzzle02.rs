@@ -1,18 +1,42 @@
-use crate::puzzle::AbstractPuzzle;
+use crate::parsing::Input;
+use crate::puzzle::{AbstractPuzzle, Result};
 
-pub struct Puzzle02 {
-    instructions: Vec<Instruction>,
-}
+#[derive(Clone, Copy)]
+pub struct Puzzle02;
 
 impl AbstractPuzzle for Puzzle02 {
+    type Parsed = Vec<Instruction>;
+
     fn get_day(&self) -> u8 {
         2
     }
 
-    fn solve_part_1(&self) -> String {
+    fn get_title(&self) -> &'static str {
+        "Dive!"
+    }
+
+    fn get_part_descriptions(&self) -> [&'static str; 2] {
+        [
+            "Multiply the final position by the final depth",
+            "Multiply the final position by the final depth, steering with aim",
+        ]
+    }
+
+    fn parse(&self, input: &str) -> Result<Vec<Instruction>> {
+        let input = Input::new(input);
+        Ok(input.parse_lines(|line| {
+            let (command, value) = input.split_pair(line, " ", "instruction")?;
+            Ok(Instruction {
+                command: command.to_string(),
+                value: input.parse(value, "distance")?,
+            })
+        })?)
+    }
+
+    fn solve_part_1(&self, instructions: &Vec<Instruction>) -> String {
         let mut x = 0;
         let mut y = 0;
-        for instruction in &self.instructions {
+        for instruction in instructions {
             match instruction.command.as_str() {
                 "forward" => x += instruction.value,
                 "down" => y += instruction.value,
@@ -23,11 +47,11 @@ impl AbstractPuzzle for Puzzle02 {
         (x * y).to_string()
     }
 
-    fn solve_part_2(&self) -> String {
+    fn solve_part_2(&self, instructions: &Vec<Instruction>) -> String {
         let mut x = 0;
         let mut y = 0;
         let mut aim = 0;
-        for instruction in &self.instructions {
+        for instruction in instructions {
             match instruction.command.as_str() {
                 "forward" => {
                     x += instruction.value;
@@ -42,37 +66,21 @@ impl AbstractPuzzle for Puzzle02 {
     }
 }
 
-impl Puzzle02 {
-    pub fn create(input: &str) -> Box<dyn AbstractPuzzle> {
-        Box::new(Puzzle02 {
-            instructions: input
-                .lines()
-                .map(|line| {
-                    let tokens = line.split_whitespace().collect::<Vec<&str>>();
-                    Instruction {
-                        command: tokens[0].to_string(),
-                        value: tokens[1].parse::<i32>().unwrap(),
-                    }
-                })
-                .collect(),
-        })
-    }
-}
-
-struct Instruction {
+pub struct Instruction {
     command: String,
     value: i32,
 }
 
 #[cfg(test)]
 mod tests {
+    use crate::puzzle::DynamicPuzzle;
     use crate::puzzle02::Puzzle02;
     use std::fs;
     use std::path::PathBuf;
 
     #[test]
     fn test_part_1_example_1() {
-        let input = vec![
+        let input = [
             "forward 5",
             "down 5",
             "forward 8",
@@ -80,20 +88,20 @@ mod tests {
             "down 8",
             "forward 2",
         ];
-        let puzzle = Puzzle02::create(&input.join("\n"));
+        let puzzle = Puzzle02.create(&input.join("\n")).unwrap();
         assert_eq!(puzzle.solve_part_1(), "150");
     }
 
     #[test]
     fn test_solve_part_1() {
         let input = fs::read_to_string(PathBuf::from("resources/tests/2")).unwrap();
-        let puzzle = Puzzle02::create(&input);
+        let puzzle = Puzzle02.create(&input).unwrap();
         assert_eq!(puzzle.solve_part_1(), "1604850");
     }
 
     #[test]
     fn test_part_2_example_1() {
-        let input = vec![
+        let input = [
             "forward 5",
             "down 5",
             "forward 8",
@@ -101,14 +109,14 @@ mod tests {
             "down 8",
             "forward 2",
         ];
-        let puzzle = Puzzle02::create(&input.join("\n"));
+        let puzzle = Puzzle02.create(&input.join("\n")).unwrap();
         assert_eq!(puzzle.solve_part_2(), "900");
     }
 
     #[test]
     fn test_solve_part_2() {
         let input = fs::read_to_string(PathBuf::from("resources/tests/2")).unwrap();
-        let puzzle = Puzzle02::create(&input);
+        let puzzle = Puzzle02.create(&input).unwrap();
         assert_eq!(puzzle.solve_part_2(), "1685186100");
     }
 }
@@ -1,22 +1,51 @@
-use crate::puzzle::AbstractPuzzle;
+use crate::parsing::Input;
+use crate::puzzle::{AbstractPuzzle, Result};
 
-pub struct Puzzle03 {
-    report: Vec<String>,
+#[derive(Clone, Copy)]
+pub struct Puzzle03;
+
+pub struct Report {
+    numbers: Vec<String>,
     length: usize,
 }
 
 impl AbstractPuzzle for Puzzle03 {
+    type Parsed = Report;
+
     fn get_day(&self) -> u8 {
         3
     }
 
-    fn solve_part_1(&self) -> String {
+    fn get_title(&self) -> &'static str {
+        "Binary Diagnostic"
+    }
+
+    fn get_part_descriptions(&self) -> [&'static str; 2] {
+        ["Find the power consumption", "Find the life support rating"]
+    }
+
+    fn parse(&self, input: &str) -> Result<Report> {
+        let numbers = Input::new(input)
+            .lines()
+            .map(|line| line.to_string())
+            .collect::<Vec<String>>();
+        let length = numbers.first().ok_or("The report is empty")?.len();
+        if let Some(number) = numbers
+            .iter()
+            .find(|number| number.len() != length || number.contains(|c| c != '0' && c != '1'))
+        {
+            return Err(format!("Invalid binary number '{}'", number));
+        }
+        Ok(Report { numbers, length })
+    }
+
+    fn solve_part_1(&self, report: &Report) -> String {
         let mut gamma: u32 = 0;
         let mut epsilon: u32 = 0;
-        for i in 0..self.length {
+        for i in 0..report.length {
             let mut zeros = 0;
             let mut ones = 0;
-            for s in self.report.iter() {
+            for s in report.numbers.iter() {
                 if s.chars().nth(i).unwrap() == '0' {
                     zeros += 1;
                 } else {
@@ -34,17 +63,18 @@ impl AbstractPuzzle for Puzzle03 {
         (gamma * epsilon).to_string()
     }
 
-    fn solve_part_2(&self) -> String {
-        let mut report = self.report.clone();
+    fn solve_part_2(&self, report: &Report) -> String {
+        let length = report.length;
+        let mut report = report.numbers.clone();
         report.sort();
         let mut low = 0;
         let mut high = report.len();
-        for i in 0..self.length {
+        for i in 0..length {
             if high - low == 1 {
                 break;
             }
             let mid = find_mid(&report, i, low, high);
-            if high - mid >= (high - low + 1) / 2 {
+            if high - mid >= (high - low).div_ceil(2) {
                 low = mid;
             } else {
                 high = mid;
@@ -53,12 +83,16 @@ impl AbstractPuzzle for Puzzle03 {
         let oxygen_rating = usize::from_str_radix(&report[low], 2).unwrap();
         low = 0;
         high = report.len();
-        for i in 0..self.length {
+        for i in 0..length {
             if high - low == 1 {
                 break;
             }
             let mid = find_mid(&report, i, low, high);
-            if high - mid >= (high - low + 1) / 2 {
+            // Every number left has the same bit here, so there is nothing to filter out
+            if mid == low || mid == high {
+                continue;
+            }
+            if high - mid >= (high - low).div_ceil(2) {
                 high = mid;
             } else {
                 low = mid;
@@ -69,17 +103,6 @@ impl AbstractPuzzle for Puzzle03 {
     }
 }
 
-impl Puzzle03 {
-    pub fn create(input: &str) -> Box<dyn AbstractPuzzle> {
-        let report = input
-            .lines()
-            .map(|line| line.to_string())
-            .collect::<Vec<String>>();
-        let length = report[0].len();
-        Box::new(Puzzle03 { report, length })
-    }
-}
-
 fn find_mid(report: &[String], position: usize, mut low: usize, mut high: usize) -> usize {
     while low < high {
         let mid = low + (high - low) / 2;
@@ -92,8 +115,56 @@ fn find_mid(report: &[String], position: usize, mut low: usize, mut high: usize)
     low
 }
 
+// Follows the puzzle's description literally, counting bits and filtering the numbers one bit at a
+// time
+#[cfg(test)]
+mod reference {
+    pub fn solve(input: &str) -> [String; 2] {
+        let numbers = input
+            .lines()
+            .map(|line| line.chars().collect::<Vec<char>>())
+            .collect::<Vec<Vec<char>>>();
+        let width = numbers[0].len();
+        let mut gamma = 0;
+        let mut epsilon = 0;
+        for i in 0..width {
+            let ones = numbers.iter().filter(|number| number[i] == '1').count();
+            let one_is_most_common = 2 * ones >= numbers.len();
+            gamma = 2 * gamma + one_is_most_common as u64;
+            epsilon = 2 * epsilon + !one_is_most_common as u64;
+        }
+        let oxygen = rating(&numbers, true);
+        let co2 = rating(&numbers, false);
+        [(gamma * epsilon).to_string(), (oxygen * co2).to_string()]
+    }
+
+    fn rating(numbers: &[Vec<char>], most_common: bool) -> u64 {
+        let mut numbers = numbers.to_vec();
+        let mut i = 0;
+        while numbers.len() > 1 {
+            let ones = numbers.iter().filter(|number| number[i] == '1').count();
+            // Numbers are only filtered out when they differ in this bit
+            if ones > 0 && ones < numbers.len() {
+                let keep = if (2 * ones >= numbers.len()) == most_common {
+                    '1'
+                } else {
+                    '0'
+                };
+                numbers.retain(|number| number[i] == keep);
+            }
+            i += 1;
+        }
+        numbers[0]
+            .iter()
+            .fold(0, |rating, &bit| 2 * rating + (bit == '1') as u64)
+    }
+}
+
 #[cfg(test)]
 mod tests {
+    use crate::differential::check_against_reference;
+    use crate::puzzle::DynamicPuzzle;
+    use crate::puzzle03::reference;
     use crate::puzzle03::Puzzle03;
     use std::fs;
     use std::path::PathBuf;
@@ -104,14 +175,14 @@ mod tests {
             "00100", "11110", "10110", "10111", "10101", "01111", "00111", "11100", "10000",
             "11001", "00010", "01010",
         ];
-        let puzzle = Puzzle03::create(&input.join("\n"));
+        let puzzle = Puzzle03.create(&input.join("\n")).unwrap();
         assert_eq!(puzzle.solve_part_1(), "198");
     }
 
     #[test]
     fn test_solve_part_1() {
         let input = fs::read_to_string(PathBuf::from("resources/tests/3")).unwrap();
-        let puzzle = Puzzle03::create(&input);
+        let puzzle = Puzzle03.create(&input).unwrap();
         assert_eq!(puzzle.solve_part_1(), "3885894");
     }
 
@@ -121,14 +192,28 @@ mod tests {
             "00100", "11110", "10110", "10111", "10101", "01111", "00111", "11100", "10000",
             "11001", "00010", "01010",
         ];
-        let puzzle = Puzzle03::create(&input.join("\n"));
+        let puzzle = Puzzle03.create(&input.join("\n")).unwrap();
         assert_eq!(puzzle.solve_part_2(), "230");
     }
 
     #[test]
     fn test_solve_part_2() {
         let input = fs::read_to_string(PathBuf::from("resources/tests/3")).unwrap();
-        let puzzle = Puzzle03::create(&input);
+        let puzzle = Puzzle03.create(&input).unwrap();
         assert_eq!(puzzle.solve_part_2(), "4375225");
     }
+
+    #[test]
+    fn test_part_2_same_bits() {
+        // Both numbers left for the CO2 scrubber rating after the first bit start with 10, so the
+        // second bit filters nothing
+        let input = "000\n001\n011\n100\n101";
+        let puzzle = Puzzle03.create(input).unwrap();
+        assert_eq!(puzzle.solve_part_2(), "4");
+    }
+
+    #[test]
+    fn test_against_reference() {
+        check_against_reference(3, &[1, 2, 5, 50, 1000], reference::solve);
+    }
 }
@@ -1,18 +1,53 @@
-use crate::puzzle::AbstractPuzzle;
+use crate::parsing::{self, Input};
+use crate::puzzle::{AbstractPuzzle, Result};
 
-pub struct Puzzle04 {
+#[derive(Clone, Copy)]
+pub struct Puzzle04;
+
+pub struct Bingo {
     numbers: Vec<u32>,
     boards: Vec<BingoBoard>,
 }
 
 impl AbstractPuzzle for Puzzle04 {
+    type Parsed = Bingo;
+
     fn get_day(&self) -> u8 {
         4
     }
 
-    fn solve_part_1(&self) -> String {
-        let mut boards = self.boards.clone();
-        for number in self.numbers.iter() {
+    fn get_title(&self) -> &'static str {
+        "Giant Squid"
+    }
+
+    fn get_part_descriptions(&self) -> [&'static str; 2] {
+        [
+            "Score the first board to win",
+            "Score the last board to win",
+        ]
+    }
+
+    fn parse(&self, input: &str) -> Result<Bingo> {
+        let input = Input::new(input);
+        let mut lines = input.lines();
+        let numbers = input.comma_separated(lines.next().ok_or("The input is empty")?, "number")?;
+        let board_numbers = lines
+            .flat_map(|line| line.split_whitespace())
+            .map(|token| input.parse(token, "number"))
+            .collect::<parsing::Result<Vec<u32>>>()?;
+        if board_numbers.is_empty() || board_numbers.len() % 25 != 0 {
+            return Err("The boards must be 5x5".to_string());
+        }
+        let boards = board_numbers
+            .chunks(25)
+            .map(BingoBoard::new)
+            .collect::<Vec<BingoBoard>>();
+        Ok(Bingo { numbers, boards })
+    }
+
+    fn solve_part_1(&self, bingo: &Bingo) -> String {
+        let mut boards = bingo.boards.clone();
+        for number in bingo.numbers.iter() {
             for board in boards.iter_mut() {
                 board.add_number(*number);
                 if board.won {
@@ -23,10 +58,10 @@ impl AbstractPuzzle for Puzzle04 {
         unreachable!()
     }
 
-    fn solve_part_2(&self) -> String {
-        let mut boards = self.boards.clone();
+    fn solve_part_2(&self, bingo: &Bingo) -> String {
+        let mut boards = bingo.boards.clone();
         let length = boards.len();
-        for number in self.numbers.iter() {
+        for number in bingo.numbers.iter() {
             for i in 0..length {
                 boards[i].add_number(*number);
                 if boards.iter_mut().all(|board| board.won) {
@@ -38,31 +73,6 @@ impl AbstractPuzzle for Puzzle04 {
     }
 }
 
-impl Puzzle04 {
-    pub fn create(input: &str) -> Box<dyn AbstractPuzzle> {
-        let mut lines = input.lines();
-        let numbers = lines
-            .next()
-            .unwrap()
-            .split(',')
-            .map(|n| n.parse::<u32>().unwrap())
-            .collect::<Vec<u32>>();
-        let boards = lines
-            .map(|line| {
-                line.split_whitespace()
-                    .map(|token| token.parse::<u32>().unwrap())
-                    .collect::<Vec<u32>>()
-            })
-            .flatten()
-            .collect::<Vec<u32>>()
-            .chunks(25)
-            .into_iter()
-            .map(BingoBoard::new)
-            .collect::<Vec<BingoBoard>>();
-        Box::new(Puzzle04 { numbers, boards })
-    }
-}
-
 #[derive(Clone)]
 struct BingoBoard {
     won: bool,
@@ -133,6 +143,7 @@ impl BingoBoard {
 
 #[cfg(test)]
 mod tests {
+    use crate::puzzle::DynamicPuzzle;
     use crate::puzzle04::Puzzle04;
     use std::fs;
     use std::path::PathBuf;
@@ -158,14 +169,14 @@ mod tests {
 18  8 23 26 20\n\
 22 11 13  6  5\n\
  2  0 12  3  7";
-        let puzzle = Puzzle04::create(&input);
+        let puzzle = Puzzle04.create(input).unwrap();
         assert_eq!(puzzle.solve_part_1(), "4512");
     }
 
     #[test]
     fn test_solve_part_1() {
         let input = fs::read_to_string(PathBuf::from("resources/tests/4")).unwrap();
-        let puzzle = Puzzle04::create(&input);
+        let puzzle = Puzzle04.create(&input).unwrap();
         assert_eq!(puzzle.solve_part_1(), "87456");
     }
 
@@ -190,14 +201,14 @@ mod tests {
 18  8 23 26 20\n\
 22 11 13  6  5\n\
  2  0 12  3  7";
-        let puzzle = Puzzle04::create(&input);
+        let puzzle = Puzzle04.create(input).unwrap();
         assert_eq!(puzzle.solve_part_2(), "1924");
     }
 
     #[test]
     fn test_solve_part_2() {
         let input = fs::read_to_string(PathBuf::from("resources/tests/4")).unwrap();
-        let puzzle = Puzzle04::create(&input);
+        let puzzle = Puzzle04.create(&input).unwrap();
         assert_eq!(puzzle.solve_part_2(), "15561");
     }
 }
@@ -1,77 +1,119 @@
-use crate::puzzle::AbstractPuzzle;
+use crate::grid::Grid;
+use crate::image_export::Image;
+use crate::parsing::Input;
+use crate::puzzle::{AbstractPuzzle, Result};
 use lazy_static::lazy_static;
 use regex::Regex;
 
-pub struct Puzzle05 {
-    diagonal_lines: Vec<Vec<u32>>,
-    non_diagonal_lines: Vec<Vec<u32>>,
+// The size of the ocean floor
+const SIZE: usize = 1000;
+
+#[derive(Clone, Copy)]
+pub struct Puzzle05;
+
+// A line of vents, which is horizontal, vertical or at 45 degrees
+pub struct Line {
+    start: (i32, i32),
+    end: (i32, i32),
 }
 
 impl AbstractPuzzle for Puzzle05 {
+    type Parsed = Vec<Line>;
+
     fn get_day(&self) -> u8 {
         5
     }
 
-    fn solve_part_1(&self) -> String {
-        let mut count = 0;
-        for x in 0..1000 {
-            for y in 0..1000 {
-                if self.non_diagonal_lines[x][y] > 1 {
-                    count += 1;
-                }
-            }
-        }
-        count.to_string()
+    fn get_title(&self) -> &'static str {
+        "Hydrothermal Venture"
     }
 
-    fn solve_part_2(&self) -> String {
-        let mut count = 0;
-        for x in 0..1000 {
-            for y in 0..1000 {
-                if self.diagonal_lines[x][y] + self.non_diagonal_lines[x][y] > 1 {
-                    count += 1;
-                }
-            }
-        }
-        count.to_string()
+    fn get_part_descriptions(&self) -> [&'static str; 2] {
+        [
+            "Count the points where horizontal and vertical lines overlap",
+            "Count the points where any lines overlap",
+        ]
     }
-}
 
-impl Puzzle05 {
-    pub fn create(input: &str) -> Box<dyn AbstractPuzzle> {
+    fn parse(&self, input: &str) -> Result<Vec<Line>> {
         lazy_static! {
-            static ref RE: Regex = Regex::new(r"(\d+),(\d+) -> (\d+),(\d+)").unwrap();
+            static ref RE: Regex = Regex::new(r"^(\d+),(\d+) -> (\d+),(\d+)$").unwrap();
         }
-        let mut diagonal_lines = vec![vec![0_u32; 1000]; 1000];
-        let mut non_diagonal_lines = vec![vec![0_u32; 1000]; 1000];
-        for cap in RE.captures_iter(input) {
-            let x1 = cap[1].parse::<i32>().unwrap();
-            let y1 = cap[2].parse::<i32>().unwrap();
-            let x2 = cap[3].parse::<i32>().unwrap();
-            let y2 = cap[4].parse::<i32>().unwrap();
-            let dx = (x2 - x1).signum();
-            let dy = (y2 - y1).signum();
-            let mut x = x1;
-            let mut y = y1;
-            while x != x2 + dx || y != y2 + dy {
-                if x1 != x2 && y1 != y2 {
-                    diagonal_lines[y as usize][x as usize] += 1;
-                } else {
-                    non_diagonal_lines[y as usize][x as usize] += 1;
-                }
-                x += dx;
-                y += dy;
+        let input = Input::new(input);
+        let mut lines = Vec::new();
+        for line in input.lines() {
+            let (x1, y1, x2, y2): (i32, i32, i32, i32) =
+                input.captures(&RE, line, "line of vents")?;
+            if [x1, y1, x2, y2].iter().any(|&c| c >= SIZE as i32) {
+                return Err(input
+                    .error(line, format!("Line of vents '{}' is out of range", line))
+                    .into());
+            }
+            if x1 != x2 && y1 != y2 && (x2 - x1).abs() != (y2 - y1).abs() {
+                return Err(input
+                    .error(
+                        line,
+                        format!("Line of vents '{}' is not at 45 degrees", line),
+                    )
+                    .into());
             }
+            lines.push(Line {
+                start: (x1, y1),
+                end: (x2, y2),
+            });
+        }
+        Ok(lines)
+    }
+
+    fn solve_part_1(&self, lines: &Vec<Line>) -> String {
+        count_overlaps(&vent_map(lines.iter().filter(|line| !line.is_diagonal()))).to_string()
+    }
+
+    fn solve_part_2(&self, lines: &Vec<Line>) -> String {
+        count_overlaps(&vent_map(lines.iter())).to_string()
+    }
+
+    fn images(&self, lines: &Vec<Line>) -> Vec<(String, Image)> {
+        vec![(
+            "vents".to_string(),
+            Image::Graymap(vent_map(lines.iter()).to_rows()),
+        )]
+    }
+}
+
+impl Line {
+    fn is_diagonal(&self) -> bool {
+        self.start.0 != self.end.0 && self.start.1 != self.end.1
+    }
+
+    // The points on the line, including both ends
+    fn points(&self) -> impl Iterator<Item = (usize, usize)> {
+        let (x1, y1) = self.start;
+        let (x2, y2) = self.end;
+        let (dx, dy) = ((x2 - x1).signum(), (y2 - y1).signum());
+        let length = (x2 - x1).abs().max((y2 - y1).abs());
+        (0..=length).map(move |i| ((x1 + i * dx) as usize, (y1 + i * dy) as usize))
+    }
+}
+
+// The number of lines of vents at each point of the ocean floor
+fn vent_map<'a>(lines: impl Iterator<Item = &'a Line>) -> Grid<u32> {
+    let mut map = Grid::new(SIZE, SIZE, 0);
+    for line in lines {
+        for point in line.points() {
+            map[point] += 1;
         }
-        Box::new(Puzzle05 {
-            diagonal_lines,
-            non_diagonal_lines,
-        })
     }
+    map
+}
+
+fn count_overlaps(map: &Grid<u32>) -> usize {
+    map.iter().filter(|&(_, &count)| count > 1).count()
 }
 
 #[cfg(test)]
 mod tests {
+    use crate::puzzle::DynamicPuzzle;
     use crate::puzzle05::Puzzle05;
     use std::fs;
     use std::path::PathBuf;
@@ -88,14 +130,14 @@ mod tests {
 3,4 -> 1,4\n\
 0,0 -> 8,8\n\
 5,5 -> 8,2";
-        let puzzle = Puzzle05::create(&input);
+        let puzzle = Puzzle05.create(input).unwrap();
         assert_eq!(puzzle.solve_part_1(), "5");
     }
 
     #[test]
     fn test_solve_part_1() {
         let input = fs::read_to_string(PathBuf::from("resources/tests/5")).unwrap();
-        let puzzle = Puzzle05::create(&input);
+        let puzzle = Puzzle05.create(&input).unwrap();
         assert_eq!(puzzle.solve_part_1(), "6113");
     }
 
@@ -111,14 +153,14 @@ mod tests {
 3,4 -> 1,4\n\
 0,0 -> 8,8\n\
 5,5 -> 8,2";
-        let puzzle = Puzzle05::create(&input);
+        let puzzle = Puzzle05.create(input).unwrap();
         assert_eq!(puzzle.solve_part_2(), "12");
     }
 
     #[test]
     fn test_solve_part_2() {
         let input = fs::read_to_string(PathBuf::from("resources/tests/5")).unwrap();
-        let puzzle = Puzzle05::create(&input);
+        let puzzle = Puzzle05.create(&input).unwrap();
         assert_eq!(puzzle.solve_part_2(), "20373");
     }
 }
@@ -1,38 +1,50 @@
-use crate::puzzle::AbstractPuzzle;
+use crate::parsing::Input;
+use crate::puzzle::{AbstractPuzzle, Result};
 
-pub struct Puzzle06 {
-    input: String,
-}
+#[derive(Clone, Copy)]
+pub struct Puzzle06;
 
 impl AbstractPuzzle for Puzzle06 {
+    // The number of fish with each timer value
+    type Parsed = [u64; 9];
+
     fn get_day(&self) -> u8 {
         6
     }
 
-    fn solve_part_1(&self) -> String {
-        simulate(self.input.as_str(), 80).to_string()
+    fn get_title(&self) -> &'static str {
+        "Lanternfish"
     }
 
-    fn solve_part_2(&self) -> String {
-        simulate(self.input.as_str(), 256).to_string()
+    fn get_part_descriptions(&self) -> [&'static str; 2] {
+        [
+            "Count the lanternfish after 80 days",
+            "Count the lanternfish after 256 days",
+        ]
+    }
+
+    fn parse(&self, input: &str) -> Result<[u64; 9]> {
+        let mut fish: [u64; 9] = [0; 9];
+        for timer in Input::new(input).comma_separated::<usize>(input, "timer")? {
+            if timer >= fish.len() {
+                return Err(format!("Invalid timer '{}'", timer));
+            }
+            fish[timer] += 1;
+        }
+        Ok(fish)
     }
-}
 
-impl Puzzle06 {
-    pub fn create(input: &str) -> Box<dyn AbstractPuzzle> {
-        Box::new(Puzzle06 {
-            input: input.to_string(),
-        })
+    fn solve_part_1(&self, fish: &[u64; 9]) -> String {
+        simulate(fish, 80).to_string()
+    }
+
+    fn solve_part_2(&self, fish: &[u64; 9]) -> String {
+        simulate(fish, 256).to_string()
     }
 }
 
-fn simulate(input: &str, days: usize) -> u64 {
-    let mut fish: [u64; 9] = [0; 9];
-    input
-        .trim()
-        .split(',')
-        .map(|x| x.parse::<usize>().unwrap())
-        .for_each(|x| fish[x] += 1);
+fn simulate(fish: &[u64; 9], days: usize) -> u64 {
+    let mut fish = *fish;
     let mut base = 0;
     for _ in 0..days {
         fish[(base + 7) % 9] += fish[base];
@@ -41,8 +53,55 @@ fn simulate(input: &str, days: usize) -> u64 {
     fish.iter().sum()
 }
 
+// Simulates every fish for part 1, and counts the descendants of each fish for part 2 (which has
+// too many fish to simulate one by one)
+#[cfg(test)]
+mod reference {
+    use std::collections::HashMap;
+
+    pub fn solve(input: &str) -> [String; 2] {
+        let timers = input
+            .trim()
+            .split(',')
+            .map(|x| x.parse::<u32>().unwrap())
+            .collect::<Vec<u32>>();
+        let mut fish = timers.clone();
+        for _ in 0..80 {
+            let new_fish = fish.iter().filter(|&&timer| timer == 0).count();
+            for timer in fish.iter_mut() {
+                *timer = if *timer == 0 { 6 } else { *timer - 1 };
+            }
+            fish.extend(std::iter::repeat_n(8, new_fish));
+        }
+        let mut memo = HashMap::new();
+        let count = timers
+            .iter()
+            .map(|&timer| family_size(timer, 256, &mut memo))
+            .sum::<u64>();
+        [fish.len().to_string(), count.to_string()]
+    }
+
+    // The number of fish after the given number of days, starting from one fish with the timer
+    fn family_size(timer: u32, days: u32, memo: &mut HashMap<(u32, u32), u64>) -> u64 {
+        if days <= timer {
+            return 1;
+        }
+        if let Some(&size) = memo.get(&(timer, days)) {
+            return size;
+        }
+        // The fish first makes a new fish after timer + 1 days, and then is like a fish at 6
+        let remaining = days - timer - 1;
+        let size = family_size(6, remaining, memo) + family_size(8, remaining, memo);
+        memo.insert((timer, days), size);
+        size
+    }
+}
+
 #[cfg(test)]
 mod tests {
+    use crate::differential::check_against_reference;
+    use crate::puzzle::DynamicPuzzle;
+    use crate::puzzle06::reference;
     use crate::puzzle06::Puzzle06;
     use std::fs;
     use std::path::PathBuf;
@@ -50,28 +109,33 @@ mod tests {
     #[test]
     fn test_part_1_example_1() {
         let input = "3,4,3,1,2";
-        let puzzle = Puzzle06::create(&input);
+        let puzzle = Puzzle06.create(input).unwrap();
         assert_eq!(puzzle.solve_part_1(), "5934");
     }
 
     #[test]
     fn test_solve_part_1() {
         let input = fs::read_to_string(PathBuf::from("resources/tests/6")).unwrap();
-        let puzzle = Puzzle06::create(&input);
+        let puzzle = Puzzle06.create(&input).unwrap();
         assert_eq!(puzzle.solve_part_1(), "349549");
     }
 
     #[test]
     fn test_part_2_example_1() {
         let input = "3,4,3,1,2";
-        let puzzle = Puzzle06::create(&input);
+        let puzzle = Puzzle06.create(input).unwrap();
         assert_eq!(puzzle.solve_part_2(), "26984457539");
     }
 
     #[test]
     fn test_solve_part_2() {
         let input = fs::read_to_string(PathBuf::from("resources/tests/6")).unwrap();
-        let puzzle = Puzzle06::create(&input);
+        let puzzle = Puzzle06.create(&input).unwrap();
         assert_eq!(puzzle.solve_part_2(), "1589590444365");
     }
+
+    #[test]
+    fn test_against_reference() {
+        check_against_reference(6, &[1, 5, 300], reference::solve);
+    }
 }
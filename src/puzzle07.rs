@@ -1,34 +1,53 @@
-use crate::puzzle::AbstractPuzzle;
+use crate::parsing::Input;
+use crate::puzzle::{AbstractPuzzle, Result};
 use std::cmp::min;
 
-pub struct Puzzle07 {
-    positions: Vec<i32>,
-}
+#[derive(Clone, Copy)]
+pub struct Puzzle07;
 
 impl AbstractPuzzle for Puzzle07 {
+    // The positions of the crabs, partitioned around the median
+    type Parsed = Vec<i32>;
+
     fn get_day(&self) -> u8 {
         7
     }
 
-    fn solve_part_1(&self) -> String {
-        let median = self.positions[self.positions.len() / 2];
-        self.positions
+    fn get_title(&self) -> &'static str {
+        "The Treachery of Whales"
+    }
+
+    fn get_part_descriptions(&self) -> [&'static str; 2] {
+        [
+            "Find the least fuel needed to align the crabs",
+            "Find the least fuel needed to align the crabs when each step costs more",
+        ]
+    }
+
+    fn parse(&self, input: &str) -> Result<Vec<i32>> {
+        let mut positions = Input::new(input).comma_separated::<i32>(input, "position")?;
+        let length = positions.len();
+        let _ = positions.select_nth_unstable(length / 2);
+        Ok(positions)
+    }
+
+    fn solve_part_1(&self, positions: &Vec<i32>) -> String {
+        let median = positions[positions.len() / 2];
+        positions
             .iter()
             .map(|&x| (x - median).abs())
             .sum::<i32>()
             .to_string()
     }
 
-    fn solve_part_2(&self) -> String {
-        let mean = (self.positions.iter().sum::<i32>() as f64) / (self.positions.len() as f64);
-        let floor_cost = self
-            .positions
+    fn solve_part_2(&self, positions: &Vec<i32>) -> String {
+        let mean = (positions.iter().sum::<i32>() as f64) / (positions.len() as f64);
+        let floor_cost = positions
             .iter()
             .map(|&x| (x as f64 - mean.floor()).abs() as i32)
             .map(|x| x * (x + 1) / 2)
             .sum::<i32>();
-        let ceil_cost = self
-            .positions
+        let ceil_cost = positions
             .iter()
             .map(|&x| (x as f64 - mean.ceil()).abs() as i32)
             .map(|x| x * (x + 1) / 2)
@@ -37,21 +56,41 @@ impl AbstractPuzzle for Puzzle07 {
     }
 }
 
-impl Puzzle07 {
-    pub fn create(input: &str) -> Box<dyn AbstractPuzzle> {
-        let mut positions = input
+// Tries every position that the crabs could line up at
+#[cfg(test)]
+mod reference {
+    pub fn solve(input: &str) -> [String; 2] {
+        let positions = input
             .trim()
             .split(',')
-            .map(|x| x.parse::<i32>().unwrap())
-            .collect::<Vec<i32>>();
-        let length = positions.len();
-        let _ = positions.select_nth_unstable(length / 2);
-        Box::new(Puzzle07 { positions })
+            .map(|x| x.parse::<i64>().unwrap())
+            .collect::<Vec<i64>>();
+        let min_position = *positions.iter().min().unwrap();
+        let max_position = *positions.iter().max().unwrap();
+        let least_fuel = |cost: fn(i64) -> i64| {
+            (min_position..=max_position)
+                .map(|target| {
+                    positions
+                        .iter()
+                        .map(|&x| cost((x - target).abs()))
+                        .sum::<i64>()
+                })
+                .min()
+                .unwrap()
+                .to_string()
+        };
+        [
+            least_fuel(|distance| distance),
+            least_fuel(|distance| distance * (distance + 1) / 2),
+        ]
     }
 }
 
 #[cfg(test)]
 mod tests {
+    use crate::differential::check_against_reference;
+    use crate::puzzle::DynamicPuzzle;
+    use crate::puzzle07::reference;
     use crate::puzzle07::Puzzle07;
     use std::cmp::min;
     use std::fs;
@@ -60,7 +99,7 @@ mod tests {
     #[test]
     fn test_part_1_example_1() {
         let input = "16,1,2,0,4,2,7,1,2,14";
-        let puzzle = Puzzle07::create(&input);
+        let puzzle = Puzzle07.create(input).unwrap();
         assert_eq!(puzzle.solve_part_1(), "37");
     }
 
@@ -82,13 +121,15 @@ mod tests {
                 min_cost = min(cost, min_cost);
             }
 
-            let puzzle = Puzzle07::create(
-                &positions
-                    .iter()
-                    .map(|&x| x.to_string())
-                    .collect::<Vec<String>>()
-                    .join(","),
-            );
+            let puzzle = Puzzle07
+                .create(
+                    &positions
+                        .iter()
+                        .map(|&x| x.to_string())
+                        .collect::<Vec<String>>()
+                        .join(","),
+                )
+                .unwrap();
             assert_eq!(puzzle.solve_part_1(), min_cost.to_string());
         }
     }
@@ -96,21 +137,26 @@ mod tests {
     #[test]
     fn test_solve_part_1() {
         let input = fs::read_to_string(PathBuf::from("resources/tests/7")).unwrap();
-        let puzzle = Puzzle07::create(&input);
+        let puzzle = Puzzle07.create(&input).unwrap();
         assert_eq!(puzzle.solve_part_1(), "356922");
     }
 
     #[test]
     fn test_part_2_example_1() {
         let input = "16,1,2,0,4,2,7,1,2,14";
-        let puzzle = Puzzle07::create(&input);
+        let puzzle = Puzzle07.create(input).unwrap();
         assert_eq!(puzzle.solve_part_2(), "168");
     }
 
     #[test]
     fn test_solve_part_2() {
         let input = fs::read_to_string(PathBuf::from("resources/tests/7")).unwrap();
-        let puzzle = Puzzle07::create(&input);
+        let puzzle = Puzzle07.create(&input).unwrap();
         assert_eq!(puzzle.solve_part_2(), "100347031");
     }
+
+    #[test]
+    fn test_against_reference() {
+        check_against_reference(7, &[1, 2, 3, 10, 100], reference::solve);
+    }
 }
@@ -1,26 +1,57 @@
-use crate::puzzle::AbstractPuzzle;
+use crate::parsing::Input;
+use crate::puzzle::{AbstractPuzzle, Result};
 
-pub struct Puzzle08 {
-    displays: Vec<Display>,
-}
+#[derive(Clone, Copy)]
+pub struct Puzzle08;
 
 impl AbstractPuzzle for Puzzle08 {
+    type Parsed = Vec<Display>;
+
     fn get_day(&self) -> u8 {
         8
     }
 
-    fn solve_part_1(&self) -> String {
-        self.displays
+    fn get_title(&self) -> &'static str {
+        "Seven Segment Search"
+    }
+
+    fn get_part_descriptions(&self) -> [&'static str; 2] {
+        [
+            "Count the 1, 4, 7 and 8 digits in the outputs",
+            "Add up the decoded outputs",
+        ]
+    }
+
+    fn parse(&self, input: &str) -> Result<Vec<Display>> {
+        let input = Input::new(input);
+        Ok(input.parse_lines(|line| {
+            let (patterns, outputs) = input.split_pair(line, "|", "display")?;
+            let patterns = patterns
+                .split_whitespace()
+                .map(|x| x.to_string())
+                .collect::<Vec<String>>();
+            let outputs = outputs
+                .split_whitespace()
+                .map(|x| x.to_string())
+                .collect::<Vec<String>>();
+            if patterns.len() != 10 {
+                return Err(input.error(line, format!("Display '{}' must have 10 patterns", line)));
+            }
+            Ok(Display { patterns, outputs })
+        })?)
+    }
+
+    fn solve_part_1(&self, displays: &Vec<Display>) -> String {
+        displays
             .iter()
-            .map(|display| display.outputs.iter())
-            .flatten()
+            .flat_map(|display| display.outputs.iter())
             .filter(|&x| x.len() == 2 || x.len() == 3 || x.len() == 4 || x.len() == 7)
             .count()
             .to_string()
     }
 
-    fn solve_part_2(&self) -> String {
-        self.displays
+    fn solve_part_2(&self, displays: &Vec<Display>) -> String {
+        displays
             .iter()
             .map(|display| display.decode())
             .sum::<usize>()
@@ -28,28 +59,7 @@ impl AbstractPuzzle for Puzzle08 {
     }
 }
 
-impl Puzzle08 {
-    pub fn create(input: &str) -> Box<dyn AbstractPuzzle> {
-        let displays = input
-            .lines()
-            .map(|line| {
-                let parts = line.split(" | ").collect::<Vec<&str>>();
-                let patterns = parts[0]
-                    .split(' ')
-                    .map(|x| x.to_string())
-                    .collect::<Vec<String>>();
-                let outputs = parts[1]
-                    .split(' ')
-                    .map(|x| x.to_string())
-                    .collect::<Vec<String>>();
-                Display { patterns, outputs }
-            })
-            .collect::<Vec<Display>>();
-        Box::new(Puzzle08 { displays })
-    }
-}
-
-struct Display {
+pub struct Display {
     patterns: Vec<String>,
     outputs: Vec<String>,
 }
@@ -132,6 +142,7 @@ fn sort(s: &str) -> String {
 
 #[cfg(test)]
 mod tests {
+    use crate::puzzle::DynamicPuzzle;
     use crate::puzzle08::Puzzle08;
     use std::fs;
     use std::path::PathBuf;
@@ -148,14 +159,14 @@ dbcfg fgd bdegcaf fgec aegbdf ecdfab fbedc dacgb gdcebf gf | cefg dcbef fcge gbc
 bdfegc cbegaf gecbf dfcage bdacg ed bedf ced adcbefg gebcd | ed bcgafe cdgba cbgef\n\
 egadfb cdbfeg cegd fecab cgb gbdefca cg fgcdab egfdb bfceg | gbdfcae bgc cg cgb\n\
 gcafb gcf dcaebfg ecagb gf abcdeg gaef cafbge fdbac fegbdc | fgae cfgab fg bagce";
-        let puzzle = Puzzle08::create(&input);
+        let puzzle = Puzzle08.create(input).unwrap();
         assert_eq!(puzzle.solve_part_1(), "26");
     }
 
     #[test]
     fn test_solve_part_1() {
         let input = fs::read_to_string(PathBuf::from("resources/tests/8")).unwrap();
-        let puzzle = Puzzle08::create(&input);
+        let puzzle = Puzzle08.create(&input).unwrap();
         assert_eq!(puzzle.solve_part_1(), "318");
     }
 
@@ -171,14 +182,14 @@ dbcfg fgd bdegcaf fgec aegbdf ecdfab fbedc dacgb gdcebf gf | cefg dcbef fcge gbc
 bdfegc cbegaf gecbf dfcage bdacg ed bedf ced adcbefg gebcd | ed bcgafe cdgba cbgef\n\
 egadfb cdbfeg cegd fecab cgb gbdefca cg fgcdab egfdb bfceg | gbdfcae bgc cg cgb\n\
 gcafb gcf dcaebfg ecagb gf abcdeg gaef cafbge fdbac fegbdc | fgae cfgab fg bagce";
-        let puzzle = Puzzle08::create(&input);
+        let puzzle = Puzzle08.create(input).unwrap();
         assert_eq!(puzzle.solve_part_2(), "61229");
     }
 
     #[test]
     fn test_solve_part_2() {
         let input = fs::read_to_string(PathBuf::from("resources/tests/8")).unwrap();
-        let puzzle = Puzzle08::create(&input);
+        let puzzle = Puzzle08.create(&input).unwrap();
         assert_eq!(puzzle.solve_part_2(), "996280");
     }
 }
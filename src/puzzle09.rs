@@ -1,29 +1,46 @@
-use crate::puzzle::AbstractPuzzle;
+use crate::grid::Grid;
+use crate::image_export::Image;
+use crate::puzzle::{AbstractPuzzle, Result};
 use std::collections::{BinaryHeap, HashSet};
 
-pub struct Puzzle09 {
-    grid: Vec<Vec<u32>>,
-    height: usize,
-    width: usize,
-}
+#[derive(Clone, Copy)]
+pub struct Puzzle09;
 
 impl AbstractPuzzle for Puzzle09 {
+    // The height of each location
+    type Parsed = Grid<u32>;
+
     fn get_day(&self) -> u8 {
         9
     }
 
-    fn solve_part_1(&self) -> String {
-        self.low_points()
+    fn get_title(&self) -> &'static str {
+        "Smoke Basin"
+    }
+
+    fn get_part_descriptions(&self) -> [&'static str; 2] {
+        [
+            "Add up the risk levels of the low points",
+            "Multiply the sizes of the three largest basins",
+        ]
+    }
+
+    fn parse(&self, input: &str) -> Result<Grid<u32>> {
+        Grid::parse_digits(input)
+    }
+
+    fn solve_part_1(&self, heightmap: &Grid<u32>) -> String {
+        low_points(heightmap)
             .iter()
-            .map(|&(x, y)| self.grid[y][x] + 1)
+            .map(|&point| heightmap[point] + 1)
             .sum::<u32>()
             .to_string()
     }
 
-    fn solve_part_2(&self) -> String {
+    fn solve_part_2(&self, heightmap: &Grid<u32>) -> String {
         let mut basin_sizes = BinaryHeap::new();
         let mut seen = HashSet::new();
-        for point in self.low_points() {
+        for point in low_points(heightmap) {
             let mut size = 0;
             let mut queue = vec![point];
             while let Some((x, y)) = queue.pop() {
@@ -32,78 +49,43 @@ impl AbstractPuzzle for Puzzle09 {
                 }
                 seen.insert((x, y));
                 size += 1;
-                for (x, y) in self.adjacent_rising(x, y) {
-                    queue.push((x, y));
-                }
+                queue.extend(adjacent_rising(heightmap, x, y));
             }
             basin_sizes.push(size);
         }
         basin_sizes.iter().take(3).product::<u32>().to_string()
     }
-}
 
-impl Puzzle09 {
-    pub fn create(input: &str) -> Box<dyn AbstractPuzzle> {
-        let grid = input
-            .lines()
-            .map(|line| {
-                line.chars()
-                    .map(|c| c.to_digit(10).unwrap())
-                    .collect::<Vec<u32>>()
-            })
-            .collect::<Vec<Vec<u32>>>();
-        let height = grid.len();
-        let width = grid[0].len();
-        Box::new(Puzzle09 {
-            grid,
-            height,
-            width,
-        })
-    }
-
-    fn low_points(&self) -> Vec<(usize, usize)> {
-        let mut points = Vec::new();
-        for y in 0..self.height {
-            for x in 0..self.width {
-                if self
-                    .adjacent(x, y)
-                    .iter()
-                    .all(|&(i, j)| self.grid[j][i] > self.grid[y][x])
-                {
-                    points.push((x, y));
-                }
-            }
-        }
-        points
+    fn images(&self, heightmap: &Grid<u32>) -> Vec<(String, Image)> {
+        vec![("heightmap".to_string(), Image::Graymap(heightmap.to_rows()))]
     }
+}
 
-    fn adjacent_rising(&self, x: usize, y: usize) -> Vec<(usize, usize)> {
-        let mut adjacent = self.adjacent(x, y);
-        adjacent.retain(|&(i, j)| self.grid[j][i] > self.grid[y][x]);
-        adjacent.retain(|&(i, j)| self.grid[j][i] != 9);
-        adjacent
-    }
+fn low_points(heightmap: &Grid<u32>) -> Vec<(usize, usize)> {
+    heightmap
+        .iter()
+        .filter(|&((x, y), &height)| {
+            heightmap
+                .neighbours_4(x, y)
+                .all(|point| heightmap[point] > height)
+        })
+        .map(|(point, _)| point)
+        .collect()
+}
 
-    fn adjacent(&self, x: usize, y: usize) -> Vec<(usize, usize)> {
-        let mut adjacent = Vec::new();
-        if x > 0 {
-            adjacent.push((x - 1, y));
-        }
-        if x < self.width - 1 {
-            adjacent.push((x + 1, y));
-        }
-        if y > 0 {
-            adjacent.push((x, y - 1));
-        }
-        if y < self.height - 1 {
-            adjacent.push((x, y + 1));
-        }
-        adjacent
-    }
+fn adjacent_rising(
+    heightmap: &Grid<u32>,
+    x: usize,
+    y: usize,
+) -> impl Iterator<Item = (usize, usize)> + '_ {
+    heightmap
+        .neighbours_4(x, y)
+        .filter(move |&point| heightmap[point] > heightmap[(x, y)] && heightmap[point] != 9)
 }
 
 #[cfg(test)]
 mod tests {
+    use crate::puzzle::DynamicPuzzle;
     use crate::puzzle09::Puzzle09;
     use std::fs;
     use std::path::PathBuf;
@@ -115,14 +97,14 @@ mod tests {
 9856789892\n\
 8767896789\n\
 9899965678";
-        let puzzle = Puzzle09::create(&input);
+        let puzzle = Puzzle09.create(input).unwrap();
         assert_eq!(puzzle.solve_part_1(), "15");
     }
 
     #[test]
     fn test_solve_part_1() {
         let input = fs::read_to_string(PathBuf::from("resources/tests/9")).unwrap();
-        let puzzle = Puzzle09::create(&input);
+        let puzzle = Puzzle09.create(&input).unwrap();
         assert_eq!(puzzle.solve_part_1(), "550");
     }
 
@@ -133,14 +115,14 @@ mod tests {
 9856789892\n\
 8767896789\n\
 9899965678";
-        let puzzle = Puzzle09::create(&input);
+        let puzzle = Puzzle09.create(input).unwrap();
         assert_eq!(puzzle.solve_part_2(), "1134");
     }
 
     #[test]
     fn test_solve_part_2() {
         let input = fs::read_to_string(PathBuf::from("resources/tests/9")).unwrap();
-        let puzzle = Puzzle09::create(&input);
+        let puzzle = Puzzle09.create(&input).unwrap();
         assert_eq!(puzzle.solve_part_2(), "1100682");
     }
 }
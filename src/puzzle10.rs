@@ -1,17 +1,42 @@
-use crate::puzzle::AbstractPuzzle;
+use crate::parsing::Input;
+use crate::puzzle::{AbstractPuzzle, Result};
 
-pub struct Puzzle10 {
-    lines: Vec<String>,
-}
+#[derive(Clone, Copy)]
+pub struct Puzzle10;
 
 impl AbstractPuzzle for Puzzle10 {
+    type Parsed = Vec<String>;
+
     fn get_day(&self) -> u8 {
         10
     }
 
-    fn solve_part_1(&self) -> String {
+    fn get_title(&self) -> &'static str {
+        "Syntax Scoring"
+    }
+
+    fn get_part_descriptions(&self) -> [&'static str; 2] {
+        [
+            "Score the syntax errors in the corrupted lines",
+            "Find the middle score of completing the incomplete lines",
+        ]
+    }
+
+    fn parse(&self, input: &str) -> Result<Vec<String>> {
+        Input::new(input)
+            .lines()
+            .map(
+                |line| match line.chars().find(|c| !"()[]{}<>".contains(*c)) {
+                    Some(c) => Err(format!("Invalid character '{}' in line '{}'", c, line)),
+                    None => Ok(line.to_string()),
+                },
+            )
+            .collect()
+    }
+
+    fn solve_part_1(&self, lines: &Vec<String>) -> String {
         let mut score = 0;
-        for line in &self.lines {
+        for line in lines {
             let mut stack = Vec::new();
             for c in line.chars() {
                 if c == '(' || c == '[' || c == '{' || c == '<' {
@@ -39,9 +64,9 @@ impl AbstractPuzzle for Puzzle10 {
         score.to_string()
     }
 
-    fn solve_part_2(&self) -> String {
+    fn solve_part_2(&self, lines: &Vec<String>) -> String {
         let mut costs: Vec<u64> = Vec::new();
-        for line in &self.lines {
+        for line in lines {
             let mut corrupted = false;
             let mut stack = Vec::new();
             for c in line.chars() {
@@ -81,16 +106,9 @@ impl AbstractPuzzle for Puzzle10 {
     }
 }
 
-impl Puzzle10 {
-    pub fn create(input: &str) -> Box<dyn AbstractPuzzle> {
-        Box::new(Puzzle10 {
-            lines: input.lines().map(|x| x.to_string()).collect(),
-        })
-    }
-}
-
 #[cfg(test)]
 mod tests {
+    use crate::puzzle::DynamicPuzzle;
     use crate::puzzle10::Puzzle10;
     use std::fs;
     use std::path::PathBuf;
@@ -107,14 +125,14 @@ mod tests {
 [<(<(<(<{}))><([]([]()\n\
 <{([([[(<>()){}]>(<<{{\n\
 <{([{{}}[<[[[<>{}]]]>[]]";
-        let puzzle = Puzzle10::create(&input);
+        let puzzle = Puzzle10.create(input).unwrap();
         assert_eq!(puzzle.solve_part_1(), "26397");
     }
 
     #[test]
     fn test_solve_part_1() {
         let input = fs::read_to_string(PathBuf::from("resources/tests/10")).unwrap();
-        let puzzle = Puzzle10::create(&input);
+        let puzzle = Puzzle10.create(&input).unwrap();
         assert_eq!(puzzle.solve_part_1(), "271245");
     }
 
@@ -130,14 +148,14 @@ mod tests {
 [<(<(<(<{}))><([]([]()\n\
 <{([([[(<>()){}]>(<<{{\n\
 <{([{{}}[<[[[<>{}]]]>[]]";
-        let puzzle = Puzzle10::create(&input);
+        let puzzle = Puzzle10.create(input).unwrap();
         assert_eq!(puzzle.solve_part_2(), "288957");
     }
 
     #[test]
     fn test_solve_part_2() {
         let input = fs::read_to_string(PathBuf::from("resources/tests/10")).unwrap();
-        let puzzle = Puzzle10::create(&input);
+        let puzzle = Puzzle10.create(&input).unwrap();
         assert_eq!(puzzle.solve_part_2(), "1685293086");
     }
 }
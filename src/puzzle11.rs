@@ -1,127 +1,92 @@
-use crate::puzzle::AbstractPuzzle;
+use crate::grid::Grid;
+use crate::puzzle::{AbstractPuzzle, Result};
+use crate::solve_context::SolveContext;
 
-pub struct Puzzle11 {
-    input: String,
-    height: usize,
-    width: usize,
-}
+#[derive(Clone, Copy)]
+pub struct Puzzle11;
 
 impl AbstractPuzzle for Puzzle11 {
+    // The energy level of each octopus
+    type Parsed = Grid<u8>;
+
     fn get_day(&self) -> u8 {
         11
     }
 
-    fn solve_part_1(&self) -> String {
-        let mut grid = self.input_to_grid();
+    fn get_title(&self) -> &'static str {
+        "Dumbo Octopus"
+    }
+
+    fn get_part_descriptions(&self) -> [&'static str; 2] {
+        [
+            "Count the flashes after 100 steps",
+            "Find the first step when every octopus flashes",
+        ]
+    }
+
+    fn parse(&self, input: &str) -> Result<Grid<u8>> {
+        Ok(Grid::parse_digits(input)?.map(|&energy| energy as u8))
+    }
+
+    fn solve_part_1(&self, octopuses: &Grid<u8>) -> String {
+        let mut grid = octopuses.clone();
         let mut flashes = 0;
         for _ in 0..100 {
-            flashes += self.do_step(&mut grid);
+            flashes += do_step(&mut grid);
         }
         flashes.to_string()
     }
 
-    fn solve_part_2(&self) -> String {
-        let mut grid = self.input_to_grid();
+    fn solve_part_2(&self, octopuses: &Grid<u8>) -> String {
+        self.solve_part_2_with(octopuses, &SolveContext::new())
+            .unwrap()
+    }
+
+    // The octopuses might never all flash at once, so this keeps checking whether to give up
+    fn solve_part_2_with(&self, octopuses: &Grid<u8>, ctx: &SolveContext) -> Result<String> {
+        let mut grid = octopuses.clone();
         let mut step = 1;
         loop {
-            let count = self.do_step(&mut grid);
-            if count == self.height * self.width {
-                return step.to_string();
+            ctx.check()?;
+            let count = do_step(&mut grid);
+            if count == grid.len() {
+                return Ok(step.to_string());
             }
             step += 1;
         }
     }
 }
 
-impl Puzzle11 {
-    pub fn create(input: &str) -> Box<dyn AbstractPuzzle> {
-        let height = input.lines().count();
-        let width = input.lines().next().unwrap().len();
-        Box::new(Puzzle11 {
-            input: input.to_string(),
-            height,
-            width,
-        })
+// Advances the octopuses by a step, returning how many flashed
+pub(crate) fn do_step(grid: &mut Grid<u8>) -> usize {
+    for energy in grid.iter_mut() {
+        *energy += 1;
     }
-
-    fn do_step(&self, grid: &mut Vec<Vec<u8>>) -> usize {
-        let mut num_flashed = 0;
-        for row in grid.iter_mut().take(self.height) {
-            for x in row.iter_mut().take(self.width) {
-                *x += 1;
-            }
-        }
-        let mut flashed = vec![vec![false; self.width]; self.height];
-        let mut any_flashed = true;
-        while any_flashed {
-            any_flashed = false;
-            for y in 0..self.height {
-                for x in 0..self.width {
-                    if grid[y][x] > 9 && !flashed[y][x] {
-                        flashed[y][x] = true;
-                        any_flashed = true;
-                        num_flashed += 1;
-                        for point in self.adjacent(x, y) {
-                            grid[point.1][point.0] += 1;
-                        }
-                    }
-                }
-            }
-        }
-        for y in 0..self.height {
-            for x in 0..self.width {
-                if flashed[y][x] {
-                    grid[y][x] = 0;
+    let mut flashed = Grid::new(grid.width(), grid.height(), false);
+    let mut any_flashed = true;
+    while any_flashed {
+        any_flashed = false;
+        for (x, y) in grid.positions() {
+            if grid[(x, y)] > 9 && !flashed[(x, y)] {
+                flashed[(x, y)] = true;
+                any_flashed = true;
+                for point in grid.neighbours_8(x, y).collect::<Vec<_>>() {
+                    grid[point] += 1;
                 }
             }
         }
-        num_flashed
-    }
-
-    fn adjacent(&self, x: usize, y: usize) -> Vec<(usize, usize)> {
-        let mut adjacent = Vec::new();
-        if x > 0 && y > 0 {
-            adjacent.push((x - 1, y - 1));
-        }
-        if y > 0 {
-            adjacent.push((x, y - 1));
-        }
-        if x < self.width - 1 && y > 0 {
-            adjacent.push((x + 1, y - 1));
-        }
-        if x > 0 {
-            adjacent.push((x - 1, y));
-        }
-        if x < self.width - 1 {
-            adjacent.push((x + 1, y));
-        }
-        if x > 0 && y < self.height - 1 {
-            adjacent.push((x - 1, y + 1));
-        }
-        if y < self.height - 1 {
-            adjacent.push((x, y + 1));
-        }
-        if x < self.width - 1 && y < self.height - 1 {
-            adjacent.push((x + 1, y + 1));
-        }
-        adjacent
     }
-
-    fn input_to_grid(&self) -> Vec<Vec<u8>> {
-        let mut grid = Vec::new();
-        for line in self.input.lines() {
-            let mut row = Vec::new();
-            for c in line.chars() {
-                row.push(c.to_digit(10).unwrap() as u8);
-            }
-            grid.push(row);
+    for (point, &flashed) in flashed.iter() {
+        if flashed {
+            grid[point] = 0;
         }
-        grid
     }
+    flashed.iter().filter(|&(_, &flashed)| flashed).count()
 }
 
 #[cfg(test)]
 mod tests {
+    use crate::puzzle::DynamicPuzzle;
     use crate::puzzle11::Puzzle11;
     use std::fs;
     use std::path::PathBuf;
@@ -138,14 +103,14 @@ mod tests {
 6882881134\n\
 4846848554\n\
 5283751526";
-        let puzzle = Puzzle11::create(&input);
+        let puzzle = Puzzle11.create(input).unwrap();
         assert_eq!(puzzle.solve_part_1(), "1656");
     }
 
     #[test]
     fn test_solve_part_1() {
         let input = fs::read_to_string(PathBuf::from("resources/tests/11")).unwrap();
-        let puzzle = Puzzle11::create(&input);
+        let puzzle = Puzzle11.create(&input).unwrap();
         assert_eq!(puzzle.solve_part_1(), "1634");
     }
 
@@ -161,14 +126,14 @@ mod tests {
 6882881134\n\
 4846848554\n\
 5283751526";
-        let puzzle = Puzzle11::create(&input);
+        let puzzle = Puzzle11.create(input).unwrap();
         assert_eq!(puzzle.solve_part_2(), "195");
     }
 
     #[test]
     fn test_solve_part_2() {
         let input = fs::read_to_string(PathBuf::from("resources/tests/11")).unwrap();
-        let puzzle = Puzzle11::create(&input);
+        let puzzle = Puzzle11.create(&input).unwrap();
         assert_eq!(puzzle.solve_part_2(), "210");
     }
 }
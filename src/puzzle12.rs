@@ -1,7 +1,16 @@
-use crate::puzzle::AbstractPuzzle;
+use crate::parsing::Input;
+use crate::puzzle::{AbstractPuzzle, Result};
+use crate::solve_context::SolveContext;
 use std::collections::{HashMap, HashSet};
 
-pub struct Puzzle12 {
+// Whether to give up is only checked once every 1024 caves visited (when the count masked by this
+// is zero), as checking reads the clock
+const CHECK_MASK: usize = 1023;
+
+#[derive(Clone, Copy)]
+pub struct Puzzle12;
+
+pub struct Caves {
     start: usize,
     end: usize,
     size: usize,
@@ -10,28 +19,28 @@ pub struct Puzzle12 {
 }
 
 impl AbstractPuzzle for Puzzle12 {
+    type Parsed = Caves;
+
     fn get_day(&self) -> u8 {
         12
     }
 
-    fn solve_part_1(&self) -> String {
-        self.count_paths(&mut vec![0; self.size], self.start, true)
-            .to_string()
+    fn get_title(&self) -> &'static str {
+        "Passage Pathing"
     }
 
-    fn solve_part_2(&self) -> String {
-        self.count_paths(&mut vec![0; self.size], self.start, false)
-            .to_string()
+    fn get_part_descriptions(&self) -> [&'static str; 2] {
+        [
+            "Count the paths that visit small caves at most once",
+            "Count the paths that may visit one small cave twice",
+        ]
     }
-}
 
-impl Puzzle12 {
-    pub fn create(input: &str) -> Box<dyn AbstractPuzzle> {
+    fn parse(&self, input: &str) -> Result<Caves> {
+        let input = Input::new(input);
         let mut string_edges = HashMap::new();
         for line in input.lines() {
-            let mut parts = line.split('-');
-            let from = parts.next().unwrap();
-            let to = parts.next().unwrap();
+            let (from, to) = input.split_pair(line, "-", "passage")?;
             string_edges
                 .entry(from.to_string())
                 .or_insert_with(HashSet::new)
@@ -52,15 +61,19 @@ impl Puzzle12 {
                 }
             }
         }
-        let start = caves.binary_search(&"start".to_string()).unwrap();
-        let end = caves.binary_search(&"end".to_string()).unwrap();
+        let start = caves
+            .binary_search(&"start".to_string())
+            .map_err(|_| "There is no start cave")?;
+        let end = caves
+            .binary_search(&"end".to_string())
+            .map_err(|_| "There is no end cave")?;
         let mut small_caves = vec![false; size];
         for i in 0..size {
             if caves[i].chars().next().unwrap() >= 'a' {
                 small_caves[i] = true;
             }
         }
-        Box::new(Puzzle12 {
+        Ok(Caves {
             start,
             end,
             size,
@@ -69,13 +82,59 @@ impl Puzzle12 {
         })
     }
 
-    fn count_paths(&self, path: &mut Vec<i8>, cave: usize, mut seen_twice: bool) -> u32 {
+    fn solve_part_1(&self, caves: &Caves) -> String {
+        self.solve_part_1_with(caves, &SolveContext::new()).unwrap()
+    }
+
+    fn solve_part_2(&self, caves: &Caves) -> String {
+        self.solve_part_2_with(caves, &SolveContext::new()).unwrap()
+    }
+
+    fn solve_part_1_with(&self, caves: &Caves, ctx: &SolveContext) -> Result<String> {
+        Ok(caves.count_all_paths(true, ctx)?.to_string())
+    }
+
+    fn solve_part_2_with(&self, caves: &Caves, ctx: &SolveContext) -> Result<String> {
+        Ok(caves.count_all_paths(false, ctx)?.to_string())
+    }
+}
+
+impl Caves {
+    // Counts the paths from the start to the end, reporting progress as each passage out of the
+    // start is explored
+    fn count_all_paths(&self, seen_twice: bool, ctx: &SolveContext) -> Result<u32> {
+        let mut path = vec![0; self.size];
+        path[self.start] += 1;
+        let mut visited = 0;
+        let next_caves = (0..self.size)
+            .filter(|&next| self.edges[self.start][next])
+            .collect::<Vec<usize>>();
+        let mut count = 0;
+        for (i, &next) in next_caves.iter().enumerate() {
+            ctx.report_progress(i, next_caves.len());
+            count += self.count_paths(&mut path, next, seen_twice, &mut visited, ctx)?;
+        }
+        Ok(count)
+    }
+
+    fn count_paths(
+        &self,
+        path: &mut Vec<i8>,
+        cave: usize,
+        mut seen_twice: bool,
+        visited: &mut usize,
+        ctx: &SolveContext,
+    ) -> Result<u32> {
+        if *visited & CHECK_MASK == 0 {
+            ctx.check()?;
+        }
+        *visited += 1;
         if cave == self.end {
-            return 1;
+            return Ok(1);
         }
         if self.small_caves[cave] && path[cave] > 0 {
             if seen_twice || cave == self.start {
-                return 0;
+                return Ok(0);
             }
             seen_twice = true;
         }
@@ -83,19 +142,22 @@ impl Puzzle12 {
         let mut count = 0;
         for next in 0..self.size {
             if self.edges[cave][next] {
-                count += self.count_paths(path, next, seen_twice);
+                count += self.count_paths(path, next, seen_twice, visited, ctx)?;
             }
         }
         path[cave] -= 1;
-        count
+        Ok(count)
     }
 }
 
 #[cfg(test)]
 mod tests {
+    use crate::puzzle::DynamicPuzzle;
     use crate::puzzle12::Puzzle12;
+    use crate::solve_context::SolveContext;
     use std::fs;
     use std::path::PathBuf;
+    use std::time::Duration;
 
     #[test]
     fn test_part_1_example_1() {
@@ -106,7 +168,7 @@ A-b\n\
 b-d\n\
 A-end\n\
 b-end";
-        let puzzle = Puzzle12::create(&input);
+        let puzzle = Puzzle12.create(input).unwrap();
         assert_eq!(puzzle.solve_part_1(), "10");
     }
 
@@ -122,7 +184,7 @@ HN-end\n\
 kj-sa\n\
 kj-HN\n\
 kj-dc";
-        let puzzle = Puzzle12::create(&input);
+        let puzzle = Puzzle12.create(input).unwrap();
         assert_eq!(puzzle.solve_part_1(), "19");
     }
 
@@ -146,14 +208,14 @@ he-WI\n\
 zg-he\n\
 pj-fs\n\
 start-RW";
-        let puzzle = Puzzle12::create(&input);
+        let puzzle = Puzzle12.create(input).unwrap();
         assert_eq!(puzzle.solve_part_1(), "226");
     }
 
     #[test]
     fn test_solve_part_1() {
         let input = fs::read_to_string(PathBuf::from("resources/tests/12")).unwrap();
-        let puzzle = Puzzle12::create(&input);
+        let puzzle = Puzzle12.create(&input).unwrap();
         assert_eq!(puzzle.solve_part_1(), "4338");
     }
 
@@ -166,7 +228,7 @@ A-b\n\
 b-d\n\
 A-end\n\
 b-end";
-        let puzzle = Puzzle12::create(&input);
+        let puzzle = Puzzle12.create(input).unwrap();
         assert_eq!(puzzle.solve_part_2(), "36");
     }
 
@@ -182,7 +244,7 @@ HN-end\n\
 kj-sa\n\
 kj-HN\n\
 kj-dc";
-        let puzzle = Puzzle12::create(&input);
+        let puzzle = Puzzle12.create(input).unwrap();
         assert_eq!(puzzle.solve_part_2(), "103");
     }
 
@@ -206,14 +268,22 @@ he-WI\n\
 zg-he\n\
 pj-fs\n\
 start-RW";
-        let puzzle = Puzzle12::create(&input);
+        let puzzle = Puzzle12.create(input).unwrap();
         assert_eq!(puzzle.solve_part_2(), "3509");
     }
 
     #[test]
     fn test_solve_part_2() {
         let input = fs::read_to_string(PathBuf::from("resources/tests/12")).unwrap();
-        let puzzle = Puzzle12::create(&input);
+        let puzzle = Puzzle12.create(&input).unwrap();
         assert_eq!(puzzle.solve_part_2(), "114189");
     }
+
+    #[test]
+    fn test_timed_out() {
+        let input = fs::read_to_string(PathBuf::from("resources/tests/12")).unwrap();
+        let puzzle = Puzzle12.create(&input).unwrap();
+        let ctx = SolveContext::new().with_timeout(Duration::ZERO);
+        assert_eq!(puzzle.solve_part_2_with(&ctx).unwrap_err(), "Timed out");
+    }
 }
@@ -1,20 +1,74 @@
+use crate::image_export::Image;
 use crate::letter_ocr::ocr_image;
-use crate::puzzle::AbstractPuzzle;
+use crate::parsing::{self, Input};
+use crate::puzzle::{AbstractPuzzle, Result};
 use std::collections::HashSet;
 
-pub struct Puzzle13 {
+#[derive(Clone, Copy)]
+pub struct Puzzle13;
+
+pub struct Manual {
     points: HashSet<(usize, usize)>,
     instructions: Vec<(char, usize)>,
 }
 
 impl AbstractPuzzle for Puzzle13 {
+    type Parsed = Manual;
+
     fn get_day(&self) -> u8 {
         13
     }
 
-    fn solve_part_1(&self) -> String {
-        let mut points = self.points.clone();
-        let instruction = self.instructions[0];
+    fn get_title(&self) -> &'static str {
+        "Transparent Origami"
+    }
+
+    fn get_part_descriptions(&self) -> [&'static str; 2] {
+        [
+            "Count the dots after the first fold",
+            "Read the code after every fold",
+        ]
+    }
+
+    fn parse(&self, input: &str) -> Result<Manual> {
+        let input = Input::new(input);
+        let (points, instructions) = match &input.sections()[..] {
+            [points, instructions] => (points.clone(), instructions.clone()),
+            _ => return Err("The input must have points and fold instructions".to_string()),
+        };
+        let points = points
+            .into_iter()
+            .map(|line| {
+                let (x, y) = input.split_pair(line, ",", "point")?;
+                Ok((input.parse(x, "coordinate")?, input.parse(y, "coordinate")?))
+            })
+            .collect::<parsing::Result<HashSet<(usize, usize)>>>()?;
+        let instructions = instructions
+            .into_iter()
+            .map(|line| {
+                let invalid = || input.error(line, format!("Invalid fold instruction '{}'", line));
+                let (axis, location) = line
+                    .strip_prefix("fold along ")
+                    .and_then(|line| line.split_once('='))
+                    .ok_or_else(invalid)?;
+                match axis {
+                    "x" | "y" => Ok((
+                        axis.chars().next().unwrap(),
+                        input.parse(location, "fold line")?,
+                    )),
+                    _ => Err(invalid()),
+                }
+            })
+            .collect::<parsing::Result<Vec<(char, usize)>>>()?;
+        Ok(Manual {
+            points,
+            instructions,
+        })
+    }
+
+    fn solve_part_1(&self, manual: &Manual) -> String {
+        let mut points = manual.points.clone();
+        let instruction = manual.instructions[0];
         if instruction.0 == 'x' {
             fold_x(&mut points, instruction.1);
         } else {
@@ -23,7 +77,23 @@ impl AbstractPuzzle for Puzzle13 {
         points.len().to_string()
     }
 
-    fn solve_part_2(&self) -> String {
+    fn solve_part_2(&self, manual: &Manual) -> String {
+        ocr_image(&to_image(&manual.folded()))
+    }
+
+    fn images(&self, manual: &Manual) -> Vec<(String, Image)> {
+        vec![
+            ("paper".to_string(), Image::Bitmap(to_image(&manual.points))),
+            (
+                "folded".to_string(),
+                Image::Bitmap(to_image(&manual.folded())),
+            ),
+        ]
+    }
+}
+
+impl Manual {
+    fn folded(&self) -> HashSet<(usize, usize)> {
         let mut points = self.points.clone();
         for instruction in &self.instructions {
             if instruction.0 == 'x' {
@@ -32,8 +102,7 @@ impl AbstractPuzzle for Puzzle13 {
                 fold_y(&mut points, instruction.1);
             }
         }
-        let image = to_image(&points);
-        ocr_image(&image)
+        points
     }
 }
 
@@ -62,50 +131,62 @@ fn to_image(grid: &HashSet<(usize, usize)>) -> Vec<Vec<bool>> {
     let max_x = grid.iter().map(|(x, _)| x).max().unwrap();
     let min_y = grid.iter().map(|(_, y)| y).min().unwrap();
     let max_y = grid.iter().map(|(_, y)| y).max().unwrap();
-    let mut image = vec![vec![false; (max_x - min_x + 1) as usize]; (max_y - min_y + 1) as usize];
+    let mut image = vec![vec![false; max_x - min_x + 1]; max_y - min_y + 1];
     for point in grid {
-        image[(point.1 - min_y) as usize][(point.0 - min_x) as usize] = true;
+        image[point.1 - min_y][point.0 - min_x] = true;
     }
     image
 }
 
-impl Puzzle13 {
-    pub fn create(input: &str) -> Box<dyn AbstractPuzzle> {
-        let parts = input.split("\n\n").collect::<Vec<&str>>();
-        let points = parts[0]
-            .lines()
-            .map(|line| {
-                let parts = line.split(',').collect::<Vec<&str>>();
-                (
-                    parts[0].parse::<usize>().unwrap(),
-                    parts[1].parse::<usize>().unwrap(),
-                )
-            })
-            .collect::<HashSet<(usize, usize)>>();
-        let instructions = parts[1]
-            .lines()
-            .map(|line| {
-                let line = line.replace("fold along ", "");
-                let parts = line.split('=').collect::<Vec<&str>>();
-                (
-                    parts[0].chars().next().unwrap(),
-                    parts[1].parse::<usize>().unwrap(),
-                )
-            })
-            .collect::<Vec<(char, usize)>>();
-        Box::new(Puzzle13 {
-            points,
-            instructions,
-        })
-    }
-}
-
 #[cfg(test)]
 mod tests {
+    use crate::letter_ocr::{render_text, FontSize};
+    use crate::puzzle::DynamicPuzzle;
     use crate::puzzle13::Puzzle13;
+    use rand::Rng;
+    use std::collections::HashSet;
     use std::fs;
     use std::path::PathBuf;
 
+    // Hides the image in a larger sheet of paper by unfolding it at random, and returns the puzzle
+    // input that folds it back up
+    fn fold_encode(image: &[Vec<bool>], folds: usize) -> String {
+        let mut rng = rand::thread_rng();
+        let mut points = HashSet::new();
+        for (y, row) in image.iter().enumerate() {
+            for (x, &point) in row.iter().enumerate() {
+                if point {
+                    points.insert((x, y));
+                }
+            }
+        }
+        let mut instructions = Vec::new();
+        for _ in 0..folds {
+            let axis = if rng.gen_bool(0.5) { 'x' } else { 'y' };
+            let max = points
+                .iter()
+                .map(|&(x, y)| if axis == 'x' { x } else { y })
+                .max()
+                .unwrap();
+            let location = max + rng.gen_range(1..4);
+            points = points
+                .into_iter()
+                .map(|(x, y)| match (axis, rng.gen_bool(0.5)) {
+                    ('x', true) => (2 * location - x, y),
+                    ('y', true) => (x, 2 * location - y),
+                    _ => (x, y),
+                })
+                .collect();
+            instructions.push(format!("fold along {}={}", axis, location));
+        }
+        instructions.reverse();
+        let points = points
+            .iter()
+            .map(|(x, y)| format!("{},{}", x, y))
+            .collect::<Vec<_>>();
+        format!("{}\n\n{}", points.join("\n"), instructions.join("\n"))
+    }
+
     #[test]
     fn test_part_1_example_1() {
         let input = "6,10\n\
@@ -129,21 +210,36 @@ mod tests {
 \n\
 fold along y=7\n\
 fold along x=5";
-        let puzzle = Puzzle13::create(&input);
+        let puzzle = Puzzle13.create(input).unwrap();
         assert_eq!(puzzle.solve_part_1(), "17");
     }
 
     #[test]
     fn test_solve_part_1() {
         let input = fs::read_to_string(PathBuf::from("resources/tests/13")).unwrap();
-        let puzzle = Puzzle13::create(&input);
+        let puzzle = Puzzle13.create(&input).unwrap();
         assert_eq!(puzzle.solve_part_1(), "655");
     }
 
     #[test]
     fn test_solve_part_2() {
         let input = fs::read_to_string(PathBuf::from("resources/tests/13")).unwrap();
-        let puzzle = Puzzle13::create(&input);
+        let puzzle = Puzzle13.create(&input).unwrap();
         assert_eq!(puzzle.solve_part_2(), "JPZCUAUR");
     }
+
+    #[test]
+    fn test_part_2_fold_encoded_words() {
+        let alphabet = "ABCDEFGHIJKLMNOPQRSTUVWXYZ".chars().collect::<Vec<_>>();
+        let mut rng = rand::thread_rng();
+        for _ in 0..20 {
+            let word = (0..rng.gen_range(1..=8))
+                .map(|_| alphabet[rng.gen_range(0..alphabet.len())])
+                .collect::<String>();
+            let image = render_text(&word, FontSize::Small).unwrap();
+            let input = fold_encode(&image, rng.gen_range(1..=12));
+            let puzzle = Puzzle13.create(&input).unwrap();
+            assert_eq!(puzzle.solve_part_2(), word, "{}", input);
+        }
+    }
 }
@@ -1,45 +1,95 @@
-use crate::puzzle::AbstractPuzzle;
+use crate::parsing::{self, Input};
+use crate::puzzle::{AbstractPuzzle, Result};
+use crate::solve_context::SolveContext;
 use std::collections::HashMap;
 
-pub struct Puzzle14 {
+#[derive(Clone, Copy)]
+pub struct Puzzle14;
+
+pub struct Polymer {
     template: String,
     rules: HashMap<String, String>,
 }
 
 impl AbstractPuzzle for Puzzle14 {
+    type Parsed = Polymer;
+
     fn get_day(&self) -> u8 {
         14
     }
 
-    fn solve_part_1(&self) -> String {
-        let mut counter = self.make_counter();
-        for _ in 0..10 {
-            counter = self.do_step(&counter);
-        }
-        self.score(&counter).to_string()
+    fn get_title(&self) -> &'static str {
+        "Extended Polymerization"
     }
 
-    fn solve_part_2(&self) -> String {
-        let mut counter = self.make_counter();
-        for _ in 0..40 {
-            counter = self.do_step(&counter);
-        }
-        self.score(&counter).to_string()
+    fn get_part_descriptions(&self) -> [&'static str; 2] {
+        [
+            "Subtract the least from the most common element after 10 steps",
+            "Subtract the least from the most common element after 40 steps",
+        ]
     }
-}
 
-impl Puzzle14 {
-    pub fn create(input: &str) -> Box<dyn AbstractPuzzle> {
-        let parts = input.trim().split("\n\n").collect::<Vec<&str>>();
-        let template = parts[0].to_string();
-        let rules = parts[1]
-            .split('\n')
+    fn parse(&self, input: &str) -> Result<Polymer> {
+        let input = Input::new(input);
+        let (template, rules) = match &input.sections()[..] {
+            [template, rules] if template.len() == 1 => (template[0], rules.clone()),
+            _ => return Err("The input must have a template and insertion rules".to_string()),
+        };
+        let is_element = |c: char| c.is_ascii_uppercase();
+        if template.len() < 2 || !template.chars().all(is_element) {
+            return Err(input
+                .error(template, format!("Invalid template '{}'", template))
+                .into());
+        }
+        let rules = rules
+            .into_iter()
             .map(|line| {
-                let parts = line.split(" -> ").collect::<Vec<&str>>();
-                (parts[0].to_string(), parts[1].to_string())
+                let (pair, element) = input.rule(line, "insertion rule")?;
+                if pair.len() == 2
+                    && element.len() == 1
+                    && pair.chars().chain(element.chars()).all(is_element)
+                {
+                    Ok((pair.to_string(), element.to_string()))
+                } else {
+                    Err(input.error(line, format!("Invalid insertion rule '{}'", line)))
+                }
             })
-            .collect::<HashMap<String, String>>();
-        Box::new(Puzzle14 { template, rules })
+            .collect::<parsing::Result<HashMap<String, String>>>()?;
+        Ok(Polymer {
+            template: template.to_string(),
+            rules,
+        })
+    }
+
+    fn solve_part_1(&self, polymer: &Polymer) -> String {
+        self.solve_part_1_with(polymer, &SolveContext::new())
+            .unwrap()
+    }
+
+    fn solve_part_2(&self, polymer: &Polymer) -> String {
+        self.solve_part_2_with(polymer, &SolveContext::new())
+            .unwrap()
+    }
+
+    fn solve_part_1_with(&self, polymer: &Polymer, ctx: &SolveContext) -> Result<String> {
+        Ok(polymer.grow(10, ctx)?.to_string())
+    }
+
+    fn solve_part_2_with(&self, polymer: &Polymer, ctx: &SolveContext) -> Result<String> {
+        Ok(polymer.grow(40, ctx)?.to_string())
+    }
+}
+
+impl Polymer {
+    // Applies the rules for the given number of steps and scores the result, checking whether to
+    // give up between steps as a step takes longer the more rules there are
+    fn grow(&self, steps: usize, ctx: &SolveContext) -> Result<u64> {
+        let mut counter = self.make_counter();
+        for _ in 0..steps {
+            ctx.check()?;
+            counter = self.do_step(&counter);
+        }
+        Ok(self.score(&counter))
     }
 
     fn make_counter(&self) -> HashMap<String, u64> {
@@ -79,7 +129,9 @@ impl Puzzle14 {
 
 #[cfg(test)]
 mod tests {
+    use crate::puzzle::DynamicPuzzle;
     use crate::puzzle14::Puzzle14;
+    use crate::solve_context::SolveContext;
     use std::fs;
     use std::path::PathBuf;
 
@@ -103,14 +155,14 @@ BB -> N\n\
 BC -> B\n\
 CC -> N\n\
 CN -> C";
-        let puzzle = Puzzle14::create(&input);
+        let puzzle = Puzzle14.create(input).unwrap();
         assert_eq!(puzzle.solve_part_1(), "1588");
     }
 
     #[test]
     fn test_solve_part_1() {
         let input = fs::read_to_string(PathBuf::from("resources/tests/14")).unwrap();
-        let puzzle = Puzzle14::create(&input);
+        let puzzle = Puzzle14.create(&input).unwrap();
         assert_eq!(puzzle.solve_part_1(), "3247");
     }
 
@@ -134,14 +186,24 @@ BB -> N\n\
 BC -> B\n\
 CC -> N\n\
 CN -> C";
-        let puzzle = Puzzle14::create(&input);
+        let puzzle = Puzzle14.create(input).unwrap();
         assert_eq!(puzzle.solve_part_2(), "2188189693529");
     }
 
     #[test]
     fn test_solve_part_2() {
         let input = fs::read_to_string(PathBuf::from("resources/tests/14")).unwrap();
-        let puzzle = Puzzle14::create(&input);
+        let puzzle = Puzzle14.create(&input).unwrap();
         assert_eq!(puzzle.solve_part_2(), "4110568157153");
     }
+
+    #[test]
+    fn test_cancelled() {
+        let input = fs::read_to_string(PathBuf::from("resources/tests/14")).unwrap();
+        let puzzle = Puzzle14.create(&input).unwrap();
+        let ctx = SolveContext::new();
+        ctx.cancel();
+        assert_eq!(puzzle.solve_part_1_with(&ctx).unwrap_err(), "Cancelled");
+        assert_eq!(puzzle.solve_part_2_with(&ctx).unwrap_err(), "Cancelled");
+    }
 }
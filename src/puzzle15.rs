@@ -1,74 +1,97 @@
-use crate::puzzle::AbstractPuzzle;
+use crate::grid::Grid;
+use crate::image_export::Image;
+use crate::puzzle::{AbstractPuzzle, Result};
+use crate::solve_context::SolveContext;
 use std::collections::BinaryHeap;
 
-pub struct Puzzle15 {
-    maze: Vec<Vec<i32>>,
-}
+// Whether to give up is only checked once every 1024 positions visited (when the count masked by
+// this is zero), as checking reads the clock
+const CHECK_MASK: usize = 1023;
+
+#[derive(Clone, Copy)]
+pub struct Puzzle15;
 
 impl AbstractPuzzle for Puzzle15 {
+    // The risk level of each position in the cave
+    type Parsed = Grid<i32>;
+
     fn get_day(&self) -> u8 {
         15
     }
 
-    fn solve_part_1(&self) -> String {
-        minimum_risk(&self.maze).to_string()
+    fn get_title(&self) -> &'static str {
+        "Chiton"
     }
 
-    fn solve_part_2(&self) -> String {
-        minimum_risk(&self.expanded_maze()).to_string()
+    fn get_part_descriptions(&self) -> [&'static str; 2] {
+        [
+            "Find the lowest total risk of a path through the cave",
+            "Find the lowest total risk of a path through the full cave",
+        ]
     }
-}
 
-impl Puzzle15 {
-    pub fn create(input: &str) -> Box<dyn AbstractPuzzle> {
-        let maze = input
-            .lines()
-            .map(|line| {
-                line.chars()
-                    .map(|c| c.to_digit(10).unwrap() as i32)
-                    .collect::<Vec<i32>>()
-            })
-            .collect::<Vec<Vec<i32>>>();
-        Box::new(Puzzle15 { maze })
+    fn parse(&self, input: &str) -> Result<Grid<i32>> {
+        Grid::parse(input, |c| match c.to_digit(10) {
+            Some(risk) if risk > 0 => Ok(risk as i32),
+            _ => Err(format!("Invalid risk level '{}'", c)),
+        })
     }
 
-    fn expanded_maze(&self) -> Vec<Vec<i32>> {
-        let height = self.maze.len();
-        let width = self.maze[0].len();
-        (0..(5 * self.maze.len()))
-            .map(|y| {
-                (0..(5 * self.maze[0].len()))
-                    .map(|x| {
-                        (self.maze[y % height][x % width]
-                            + (x / width) as i32
-                            + (y / height) as i32
-                            - 1)
-                            % 9
-                            + 1
-                    })
-                    .collect::<Vec<i32>>()
-            })
-            .collect()
+    fn solve_part_1(&self, maze: &Grid<i32>) -> String {
+        self.solve_part_1_with(maze, &SolveContext::new()).unwrap()
+    }
+
+    fn solve_part_2(&self, maze: &Grid<i32>) -> String {
+        self.solve_part_2_with(maze, &SolveContext::new()).unwrap()
+    }
+
+    fn solve_part_1_with(&self, maze: &Grid<i32>, ctx: &SolveContext) -> Result<String> {
+        Ok(minimum_risk(maze, ctx)?.to_string())
+    }
+
+    fn solve_part_2_with(&self, maze: &Grid<i32>, ctx: &SolveContext) -> Result<String> {
+        Ok(minimum_risk(&expanded_maze(maze), ctx)?.to_string())
+    }
+
+    fn images(&self, maze: &Grid<i32>) -> Vec<(String, Image)> {
+        let to_image = |maze: &Grid<i32>| Image::Graymap(maze.map(|&risk| risk as u32).to_rows());
+        vec![
+            ("risk".to_string(), to_image(maze)),
+            ("expanded-risk".to_string(), to_image(&expanded_maze(maze))),
+        ]
     }
 }
 
-fn minimum_risk(maze: &[Vec<i32>]) -> i32 {
-    let height = maze.len();
-    let width = maze[0].len();
-    let mut dist = vec![vec![(10 * height * width) as i32; width]; height];
+fn expanded_maze(maze: &Grid<i32>) -> Grid<i32> {
+    let height = maze.height();
+    let width = maze.width();
+    Grid::from_fn(5 * width, 5 * height, |x, y| {
+        (maze[(x % width, y % height)] + (x / width) as i32 + (y / height) as i32 - 1) % 9 + 1
+    })
+}
+
+fn minimum_risk(maze: &Grid<i32>, ctx: &SolveContext) -> Result<i32> {
+    let height = maze.height();
+    let width = maze.width();
+    let mut dist = Grid::new(width, height, (10 * height * width) as i32);
     let mut queue = BinaryHeap::new();
     queue.push((0, 0, 0));
+    let mut visited = 0;
     while let Some((cost, x, y)) = queue.pop() {
+        if visited & CHECK_MASK == 0 {
+            ctx.check()?;
+        }
+        visited += 1;
         if x == width - 1 && y == height - 1 {
-            return -cost;
+            return Ok(-cost);
         }
-        if dist[y][x] < -cost {
+        if dist[(x, y)] < -cost {
             continue;
         }
-        for (i, j) in adjacent(x, y, maze) {
-            let new_cost = -cost + maze[j][i];
-            if new_cost < dist[j][i] {
-                dist[j][i] = new_cost;
+        for (i, j) in maze.neighbours_4(x, y) {
+            let new_cost = -cost + maze[(i, j)];
+            if new_cost < dist[(i, j)] {
+                dist[(i, j)] = new_cost;
                 queue.push((-new_cost, i, j));
             }
         }
@@ -76,28 +99,14 @@ fn minimum_risk(maze: &[Vec<i32>]) -> i32 {
     unreachable!()
 }
 
-fn adjacent(x: usize, y: usize, maze: &[Vec<i32>]) -> Vec<(usize, usize)> {
-    let mut adjacent = Vec::new();
-    if x > 0 {
-        adjacent.push((x - 1, y));
-    }
-    if x < maze[0].len() - 1 {
-        adjacent.push((x + 1, y));
-    }
-    if y > 0 {
-        adjacent.push((x, y - 1));
-    }
-    if y < maze.len() - 1 {
-        adjacent.push((x, y + 1));
-    }
-    adjacent
-}
-
 #[cfg(test)]
 mod tests {
+    use crate::puzzle::DynamicPuzzle;
     use crate::puzzle15::Puzzle15;
+    use crate::solve_context::SolveContext;
     use std::fs;
     use std::path::PathBuf;
+    use std::time::Duration;
 
     #[test]
     fn test_part_1_example_1() {
@@ -111,14 +120,14 @@ mod tests {
 3125421639\n\
 1293138521\n\
 2311944581";
-        let puzzle = Puzzle15::create(&input);
+        let puzzle = Puzzle15.create(input).unwrap();
         assert_eq!(puzzle.solve_part_1(), "40");
     }
 
     #[test]
     fn test_solve_part_1() {
         let input = fs::read_to_string(PathBuf::from("resources/tests/15")).unwrap();
-        let puzzle = Puzzle15::create(&input);
+        let puzzle = Puzzle15.create(&input).unwrap();
         assert_eq!(puzzle.solve_part_1(), "458");
     }
 
@@ -134,14 +143,23 @@ mod tests {
 3125421639\n\
 1293138521\n\
 2311944581";
-        let puzzle = Puzzle15::create(&input);
+        let puzzle = Puzzle15.create(input).unwrap();
         assert_eq!(puzzle.solve_part_2(), "315");
     }
 
     #[test]
     fn test_solve_part_2() {
         let input = fs::read_to_string(PathBuf::from("resources/tests/15")).unwrap();
-        let puzzle = Puzzle15::create(&input);
+        let puzzle = Puzzle15.create(&input).unwrap();
         assert_eq!(puzzle.solve_part_2(), "2800");
     }
+
+    #[test]
+    fn test_timed_out() {
+        let input = fs::read_to_string(PathBuf::from("resources/tests/15")).unwrap();
+        let puzzle = Puzzle15.create(&input).unwrap();
+        let ctx = SolveContext::new().with_timeout(Duration::ZERO);
+        assert_eq!(puzzle.solve_part_1_with(&ctx).unwrap_err(), "Timed out");
+        assert_eq!(puzzle.solve_part_2_with(&ctx).unwrap_err(), "Timed out");
+    }
 }
@@ -1,32 +1,37 @@
-use crate::puzzle::AbstractPuzzle;
+use crate::puzzle::{AbstractPuzzle, Result};
 
-pub struct Puzzle16 {
-    input: Vec<bool>,
-}
+#[derive(Clone, Copy)]
+pub struct Puzzle16;
 
 impl AbstractPuzzle for Puzzle16 {
+    // The outermost packet of the transmission
+    type Parsed = Packet;
+
     fn get_day(&self) -> u8 {
         16
     }
 
-    fn solve_part_1(&self) -> String {
-        let mut input = self.input.clone();
-        let packet = parse_packet(&mut input);
-        version_sum(&packet).to_string()
+    fn get_title(&self) -> &'static str {
+        "Packet Decoder"
     }
 
-    fn solve_part_2(&self) -> String {
-        let mut input = self.input.clone();
-        let packet = parse_packet(&mut input);
-        evaluate(&packet).to_string()
+    fn get_part_descriptions(&self) -> [&'static str; 2] {
+        [
+            "Add up the version numbers of the packets",
+            "Evaluate the outermost packet",
+        ]
     }
-}
 
-impl Puzzle16 {
-    pub fn create(input: &str) -> Box<dyn AbstractPuzzle> {
-        Box::new(Puzzle16 {
-            input: parse_input(input),
-        })
+    fn parse(&self, input: &str) -> Result<Packet> {
+        parse_packet(&mut parse_input(input)?)
+    }
+
+    fn solve_part_1(&self, packet: &Packet) -> String {
+        version_sum(packet).to_string()
+    }
+
+    fn solve_part_2(&self, packet: &Packet) -> String {
+        evaluate(packet).to_string()
     }
 }
 
@@ -39,7 +44,7 @@ fn version_sum(packet: &Packet) -> u64 {
 }
 
 fn evaluate(packet: &Packet) -> u64 {
-    return match packet.type_id {
+    match packet.type_id {
         0 => packet.sub_packets.iter().map(evaluate).sum(),
         1 => packet.sub_packets.iter().map(evaluate).product(),
         2 => packet.sub_packets.iter().map(evaluate).min().unwrap(),
@@ -49,10 +54,10 @@ fn evaluate(packet: &Packet) -> u64 {
         6 => (evaluate(&packet.sub_packets[0]) < evaluate(&packet.sub_packets[1])) as u64,
         7 => (evaluate(&packet.sub_packets[0]) == evaluate(&packet.sub_packets[1])) as u64,
         _ => panic!("Unknown packet type"),
-    };
+    }
 }
 
-fn parse_input(input: &str) -> Vec<bool> {
+fn parse_input(input: &str) -> Result<Vec<bool>> {
     let mut binary_input: Vec<bool> = Vec::new();
     for c1 in input.trim().chars() {
         match c1 {
@@ -72,125 +77,129 @@ fn parse_input(input: &str) -> Vec<bool> {
             'D' => binary_input.extend(vec![true, true, false, true]),
             'E' => binary_input.extend(vec![true, true, true, false]),
             'F' => binary_input.extend(vec![true, true, true, true]),
-            _ => panic!("Invalid input"),
+            _ => return Err(format!("Invalid hexadecimal digit '{}'", c1)),
         }
     }
-    binary_input
+    Ok(binary_input)
 }
 
-struct Packet {
+pub struct Packet {
     version: u8,
     type_id: u8,
     payload: u64,
     sub_packets: Vec<Packet>,
 }
 
-fn parse_packet(input: &mut Vec<bool>) -> Packet {
-    let version = read_bits(input, 3) as u8;
-    let type_id = read_bits(input, 3) as u8;
+fn parse_packet(input: &mut Vec<bool>) -> Result<Packet> {
+    let version = read_bits(input, 3)? as u8;
+    let type_id = read_bits(input, 3)? as u8;
     if type_id == 4 {
         let mut payload: u64 = 0;
         loop {
-            let x = read_bits(input, 5) as u8;
+            let x = read_bits(input, 5)? as u8;
             payload <<= 4;
             payload |= (x & 15) as u64;
             if (x & 16) == 0 {
                 break;
             }
         }
-        Packet {
+        Ok(Packet {
             version,
             type_id,
             payload,
             sub_packets: Vec::new(),
-        }
+        })
     } else {
-        let length_type_id = read_bits(input, 1) == 1;
+        let length_type_id = read_bits(input, 1)? == 1;
         if length_type_id {
-            let payload = read_bits(input, 11);
+            let payload = read_bits(input, 11)?;
             let mut sub_packets: Vec<Packet> = Vec::new();
             for _ in 0..payload {
-                sub_packets.push(parse_packet(input));
+                sub_packets.push(parse_packet(input)?);
             }
-            Packet {
+            Ok(Packet {
                 version,
                 type_id,
                 payload,
                 sub_packets,
-            }
+            })
         } else {
-            let payload = read_bits(input, 15);
+            let payload = read_bits(input, 15)?;
             let mut payload_bits: Vec<bool> = Vec::new();
             for _ in 0..payload {
-                payload_bits.push(input.remove(0));
+                payload_bits.push(read_bits(input, 1)? == 1);
             }
             let mut sub_packets: Vec<Packet> = Vec::new();
             while !payload_bits.is_empty() {
-                sub_packets.push(parse_packet(&mut payload_bits));
+                sub_packets.push(parse_packet(&mut payload_bits)?);
             }
-            Packet {
+            Ok(Packet {
                 version,
                 type_id,
                 payload,
                 sub_packets,
-            }
+            })
         }
     }
 }
 
-fn read_bits(input: &mut Vec<bool>, size: usize) -> u64 {
+fn read_bits(input: &mut Vec<bool>, size: usize) -> Result<u64> {
     let mut result: u64 = 0;
     for _ in 0..size {
+        if input.is_empty() {
+            return Err("The packet ends unexpectedly".to_string());
+        }
         result <<= 1;
         if input.remove(0) {
             result |= 1;
         }
     }
-    result
+    Ok(result)
 }
 
 #[cfg(test)]
 mod tests {
+    use crate::puzzle::DynamicPuzzle;
     use crate::puzzle16::{parse_input, parse_packet, Puzzle16};
     use std::fs;
     use std::path::PathBuf;
 
     #[test]
     fn test_single_digit_input_parsing() {
-        assert_eq!(parse_input("0"), vec![false, false, false, false]);
-        assert_eq!(parse_input("1"), vec![false, false, false, true]);
-        assert_eq!(parse_input("2"), vec![false, false, true, false]);
-        assert_eq!(parse_input("3"), vec![false, false, true, true]);
-        assert_eq!(parse_input("4"), vec![false, true, false, false]);
-        assert_eq!(parse_input("5"), vec![false, true, false, true]);
-        assert_eq!(parse_input("6"), vec![false, true, true, false]);
-        assert_eq!(parse_input("7"), vec![false, true, true, true]);
-        assert_eq!(parse_input("8"), vec![true, false, false, false]);
-        assert_eq!(parse_input("9"), vec![true, false, false, true]);
-        assert_eq!(parse_input("A"), vec![true, false, true, false]);
-        assert_eq!(parse_input("B"), vec![true, false, true, true]);
-        assert_eq!(parse_input("C"), vec![true, true, false, false]);
-        assert_eq!(parse_input("D"), vec![true, true, false, true]);
-        assert_eq!(parse_input("E"), vec![true, true, true, false]);
-        assert_eq!(parse_input("F"), vec![true, true, true, true]);
+        assert_eq!(parse_input("0").unwrap(), vec![false, false, false, false]);
+        assert_eq!(parse_input("1").unwrap(), vec![false, false, false, true]);
+        assert_eq!(parse_input("2").unwrap(), vec![false, false, true, false]);
+        assert_eq!(parse_input("3").unwrap(), vec![false, false, true, true]);
+        assert_eq!(parse_input("4").unwrap(), vec![false, true, false, false]);
+        assert_eq!(parse_input("5").unwrap(), vec![false, true, false, true]);
+        assert_eq!(parse_input("6").unwrap(), vec![false, true, true, false]);
+        assert_eq!(parse_input("7").unwrap(), vec![false, true, true, true]);
+        assert_eq!(parse_input("8").unwrap(), vec![true, false, false, false]);
+        assert_eq!(parse_input("9").unwrap(), vec![true, false, false, true]);
+        assert_eq!(parse_input("A").unwrap(), vec![true, false, true, false]);
+        assert_eq!(parse_input("B").unwrap(), vec![true, false, true, true]);
+        assert_eq!(parse_input("C").unwrap(), vec![true, true, false, false]);
+        assert_eq!(parse_input("D").unwrap(), vec![true, true, false, true]);
+        assert_eq!(parse_input("E").unwrap(), vec![true, true, true, false]);
+        assert_eq!(parse_input("F").unwrap(), vec![true, true, true, true]);
     }
 
     #[test]
     fn test_double_digit_input_parsing() {
         assert_eq!(
-            parse_input("00"),
+            parse_input("00").unwrap(),
             vec![false, false, false, false, false, false, false, false]
         );
         assert_eq!(
-            parse_input("22"),
+            parse_input("22").unwrap(),
             vec![false, false, true, false, false, false, true, false]
         );
     }
 
     #[test]
     fn test_example_packet_1() {
-        let mut input = parse_input("D2FE28");
-        let packet = parse_packet(&mut input);
+        let mut input = parse_input("D2FE28").unwrap();
+        let packet = parse_packet(&mut input).unwrap();
         assert_eq!(packet.version, 6);
         assert_eq!(packet.type_id, 4);
         assert_eq!(packet.payload, 2021);
@@ -198,8 +207,8 @@ mod tests {
 
     #[test]
     fn test_example_packet_2() {
-        let mut input = parse_input("38006F45291200");
-        let packet = parse_packet(&mut input);
+        let mut input = parse_input("38006F45291200").unwrap();
+        let packet = parse_packet(&mut input).unwrap();
         assert_eq!(packet.version, 1);
         assert_eq!(packet.type_id, 6);
         assert_eq!(packet.payload, 27);
@@ -212,8 +221,8 @@ mod tests {
 
     #[test]
     fn test_example_packet_3() {
-        let mut input = parse_input("EE00D40C823060");
-        let packet = parse_packet(&mut input);
+        let mut input = parse_input("EE00D40C823060").unwrap();
+        let packet = parse_packet(&mut input).unwrap();
         assert_eq!(packet.version, 7);
         assert_eq!(packet.type_id, 3);
         assert_eq!(packet.payload, 3);
@@ -228,8 +237,8 @@ mod tests {
 
     #[test]
     fn test_example_packet_4() {
-        let mut input = parse_input("8A004A801A8002F478");
-        let packet = parse_packet(&mut input);
+        let mut input = parse_input("8A004A801A8002F478").unwrap();
+        let packet = parse_packet(&mut input).unwrap();
         assert_eq!(packet.version, 4);
         assert_ne!(packet.type_id, 4);
         assert_eq!(packet.sub_packets.len(), 1);
@@ -257,8 +266,8 @@ mod tests {
 
     #[test]
     fn test_example_packet_5() {
-        let mut input = parse_input("620080001611562C8802118E34");
-        let packet = parse_packet(&mut input);
+        let mut input = parse_input("620080001611562C8802118E34").unwrap();
+        let packet = parse_packet(&mut input).unwrap();
         assert_eq!(packet.version, 3);
         assert_ne!(packet.type_id, 4);
         assert_eq!(packet.sub_packets.len(), 2);
@@ -271,7 +280,7 @@ mod tests {
         assert!(packet.sub_packets[0]
             .sub_packets
             .iter()
-            .all(|p| p.sub_packets.len() == 0));
+            .all(|p| p.sub_packets.is_empty()));
         assert_ne!(packet.sub_packets[1].type_id, 4);
         assert_eq!(packet.sub_packets[1].sub_packets.len(), 2);
         assert!(packet.sub_packets[1]
@@ -281,13 +290,13 @@ mod tests {
         assert!(packet.sub_packets[1]
             .sub_packets
             .iter()
-            .all(|p| p.sub_packets.len() == 0));
+            .all(|p| p.sub_packets.is_empty()));
     }
 
     #[test]
     fn test_example_packet_6() {
-        let mut input = parse_input("C0015000016115A2E0802F182340");
-        let packet = parse_packet(&mut input);
+        let mut input = parse_input("C0015000016115A2E0802F182340").unwrap();
+        let packet = parse_packet(&mut input).unwrap();
         assert_ne!(packet.type_id, 4);
         assert_eq!(packet.sub_packets.len(), 2);
         assert_ne!(packet.sub_packets[0].type_id, 4);
@@ -299,7 +308,7 @@ mod tests {
         assert!(packet.sub_packets[0]
             .sub_packets
             .iter()
-            .all(|p| p.sub_packets.len() == 0));
+            .all(|p| p.sub_packets.is_empty()));
         assert_ne!(packet.sub_packets[1].type_id, 4);
         assert_eq!(packet.sub_packets[1].sub_packets.len(), 2);
         assert!(packet.sub_packets[1]
@@ -309,13 +318,13 @@ mod tests {
         assert!(packet.sub_packets[1]
             .sub_packets
             .iter()
-            .all(|p| p.sub_packets.len() == 0));
+            .all(|p| p.sub_packets.is_empty()));
     }
 
     #[test]
     fn test_example_packet_7() {
-        let mut input = parse_input("A0016C880162017C3686B18A3D4780");
-        let packet = parse_packet(&mut input);
+        let mut input = parse_input("A0016C880162017C3686B18A3D4780").unwrap();
+        let packet = parse_packet(&mut input).unwrap();
         assert_ne!(packet.type_id, 4);
         assert_eq!(packet.sub_packets.len(), 1);
         assert_ne!(packet.sub_packets[0].type_id, 4);
@@ -329,104 +338,104 @@ mod tests {
         assert!(packet.sub_packets[0].sub_packets[0]
             .sub_packets
             .iter()
-            .all(|p| p.sub_packets.len() == 0));
+            .all(|p| p.sub_packets.is_empty()));
     }
 
     #[test]
     fn test_part_1_example_1() {
         let input = "8A004A801A8002F478";
-        let puzzle = Puzzle16::create(&input);
+        let puzzle = Puzzle16.create(input).unwrap();
         assert_eq!(puzzle.solve_part_1(), "16");
     }
 
     #[test]
     fn test_part_1_example_2() {
         let input = "620080001611562C8802118E34";
-        let puzzle = Puzzle16::create(&input);
+        let puzzle = Puzzle16.create(input).unwrap();
         assert_eq!(puzzle.solve_part_1(), "12");
     }
 
     #[test]
     fn test_part_1_example_3() {
         let input = "C0015000016115A2E0802F182340";
-        let puzzle = Puzzle16::create(&input);
+        let puzzle = Puzzle16.create(input).unwrap();
         assert_eq!(puzzle.solve_part_1(), "23");
     }
 
     #[test]
     fn test_part_1_example_4() {
         let input = "A0016C880162017C3686B18A3D4780";
-        let puzzle = Puzzle16::create(&input);
+        let puzzle = Puzzle16.create(input).unwrap();
         assert_eq!(puzzle.solve_part_1(), "31");
     }
 
     #[test]
     fn test_solve_part_1() {
         let input = fs::read_to_string(PathBuf::from("resources/tests/16")).unwrap();
-        let puzzle = Puzzle16::create(&input);
+        let puzzle = Puzzle16.create(&input).unwrap();
         assert_eq!(puzzle.solve_part_1(), "883");
     }
 
     #[test]
     fn test_part_2_example_1() {
         let input = "C200B40A82";
-        let puzzle = Puzzle16::create(&input);
+        let puzzle = Puzzle16.create(input).unwrap();
         assert_eq!(puzzle.solve_part_2(), "3");
     }
 
     #[test]
     fn test_part_2_example_2() {
         let input = "04005AC33890";
-        let puzzle = Puzzle16::create(&input);
+        let puzzle = Puzzle16.create(input).unwrap();
         assert_eq!(puzzle.solve_part_2(), "54");
     }
 
     #[test]
     fn test_part_2_example_3() {
         let input = "880086C3E88112";
-        let puzzle = Puzzle16::create(&input);
+        let puzzle = Puzzle16.create(input).unwrap();
         assert_eq!(puzzle.solve_part_2(), "7");
     }
 
     #[test]
     fn test_part_2_example_4() {
         let input = "CE00C43D881120";
-        let puzzle = Puzzle16::create(&input);
+        let puzzle = Puzzle16.create(input).unwrap();
         assert_eq!(puzzle.solve_part_2(), "9");
     }
 
     #[test]
     fn test_part_2_example_5() {
         let input = "D8005AC2A8F0";
-        let puzzle = Puzzle16::create(&input);
+        let puzzle = Puzzle16.create(input).unwrap();
         assert_eq!(puzzle.solve_part_2(), "1");
     }
 
     #[test]
     fn test_part_2_example_6() {
         let input = "F600BC2D8F";
-        let puzzle = Puzzle16::create(&input);
+        let puzzle = Puzzle16.create(input).unwrap();
         assert_eq!(puzzle.solve_part_2(), "0");
     }
 
     #[test]
     fn test_part_2_example_7() {
         let input = "9C005AC2F8F0";
-        let puzzle = Puzzle16::create(&input);
+        let puzzle = Puzzle16.create(input).unwrap();
         assert_eq!(puzzle.solve_part_2(), "0");
     }
 
     #[test]
     fn test_part_2_example_8() {
         let input = "9C0141080250320F1802104A08";
-        let puzzle = Puzzle16::create(&input);
+        let puzzle = Puzzle16.create(input).unwrap();
         assert_eq!(puzzle.solve_part_2(), "1");
     }
 
     #[test]
     fn test_solve_part_2() {
         let input = fs::read_to_string(PathBuf::from("resources/tests/16")).unwrap();
-        let puzzle = Puzzle16::create(&input);
+        let puzzle = Puzzle16.create(&input).unwrap();
         assert_eq!(puzzle.solve_part_2(), "1675198555015");
     }
 }
@@ -1,9 +1,15 @@
-use crate::puzzle::AbstractPuzzle;
+use crate::parsing::Input;
+use crate::puzzle::{AbstractPuzzle, Result};
+use crate::solve_context::SolveContext;
 use lazy_static::lazy_static;
 use regex::Regex;
 use std::cmp::max;
 
-pub struct Puzzle17 {
+#[derive(Clone, Copy)]
+pub struct Puzzle17;
+
+// The target area, along with the range of velocities and number of steps worth trying
+pub struct TargetArea {
     x1: i32,
     y1: i32,
     x2: i32,
@@ -16,44 +22,33 @@ pub struct Puzzle17 {
 }
 
 impl AbstractPuzzle for Puzzle17 {
+    type Parsed = TargetArea;
+
     fn get_day(&self) -> u8 {
         17
     }
 
-    fn solve_part_1(&self) -> String {
-        let mut max_height = i32::MIN;
-        for dx in self.min_dx..self.max_dx {
-            for dy in self.min_dy..self.max_dy {
-                max_height = max(max_height, self.find_max_height(dx, dy));
-            }
-        }
-        max_height.to_string()
+    fn get_title(&self) -> &'static str {
+        "Trick Shot"
     }
 
-    fn solve_part_2(&self) -> String {
-        let mut count = 0;
-        for dx in self.min_dx..self.max_dx {
-            for dy in self.min_dy..self.max_dy {
-                if self.is_valid_vector(dx, dy) {
-                    count += 1;
-                }
-            }
-        }
-        count.to_string()
+    fn get_part_descriptions(&self) -> [&'static str; 2] {
+        [
+            "Find the highest position of a shot that hits the target",
+            "Count the initial velocities that hit the target",
+        ]
     }
-}
 
-impl Puzzle17 {
-    pub fn create(input: &str) -> Box<dyn AbstractPuzzle> {
+    fn parse(&self, input: &str) -> Result<TargetArea> {
         lazy_static! {
             static ref RE: Regex =
                 Regex::new(r"target area: x=(-?\d+)\.\.(-?\d+), y=(-?\d+)\.\.(-?\d+)").unwrap();
         }
-        let caps = RE.captures(input).unwrap();
-        let x1 = caps[1].parse::<i32>().unwrap();
-        let x2 = caps[2].parse::<i32>().unwrap();
-        let y1 = caps[3].parse::<i32>().unwrap();
-        let y2 = caps[4].parse::<i32>().unwrap();
+        let (x1, x2, y1, y2): (i32, i32, i32, i32) =
+            Input::new(input).captures(&RE, input, "target area")?;
+        if x1 > x2 || y1 > y2 {
+            return Err(format!("Invalid target area '{}'", input.trim()));
+        }
         let min_dx = if x1 < 0 {
             x1 - 1
         } else {
@@ -77,7 +72,7 @@ impl Puzzle17 {
             max_dy.abs() + 1
         };
         let max_steps = max(max_x_steps, max_y_steps) as u32;
-        Box::new(Puzzle17 {
+        Ok(TargetArea {
             x1,
             y1,
             x2,
@@ -90,6 +85,52 @@ impl Puzzle17 {
         })
     }
 
+    fn solve_part_1(&self, target: &TargetArea) -> String {
+        self.solve_part_1_with(target, &SolveContext::new())
+            .unwrap()
+    }
+
+    fn solve_part_2(&self, target: &TargetArea) -> String {
+        self.solve_part_2_with(target, &SolveContext::new())
+            .unwrap()
+    }
+
+    fn solve_part_1_with(&self, target: &TargetArea, ctx: &SolveContext) -> Result<String> {
+        let mut max_height = i32::MIN;
+        for dx in target.min_dx..target.max_dx {
+            target.check_progress(dx, ctx)?;
+            for dy in target.min_dy..target.max_dy {
+                max_height = max(max_height, target.find_max_height(dx, dy));
+            }
+        }
+        Ok(max_height.to_string())
+    }
+
+    fn solve_part_2_with(&self, target: &TargetArea, ctx: &SolveContext) -> Result<String> {
+        let mut count = 0;
+        for dx in target.min_dx..target.max_dx {
+            target.check_progress(dx, ctx)?;
+            for dy in target.min_dy..target.max_dy {
+                if target.is_valid_vector(dx, dy) {
+                    count += 1;
+                }
+            }
+        }
+        Ok(count.to_string())
+    }
+}
+
+impl TargetArea {
+    // Reports how far the scan of horizontal velocities has got, and whether to keep going
+    fn check_progress(&self, dx: i32, ctx: &SolveContext) -> Result<()> {
+        ctx.check()?;
+        ctx.report_progress(
+            (dx - self.min_dx) as usize,
+            (self.max_dx - self.min_dx) as usize,
+        );
+        Ok(())
+    }
+
     fn find_max_height(&self, mut dx: i32, mut dy: i32) -> i32 {
         let mut x = 0;
         let mut y = 0;
@@ -129,16 +170,57 @@ impl Puzzle17 {
     }
 }
 
+// Fires the probe with every velocity that could possibly reach the target area, which is below and
+// to the right of the probe
+#[cfg(test)]
+mod reference {
+    pub fn solve(input: &str) -> [String; 2] {
+        let numbers = input
+            .trim()
+            .trim_start_matches("target area: x=")
+            .split([',', '.', '=', ' '])
+            .filter_map(|token| token.parse::<i32>().ok())
+            .collect::<Vec<i32>>();
+        let (x1, x2, y1, y2) = (numbers[0], numbers[1], numbers[2], numbers[3]);
+        let mut max_height = i32::MIN;
+        let mut count = 0;
+        for start_dx in 0..=x2 {
+            for start_dy in y1..=-y1 {
+                let (mut x, mut y, mut dx, mut dy) = (0, 0, start_dx, start_dy);
+                let mut height = 0;
+                while x <= x2 && y >= y1 {
+                    height = height.max(y);
+                    if x >= x1 && y <= y2 {
+                        max_height = max_height.max(height);
+                        count += 1;
+                        break;
+                    }
+                    x += dx;
+                    y += dy;
+                    dx -= dx.signum();
+                    dy -= 1;
+                }
+            }
+        }
+        [max_height.to_string(), count.to_string()]
+    }
+}
+
 #[cfg(test)]
 mod tests {
+    use crate::differential::check_against_reference;
+    use crate::puzzle::DynamicPuzzle;
+    use crate::puzzle17::reference;
     use crate::puzzle17::Puzzle17;
+    use crate::solve_context::SolveContext;
     use std::fs;
     use std::path::PathBuf;
+    use std::sync::{Arc, Mutex};
 
     #[test]
     fn test_part_1_example_1() {
         let input = "target area: x=20..30, y=-10..-5";
-        let puzzle = Puzzle17::create(&input);
+        let puzzle = Puzzle17.create(input).unwrap();
         assert_eq!(puzzle.solve_part_1(), "45");
     }
 
@@ -146,28 +228,56 @@ mod tests {
     fn test_part_1_example_2() {
         // Example where maximum height won't be reached by y * (|y| - 1) / 2 due to x restrictions
         let input = "target area: x=22..27, y=-10..-5";
-        let puzzle = Puzzle17::create(&input);
+        let puzzle = Puzzle17.create(input).unwrap();
         assert_eq!(puzzle.solve_part_1(), "1");
     }
 
     #[test]
     fn test_solve_part_1() {
         let input = fs::read_to_string(PathBuf::from("resources/tests/17")).unwrap();
-        let puzzle = Puzzle17::create(&input);
+        let puzzle = Puzzle17.create(&input).unwrap();
         assert_eq!(puzzle.solve_part_1(), "7626");
     }
 
     #[test]
     fn test_part_2_example_1() {
         let input = "target area: x=20..30, y=-10..-5";
-        let puzzle = Puzzle17::create(&input);
+        let puzzle = Puzzle17.create(input).unwrap();
         assert_eq!(puzzle.solve_part_2(), "112");
     }
 
     #[test]
     fn test_solve_part_2() {
         let input = fs::read_to_string(PathBuf::from("resources/tests/17")).unwrap();
-        let puzzle = Puzzle17::create(&input);
+        let puzzle = Puzzle17.create(&input).unwrap();
         assert_eq!(puzzle.solve_part_2(), "2032");
     }
+
+    #[test]
+    fn test_progress() {
+        let reported = Arc::new(Mutex::new(Vec::new()));
+        let ctx = SolveContext::new().with_progress({
+            let reported = Arc::clone(&reported);
+            move |fraction| reported.lock().unwrap().push(fraction)
+        });
+        let puzzle = Puzzle17.create("target area: x=20..30, y=-10..-5").unwrap();
+        assert_eq!(puzzle.solve_part_2_with(&ctx).unwrap(), "112");
+        let reported = reported.lock().unwrap();
+        assert!(!reported.is_empty());
+        assert!(reported.windows(2).all(|pair| pair[0] < pair[1]));
+    }
+
+    #[test]
+    fn test_cancelled() {
+        let ctx = SolveContext::new();
+        ctx.cancel();
+        let puzzle = Puzzle17.create("target area: x=20..30, y=-10..-5").unwrap();
+        assert_eq!(puzzle.solve_part_1_with(&ctx).unwrap_err(), "Cancelled");
+        assert_eq!(puzzle.solve_part_2_with(&ctx).unwrap_err(), "Cancelled");
+    }
+
+    #[test]
+    fn test_against_reference() {
+        check_against_reference(17, &[10, 30, 100], reference::solve);
+    }
 }
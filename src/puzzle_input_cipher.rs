@@ -0,0 +1,152 @@
+use std::env;
+use std::fs;
+use std::path::Path;
+
+use chacha20poly1305::aead::{Aead, AeadCore, KeyInit, OsRng};
+use chacha20poly1305::{ChaCha20Poly1305, Key, Nonce};
+
+// Every encrypted puzzle input starts with this header so that we can tell it apart from a plain
+// text puzzle input (which will never start with it).
+const MAGIC: &[u8] = b"aoc2021-encrypted-v1\n";
+
+const KEY_LENGTH: usize = 32;
+const NONCE_LENGTH: usize = 12;
+
+// The environment variable that can hold the cache key (64 hex digits)
+pub const KEY_ENV_VAR: &str = "AOC_CACHE_KEY";
+
+pub struct PuzzleInputCipher {
+    cipher: ChaCha20Poly1305,
+}
+
+impl PuzzleInputCipher {
+    // Creates a PuzzleInputCipher from a key given as 64 hex digits
+    pub fn create(hex_key: &str) -> Result<PuzzleInputCipher> {
+        let key = decode_hex(hex_key.trim())?;
+        if key.len() != KEY_LENGTH {
+            return Err(format!(
+                "Cache key is not in the right format. Expected {} hex digits. Got {}",
+                2 * KEY_LENGTH,
+                2 * key.len()
+            ));
+        }
+        Ok(PuzzleInputCipher {
+            cipher: ChaCha20Poly1305::new(Key::from_slice(&key)),
+        })
+    }
+
+    // Loads the cache key first from the environment, and then from the given key file. Returns
+    // None if neither holds a key, as encrypting the cache is optional.
+    pub fn load(key_path: &Path) -> Result<Option<PuzzleInputCipher>> {
+        if let Ok(hex_key) = env::var(KEY_ENV_VAR) {
+            return PuzzleInputCipher::create(&hex_key).map(Some);
+        }
+        match fs::read_to_string(key_path) {
+            Ok(hex_key) => PuzzleInputCipher::create(&hex_key).map(Some),
+            Err(_) => Ok(None),
+        }
+    }
+
+    pub fn encrypt(&self, input: &str) -> Result<Vec<u8>> {
+        let nonce = ChaCha20Poly1305::generate_nonce(&mut OsRng);
+        let ciphertext = self
+            .cipher
+            .encrypt(&nonce, input.as_bytes())
+            .map_err(|e| format!("Failed to encrypt puzzle input: {}", e))?;
+        let mut data = Vec::with_capacity(MAGIC.len() + NONCE_LENGTH + ciphertext.len());
+        data.extend_from_slice(MAGIC);
+        data.extend_from_slice(&nonce);
+        data.extend_from_slice(&ciphertext);
+        Ok(data)
+    }
+
+    pub fn decrypt(&self, data: &[u8]) -> Result<String> {
+        if !is_encrypted(data) || data.len() < MAGIC.len() + NONCE_LENGTH {
+            return Err("Data is not an encrypted puzzle input".to_string());
+        }
+        let nonce = Nonce::from_slice(&data[MAGIC.len()..MAGIC.len() + NONCE_LENGTH]);
+        let plaintext = self
+            .cipher
+            .decrypt(nonce, &data[MAGIC.len() + NONCE_LENGTH..])
+            .map_err(|_| "Failed to decrypt puzzle input: wrong key or corrupt data".to_string())?;
+        String::from_utf8(plaintext)
+            .map_err(|e| format!("Failed to decrypt puzzle input: not valid UTF-8: {}", e))
+    }
+}
+
+pub fn is_encrypted(data: &[u8]) -> bool {
+    data.starts_with(MAGIC)
+}
+
+fn decode_hex(s: &str) -> Result<Vec<u8>> {
+    if !s.len().is_multiple_of(2) || !s.chars().all(|c| c.is_ascii_hexdigit()) {
+        return Err("Cache key is not in the right format. Expected hex digits".to_string());
+    }
+    (0..s.len())
+        .step_by(2)
+        .map(|i| u8::from_str_radix(&s[i..i + 2], 16).map_err(|e| e.to_string()))
+        .collect()
+}
+
+type Result<T> = std::result::Result<T, String>;
+
+#[cfg(test)]
+mod tests {
+    use crate::puzzle_input_cipher::{is_encrypted, PuzzleInputCipher};
+
+    const KEY: &str = "000102030405060708090a0b0c0d0e0f101112131415161718191a1b1c1d1e1f";
+    const OTHER_KEY: &str = "ffeeddccbbaa99887766554433221100ffeeddccbbaa99887766554433221100";
+
+    #[test]
+    fn test_round_trip() {
+        let cipher = PuzzleInputCipher::create(KEY).unwrap();
+        let input = "199\n200\n208\n210\n";
+        let data = cipher.encrypt(input).unwrap();
+        assert!(is_encrypted(&data));
+        assert!(!data.windows(input.len()).any(|w| w == input.as_bytes()));
+        assert_eq!(cipher.decrypt(&data).unwrap(), input);
+    }
+
+    #[test]
+    fn test_encryption_is_randomized() {
+        let cipher = PuzzleInputCipher::create(KEY).unwrap();
+        assert_ne!(
+            cipher.encrypt("input").unwrap(),
+            cipher.encrypt("input").unwrap()
+        );
+    }
+
+    #[test]
+    fn test_decrypt_with_wrong_key_fails() {
+        let data = PuzzleInputCipher::create(KEY)
+            .unwrap()
+            .encrypt("input")
+            .unwrap();
+        let cipher = PuzzleInputCipher::create(OTHER_KEY).unwrap();
+        assert!(cipher.decrypt(&data).is_err());
+    }
+
+    #[test]
+    fn test_decrypt_tampered_data_fails() {
+        let cipher = PuzzleInputCipher::create(KEY).unwrap();
+        let mut data = cipher.encrypt("input").unwrap();
+        let last = data.len() - 1;
+        data[last] ^= 1;
+        assert!(cipher.decrypt(&data).is_err());
+    }
+
+    #[test]
+    fn test_decrypt_plain_text_fails() {
+        let cipher = PuzzleInputCipher::create(KEY).unwrap();
+        assert!(cipher.decrypt(b"3,4,3,1,2").is_err());
+    }
+
+    #[test]
+    fn test_invalid_keys_rejected() {
+        assert!(PuzzleInputCipher::create("").is_err());
+        assert!(PuzzleInputCipher::create(&KEY[1..]).is_err());
+        assert!(PuzzleInputCipher::create(&KEY[2..]).is_err());
+        assert!(PuzzleInputCipher::create(&"X".repeat(64)).is_err());
+        assert!(PuzzleInputCipher::create(&format!("{}\n", KEY)).is_ok());
+    }
+}
@@ -1,19 +1,32 @@
+use std::env;
 use std::fs;
+use std::io::ErrorKind;
 use std::ops::Not;
 use std::path::{Path, PathBuf};
 use std::sync::{Arc, RwLock};
+use std::time::{Duration, Instant};
 
 use reqwest::StatusCode;
+use tracing::{debug, info, trace, warn};
+
+use crate::config::{Config, SessionTokenSource};
+use crate::puzzle_input_cipher::{is_encrypted, PuzzleInputCipher, KEY_ENV_VAR};
 
 pub struct PuzzleInputFetcher {
     // The base URL for Advent of Code (by default 'https://adventofcode.com/')
     base_url: String,
 
+    // The event year to fetch puzzle input for (by default 2021)
+    year: u16,
+
     // The location where puzzle input is stored (by default 'puzzle')
     input_path: PathBuf,
 
-    // The location where the session token is stored (by default 'cookie.txt')
-    session_token_path: PathBuf,
+    // Where the session token comes from (by default the file 'cookie.txt')
+    session_token_source: SessionTokenSource,
+
+    // The timeout for requests to the remote store (by default 30 seconds)
+    timeout: Duration,
 
     // Per-day lock for loading the puzzle input into our input cache
     is_input_set: Vec<Arc<RwLock<bool>>>,
@@ -26,20 +39,38 @@ pub struct PuzzleInputFetcher {
 
     // The session token cache
     session_token: String,
+
+    // The cipher used to encrypt puzzle input in the local store (if any)
+    cipher: Option<PuzzleInputCipher>,
+
+    // Whether the remote store may be used at all (it is never used in offline mode)
+    offline: bool,
 }
 
 impl PuzzleInputFetcher {
-    // Creates a PuzzleInputFetcher using the default values
-    pub fn create() -> PuzzleInputFetcher {
-        PuzzleInputFetcher::create_custom(
-            "https://adventofcode.com",
-            Path::new("puzzle"),
-            Path::new("cookie.txt"),
-        )
+    // Creates a PuzzleInputFetcher using the default values. The local store is encrypted if a
+    // cache key is found in the environment or in 'cache.key'.
+    pub fn create() -> Result<PuzzleInputFetcher> {
+        PuzzleInputFetcher::create_from_config(&Config::default())
+    }
+
+    // Creates a PuzzleInputFetcher using the given configuration. The local store is encrypted if
+    // a cache key is found in the environment or in the configured key file.
+    pub fn create_from_config(config: &Config) -> Result<PuzzleInputFetcher> {
+        let mut fetcher =
+            PuzzleInputFetcher::create_custom(&config.base_url, &config.cache_dir, Path::new(""));
+        fetcher.year = config.year;
+        fetcher.session_token_source = config.session_token.clone();
+        fetcher.timeout = config.request_timeout;
+        fetcher.offline = config.offline;
+        match PuzzleInputCipher::load(&config.cache_key_path)? {
+            Some(cipher) => Ok(fetcher.with_cipher(cipher)),
+            None => Ok(fetcher),
+        }
     }
 
     // Creates a PuzzleInputFetcher using the with a specified base url, puzzle input path, and
-    // session token path. Used only for testing.
+    // session token path. Used mostly for testing.
     pub fn create_custom(
         base_url: &str,
         input_path: &Path,
@@ -49,15 +80,33 @@ impl PuzzleInputFetcher {
         (0..25).for_each(|_| is_input_set.push(Arc::new(RwLock::new(false))));
         PuzzleInputFetcher {
             base_url: base_url.to_string(),
+            year: 2021,
             input_path: input_path.to_path_buf(),
+            session_token_source: SessionTokenSource::File(session_token_path.to_path_buf()),
+            timeout: Duration::from_secs(30),
             is_input_set,
             inputs: vec![String::new(); 25],
-            session_token_path: session_token_path.to_path_buf(),
             is_session_token_set: Arc::new(RwLock::new(false)),
             session_token: String::new(),
+            cipher: None,
+            offline: false,
         }
     }
 
+    // Encrypts puzzle input stored locally from now on, and decrypts encrypted puzzle input found
+    // in the local store.
+    pub fn with_cipher(mut self, cipher: PuzzleInputCipher) -> PuzzleInputFetcher {
+        self.cipher = Some(cipher);
+        self
+    }
+
+    // In offline mode, puzzle input that isn't in the local store is reported as not cached instead
+    // of being fetched from the remote store.
+    pub fn with_offline(mut self, offline: bool) -> PuzzleInputFetcher {
+        self.offline = offline;
+        self
+    }
+
     // Returns the puzzle input for the given day first by fetching it from the in-memory cache,
     // then by fetching it from the local store, and finally by fetching it from the remote store
     // (the site itself).
@@ -67,13 +116,18 @@ impl PuzzleInputFetcher {
             // Puzzle is not in our cache
             let mut is_input_set = self.is_input_set[index].write().unwrap();
             if is_input_set.not() {
-                if let Ok(local_input) = self.fetch_local_puzzle_input(day) {
+                if let Some(local_input) = self.fetch_local_puzzle_input(day)? {
                     // Puzzle is in our local store
+                    debug!(day, "Puzzle input found in the local store");
                     self.inputs[index].push_str(&local_input);
                     *is_input_set = true;
                     return Ok(&self.inputs[index]);
                 }
                 // Puzzle is not in our local store
+                debug!(day, "Puzzle input not found in the local store");
+                if self.offline {
+                    return Err(not_cached_error(day));
+                }
                 if self.is_session_token_set.read().unwrap().not() {
                     // Session token is not cached
                     let mut is_session_token_set = self.is_session_token_set.write().unwrap();
@@ -91,92 +145,231 @@ impl PuzzleInputFetcher {
                 return Ok(&self.inputs[index]);
             }
         }
+        trace!(day, "Puzzle input found in memory");
         Ok(&self.inputs[index])
     }
 
-    fn fetch_local_puzzle_input(&self, day: u8) -> Result<String> {
-        let path = self.input_path.join(day.to_string());
-        fs::read_to_string(path)
-            .map_err(|e| format!("Failed to fetch local puzzle for day {}: {}", day, e))
+    // Forgets the puzzle input for the given day, so that it is fetched again (normally from the
+    // local store) the next time it is needed
+    pub fn forget_puzzle_input(&mut self, day: u8) {
+        let index = (day - 1) as usize;
+        let mut is_input_set = self.is_input_set[index].write().unwrap();
+        self.inputs[index].clear();
+        *is_input_set = false;
+    }
+
+    // Returns where the puzzle input for the given day is kept in the local store
+    pub fn local_puzzle_input_path(&self, day: u8) -> PathBuf {
+        local_path(&self.input_path, self.year, day)
+    }
+
+    // Returns the puzzle input from the local store, or None if it isn't stored locally. A puzzle
+    // input that is stored but can't be read (e.g. it is encrypted and we have no key) is an error
+    // rather than a reason to fetch it again and overwrite it.
+    fn fetch_local_puzzle_input(&self, day: u8) -> Result<Option<String>> {
+        match fs::read(self.local_puzzle_input_path(day)) {
+            Ok(data) => decode_local_puzzle_input(day, data, self.cipher.as_ref()).map(Some),
+            Err(e) if e.kind() == ErrorKind::NotFound => Ok(None),
+            Err(e) => Err(format!(
+                "Failed to fetch local puzzle for day {}: {}",
+                day, e
+            )),
+        }
     }
 
     fn store_puzzle_input_locally(&self, day: u8, input: &str) {
         // Storing puzzle input locally on disk is a 'nice to have' feature in that it reduces load
         // on the Advent of Code site for subsequent runs, but if we can't save to disk -- it
         // shouldn't be a critical error.
-        let _ = fs::create_dir_all(self.input_path.to_path_buf());
-        let _ = fs::write(self.input_path.join(day.to_string()), input);
+        let Some(data) = encode_local_puzzle_input(input, self.cipher.as_ref()) else {
+            warn!(day, "Failed to encrypt puzzle input for the local store");
+            return;
+        };
+        let path = self.local_puzzle_input_path(day);
+        match fs::create_dir_all(path.parent().unwrap()).and_then(|_| fs::write(&path, data)) {
+            Ok(()) => debug!(day, path = %path.display(), "Stored puzzle input locally"),
+            Err(e) => {
+                warn!(day, path = %path.display(), error = %e, "Failed to store puzzle input locally")
+            }
+        }
+    }
+
+    // Encrypts every plain text puzzle input in the local store, returning the number of puzzle
+    // inputs that were encrypted.
+    pub fn encrypt_local_store(&self) -> Result<usize> {
+        let cipher = self.cipher.as_ref().ok_or(format!(
+            "No cache key was found. Set {} or create a key file",
+            KEY_ENV_VAR
+        ))?;
+        let mut count = 0;
+        for day in 1..26 {
+            let path = self.local_puzzle_input_path(day);
+            let data = match fs::read(&path) {
+                Ok(data) => data,
+                Err(e) if e.kind() == ErrorKind::NotFound => continue,
+                Err(e) => return Err(format!("Failed to read {}: {}", path_to_str(&path), e)),
+            };
+            if is_encrypted(&data) {
+                continue;
+            }
+            let input = String::from_utf8(data)
+                .map_err(|e| format!("Failed to read {}: {}", path_to_str(&path), e))?;
+            fs::write(&path, cipher.encrypt(&input)?)
+                .map_err(|e| format!("Failed to write {}: {}", path_to_str(&path), e))?;
+            count += 1;
+        }
+        Ok(count)
     }
 
     fn fetch_remote_puzzle_input(&self, day: u8, session_token: &str) -> Result<String> {
-        let client = reqwest::blocking::Client::new();
-        let path = format!("{}{}", self.base_url, remote_url_path(day));
+        let client = reqwest::blocking::Client::builder()
+            .timeout(self.timeout)
+            .build()
+            .map_err(|e| format!("Failed to fetch remote puzzle input for day {}: {}", day, e))?;
+        let path = format!("{}{}", self.base_url, remote_url_path(self.year, day));
+        info!(day, url = %path, "Fetching puzzle input from the remote store");
+        let start = Instant::now();
         let response = client
             .get(&path)
             .header("Cookie", format!("session={}", session_token))
             .send()
-            .map_err(|e| format!("Failed to fetch remote puzzle input for day {}: {}", day, e))?;
+            .map_err(|e| {
+                warn!(day, error = %e, "Request for puzzle input failed");
+                format!("Failed to fetch remote puzzle input for day {}: {}", day, e)
+            })?;
+        info!(
+            day,
+            status = response.status().as_u16(),
+            elapsed = ?start.elapsed(),
+            "Got a response from the remote store"
+        );
         if response.status() != StatusCode::OK {
-            Err(format!(
-                "Failed to fetch remote puzzle input for day {}: Got status code = {}",
-                day,
-                response.status()
-            ))
+            Err(status_error(day, response.status()))
         } else {
-            response.text().map_err(|e| {
-                format!(
-                    "Failed to fetch remote puzzle input for day {}: Failed to read body as text: {}",
-                    day, e
-                )
-            })
+            response.text().map_err(|e| body_error(day, e))
         }
     }
 
-    // Fetches the session token from the disk
+    // Fetches the session token from the disk or from the environment
     fn fetch_session_token(&self) -> Result<String> {
-        let session_token = fs::read_to_string(&self.session_token_path)
-            .map(|s| s.trim().to_string())
-            .map_err(|e| {
+        let session_token = match &self.session_token_source {
+            SessionTokenSource::File(path) => fs::read_to_string(path).map_err(|e| {
                 format!(
                     "Failed to fetch session token from {}: {}",
-                    path_to_str(&self.session_token_path),
+                    path_to_str(path),
                     e
                 )
-            })?;
-        let has_right_length = session_token.len() == 96;
-        let has_right_charset = session_token
-            .chars()
-            .all(|x| ('0'..='9').contains(&x) || ('a'..='z').contains(&x));
-        if !has_right_length || !has_right_charset {
-            Err(format!(
-                "Session token is not in the right format. Expected 96 lowercase hex digits. Got: {}",
-                session_token
-            ))
-        } else {
-            Ok(session_token)
-        }
+            })?,
+            SessionTokenSource::Env(var) => env::var(var)
+                .map_err(|e| format!("Failed to fetch session token from ${}: {}", var, e))?,
+        };
+        debug!(source = ?self.session_token_source, "Read the session token");
+        check_session_token(session_token.trim().to_string())
     }
 }
 
-fn remote_url_path(day: u8) -> String {
-    format!("/2021/day/{}/input", day.to_string())
+// Reads puzzle input as it is stored in the local store, decrypting it if it is encrypted
+pub(crate) fn decode_local_puzzle_input(
+    day: u8,
+    data: Vec<u8>,
+    cipher: Option<&PuzzleInputCipher>,
+) -> Result<String> {
+    if !is_encrypted(&data) {
+        return String::from_utf8(data)
+            .map_err(|e| format!("Failed to fetch local puzzle for day {}: {}", day, e));
+    }
+    match cipher {
+        Some(cipher) => cipher
+            .decrypt(&data)
+            .map_err(|e| format!("Failed to fetch local puzzle for day {}: {}", day, e)),
+        None => Err(format!(
+            "Failed to fetch local puzzle for day {}: It is encrypted but no cache key was found",
+            day
+        )),
+    }
+}
+
+// Returns puzzle input as it should be stored in the local store, or None if it can't be stored
+pub(crate) fn encode_local_puzzle_input(
+    input: &str,
+    cipher: Option<&PuzzleInputCipher>,
+) -> Option<Vec<u8>> {
+    match cipher {
+        Some(cipher) => cipher.encrypt(input).ok(),
+        None => Some(input.as_bytes().to_vec()),
+    }
+}
+
+pub(crate) fn check_session_token(session_token: String) -> Result<String> {
+    let has_right_length = session_token.len() == 96;
+    let has_right_charset = session_token
+        .chars()
+        .all(|x: char| x.is_ascii_digit() || x.is_ascii_lowercase());
+    if !has_right_length || !has_right_charset {
+        Err(format!(
+            "Session token is not in the right format. Expected 96 lowercase hex digits. Got: {}",
+            session_token
+        ))
+    } else {
+        Ok(session_token)
+    }
 }
 
-fn path_to_str(path: &Path) -> String {
+pub(crate) fn not_cached_error(day: u8) -> String {
+    format!(
+        "Puzzle input for day {} is not cached and network access is disabled (offline mode)",
+        day
+    )
+}
+
+pub(crate) fn status_error(day: u8, status: StatusCode) -> String {
+    format!(
+        "Failed to fetch remote puzzle input for day {}: Got status code = {}",
+        day, status
+    )
+}
+
+pub(crate) fn body_error(day: u8, error: reqwest::Error) -> String {
+    format!(
+        "Failed to fetch remote puzzle input for day {}: Failed to read body as text: {}",
+        day, error
+    )
+}
+
+// Returns where the puzzle input for a day is kept in the local store. Puzzle input for 2021 is kept
+// directly in the store, as it always has been, and puzzle input for other years is kept in a
+// directory per year so that it can't be mistaken for (or overwrite) the 2021 input.
+pub(crate) fn local_path(input_path: &Path, year: u16, day: u8) -> PathBuf {
+    match year {
+        2021 => input_path.join(day.to_string()),
+        _ => input_path.join(year.to_string()).join(day.to_string()),
+    }
+}
+
+pub(crate) fn remote_url_path(year: u16, day: u8) -> String {
+    format!("/{}/day/{}/input", year, day)
+}
+
+pub(crate) fn path_to_str(path: &Path) -> String {
     path.to_str().unwrap().to_string()
 }
 
-type Result<T> = std::result::Result<T, String>;
+pub(crate) type Result<T> = std::result::Result<T, String>;
 
 #[cfg(test)]
 mod tests {
+    use crate::config::{Config, SessionTokenSource};
+    use crate::puzzle_input_cipher::{is_encrypted, PuzzleInputCipher};
     use crate::puzzle_input_fetcher::{remote_url_path, PuzzleInputFetcher};
 
     use httpmock::Method::GET;
     use httpmock::MockServer;
     use rand::Rng;
+    use std::env;
+    use std::fs;
     use std::fs::File;
     use std::io::Write;
+    use std::path::Path;
     use tempfile::{tempdir, NamedTempFile};
 
     //noinspection DuplicatedCode
@@ -199,7 +392,7 @@ mod tests {
             puzzle_file.write_all(puzzle_input.as_bytes()).unwrap();
             let mock = server.mock(|when, then| {
                 when.method(GET)
-                    .path(remote_url_path(day).as_str())
+                    .path(remote_url_path(2021, day).as_str())
                     .header("Cookie", format!("session={}", session_token).as_str());
                 then.status(501);
             });
@@ -208,6 +401,66 @@ mod tests {
         }
     }
 
+    //noinspection DuplicatedCode
+    #[test]
+    fn test_fetch_from_local_store_in_offline_mode() {
+        let server = MockServer::start();
+        let base_url = &server.base_url();
+        let puzzle_store_dir = tempdir().unwrap();
+        let mut session_token_path = NamedTempFile::new().unwrap();
+        let session_token = random_session_token();
+        session_token_path
+            .write_all(session_token.as_bytes())
+            .unwrap();
+        let mut fetcher = PuzzleInputFetcher::create_custom(
+            base_url,
+            puzzle_store_dir.path(),
+            session_token_path.path(),
+        )
+        .with_offline(true);
+        for day in 1..26 {
+            let puzzle_input = random_puzzle();
+            let puzzle_file_path = puzzle_store_dir.path().join(day.to_string());
+            let mut puzzle_file = File::create(puzzle_file_path).unwrap();
+            puzzle_file.write_all(puzzle_input.as_bytes()).unwrap();
+            let mock = server.mock(|when, then| {
+                when.method(GET).path(remote_url_path(2021, day).as_str());
+                then.status(200).body(random_puzzle());
+            });
+            assert_eq!(fetcher.fetch_puzzle_input(day).unwrap(), puzzle_input);
+            mock.assert_hits(0);
+        }
+    }
+
+    //noinspection DuplicatedCode
+    #[test]
+    fn test_error_when_not_cached_in_offline_mode() {
+        let server = MockServer::start();
+        let base_url = &server.base_url();
+        let puzzle_store_dir = tempdir().unwrap();
+        let mut session_token_path = NamedTempFile::new().unwrap();
+        let session_token = random_session_token();
+        session_token_path
+            .write_all(session_token.as_bytes())
+            .unwrap();
+        let mut fetcher = PuzzleInputFetcher::create_custom(
+            base_url,
+            puzzle_store_dir.path(),
+            session_token_path.path(),
+        )
+        .with_offline(true);
+        let mock = server.mock(|when, then| {
+            when.any_request();
+            then.status(200).body(random_puzzle());
+        });
+        for day in 1..26 {
+            let error = fetcher.fetch_puzzle_input(day).unwrap_err();
+            assert!(error.contains("not cached"));
+            assert!(!puzzle_store_dir.path().join(day.to_string()).exists());
+        }
+        mock.assert_hits(0);
+    }
+
     //noinspection DuplicatedCode
     #[test]
     fn test_fetch_from_remote_store() {
@@ -228,7 +481,7 @@ mod tests {
             let puzzle_input = random_puzzle();
             let mock = server.mock(|when, then| {
                 when.method(GET)
-                    .path(remote_url_path(day).as_str())
+                    .path(remote_url_path(2021, day).as_str())
                     .header("Cookie", format!("session={}", session_token).as_str());
                 then.status(200).body(&puzzle_input);
             });
@@ -237,6 +490,39 @@ mod tests {
         }
     }
 
+    //noinspection DuplicatedCode
+    #[test]
+    fn test_fetch_from_remote_store_using_config() {
+        let server = MockServer::start();
+        let puzzle_store_dir = tempdir().unwrap();
+        let session_token = random_session_token();
+        let session_token_var = "AOC_TEST_FETCH_FROM_REMOTE_STORE_USING_CONFIG";
+        env::set_var(session_token_var, &session_token);
+        let config = Config {
+            base_url: server.base_url(),
+            year: 2020,
+            cache_dir: puzzle_store_dir.path().to_path_buf(),
+            session_token: SessionTokenSource::Env(session_token_var.to_string()),
+            cache_key_path: puzzle_store_dir.path().join("cache.key"),
+            ..Config::default()
+        };
+        let mut fetcher = PuzzleInputFetcher::create_from_config(&config).unwrap();
+        for day in 1..26 {
+            let puzzle_input = random_puzzle();
+            let mock = server.mock(|when, then| {
+                when.method(GET)
+                    .path(remote_url_path(2020, day).as_str())
+                    .header("Cookie", format!("session={}", session_token).as_str());
+                then.status(200).body(&puzzle_input);
+            });
+            assert_eq!(fetcher.fetch_puzzle_input(day).unwrap(), puzzle_input);
+            mock.assert();
+            let path = puzzle_store_dir.path().join("2020").join(day.to_string());
+            assert_eq!(fs::read_to_string(path).unwrap(), puzzle_input);
+            assert!(!puzzle_store_dir.path().join(day.to_string()).exists());
+        }
+    }
+
     //noinspection DuplicatedCode
     #[test]
     fn test_error_returned_when_all_sources_unavailable() {
@@ -256,7 +542,7 @@ mod tests {
         for day in 1..26 {
             let mock = server.mock(|when, then| {
                 when.method(GET)
-                    .path(remote_url_path(day).as_str())
+                    .path(remote_url_path(2021, day).as_str())
                     .header("Cookie", format!("session={}", session_token).as_str());
                 then.status(501);
             });
@@ -279,7 +565,7 @@ mod tests {
         );
         for day in 1..26 {
             let mock = server.mock(|when, then| {
-                when.method(GET).path(remote_url_path(day).as_str());
+                when.method(GET).path(remote_url_path(2021, day).as_str());
                 then.status(400)
                     .body("Puzzle inputs differ by user.  Please log in to get your puzzle input.");
             });
@@ -316,7 +602,7 @@ mod tests {
                 let puzzle_input = random_puzzle();
                 let mock = server.mock(|when, then| {
                     when.method(GET)
-                        .path(remote_url_path(day).as_str())
+                        .path(remote_url_path(2021, day).as_str())
                         .header("Cookie", format!("session={}", session_token).as_str());
                     then.status(200).body(puzzle_input);
                 });
@@ -345,7 +631,7 @@ mod tests {
         for day in 1..26 {
             let mock = server.mock(|when, then| {
                 when.method(GET)
-                    .path(remote_url_path(day).as_str())
+                    .path(remote_url_path(2021, day).as_str())
                     .header("Cookie", format!("session={}", session_token).as_str());
                 then.status(400)
                     .body("Puzzle inputs differ by user.  Please log in to get your puzzle input.");
@@ -374,7 +660,7 @@ mod tests {
         for day in 1..26 {
             let mock = server.mock(|when, then| {
                 when.method(GET)
-                    .path(remote_url_path(day).as_str())
+                    .path(remote_url_path(2021, day).as_str())
                     .header("Cookie", format!("session={}", session_token).as_str());
                 then.status(404)
                     .body("Please don't repeatedly request this endpoint before it unlocks! \
@@ -386,6 +672,192 @@ mod tests {
         }
     }
 
+    //noinspection DuplicatedCode
+    #[test]
+    fn test_fetch_from_encrypted_local_store() {
+        let server = MockServer::start();
+        let base_url = &server.base_url();
+        let puzzle_store_dir = tempdir().unwrap();
+        let session_token_path = NamedTempFile::new().unwrap();
+        let cache_key = random_cache_key();
+        let cipher = PuzzleInputCipher::create(&cache_key).unwrap();
+        let mut fetcher = PuzzleInputFetcher::create_custom(
+            base_url,
+            puzzle_store_dir.path(),
+            session_token_path.path(),
+        )
+        .with_cipher(PuzzleInputCipher::create(&cache_key).unwrap());
+        for day in 1..26 {
+            let puzzle_input = random_puzzle();
+            let puzzle_file_path = puzzle_store_dir.path().join(day.to_string());
+            fs::write(puzzle_file_path, cipher.encrypt(&puzzle_input).unwrap()).unwrap();
+            let mock = server.mock(|when, then| {
+                when.method(GET).path(remote_url_path(2021, day).as_str());
+                then.status(501);
+            });
+            assert_eq!(fetcher.fetch_puzzle_input(day).unwrap(), puzzle_input);
+            mock.assert_hits(0);
+        }
+    }
+
+    //noinspection DuplicatedCode
+    #[test]
+    fn test_fetch_from_plain_local_store_with_cipher() {
+        let server = MockServer::start();
+        let base_url = &server.base_url();
+        let puzzle_store_dir = tempdir().unwrap();
+        let session_token_path = NamedTempFile::new().unwrap();
+        let mut fetcher = PuzzleInputFetcher::create_custom(
+            base_url,
+            puzzle_store_dir.path(),
+            session_token_path.path(),
+        )
+        .with_cipher(PuzzleInputCipher::create(&random_cache_key()).unwrap());
+        for day in 1..26 {
+            let puzzle_input = random_puzzle();
+            let puzzle_file_path = puzzle_store_dir.path().join(day.to_string());
+            fs::write(puzzle_file_path, &puzzle_input).unwrap();
+            let mock = server.mock(|when, then| {
+                when.method(GET).path(remote_url_path(2021, day).as_str());
+                then.status(501);
+            });
+            assert_eq!(fetcher.fetch_puzzle_input(day).unwrap(), puzzle_input);
+            mock.assert_hits(0);
+        }
+    }
+
+    //noinspection DuplicatedCode
+    #[test]
+    fn test_remote_input_stored_encrypted() {
+        let server = MockServer::start();
+        let base_url = &server.base_url();
+        let puzzle_store_dir = tempdir().unwrap();
+        let mut session_token_path = NamedTempFile::new().unwrap();
+        let session_token = random_session_token();
+        session_token_path
+            .write_all(session_token.as_bytes())
+            .unwrap();
+        let cache_key = random_cache_key();
+        let cipher = PuzzleInputCipher::create(&cache_key).unwrap();
+        let mut fetcher = PuzzleInputFetcher::create_custom(
+            base_url,
+            puzzle_store_dir.path(),
+            session_token_path.path(),
+        )
+        .with_cipher(PuzzleInputCipher::create(&cache_key).unwrap());
+        for day in 1..26 {
+            let puzzle_input = random_puzzle();
+            let mock = server.mock(|when, then| {
+                when.method(GET)
+                    .path(remote_url_path(2021, day).as_str())
+                    .header("Cookie", format!("session={}", session_token).as_str());
+                then.status(200).body(&puzzle_input);
+            });
+            assert_eq!(fetcher.fetch_puzzle_input(day).unwrap(), puzzle_input);
+            mock.assert();
+            let stored = fs::read(puzzle_store_dir.path().join(day.to_string())).unwrap();
+            assert!(is_encrypted(&stored));
+            assert_eq!(cipher.decrypt(&stored).unwrap(), puzzle_input);
+        }
+    }
+
+    //noinspection DuplicatedCode
+    #[test]
+    fn test_error_when_local_store_encrypted_without_key() {
+        let server = MockServer::start();
+        let base_url = &server.base_url();
+        let puzzle_store_dir = tempdir().unwrap();
+        let mut session_token_path = NamedTempFile::new().unwrap();
+        let session_token = random_session_token();
+        session_token_path
+            .write_all(session_token.as_bytes())
+            .unwrap();
+        let cipher = PuzzleInputCipher::create(&random_cache_key()).unwrap();
+        let mut fetcher = PuzzleInputFetcher::create_custom(
+            base_url,
+            puzzle_store_dir.path(),
+            session_token_path.path(),
+        );
+        for day in 1..26 {
+            let puzzle_input = random_puzzle();
+            let puzzle_file_path = puzzle_store_dir.path().join(day.to_string());
+            let encrypted = cipher.encrypt(&puzzle_input).unwrap();
+            fs::write(&puzzle_file_path, &encrypted).unwrap();
+            let mock = server.mock(|when, then| {
+                when.method(GET).path(remote_url_path(2021, day).as_str());
+                then.status(200).body(&puzzle_input);
+            });
+            assert!(fetcher.fetch_puzzle_input(day).is_err());
+            mock.assert_hits(0);
+            assert_eq!(fs::read(&puzzle_file_path).unwrap(), encrypted);
+        }
+    }
+
+    //noinspection DuplicatedCode
+    #[test]
+    fn test_encrypt_local_store() {
+        let puzzle_store_dir = tempdir().unwrap();
+        let session_token_path = NamedTempFile::new().unwrap();
+        let cache_key = random_cache_key();
+        let cipher = PuzzleInputCipher::create(&cache_key).unwrap();
+        let fetcher = PuzzleInputFetcher::create_custom(
+            "http://localhost",
+            puzzle_store_dir.path(),
+            session_token_path.path(),
+        )
+        .with_cipher(PuzzleInputCipher::create(&cache_key).unwrap());
+        let mut puzzle_inputs = Vec::new();
+        for day in 1..11 {
+            let puzzle_input = random_puzzle();
+            fs::write(puzzle_store_dir.path().join(day.to_string()), &puzzle_input).unwrap();
+            puzzle_inputs.push(puzzle_input);
+        }
+        assert_eq!(fetcher.encrypt_local_store().unwrap(), 10);
+        // Already encrypted puzzle inputs are left alone
+        assert_eq!(fetcher.encrypt_local_store().unwrap(), 0);
+        for day in 1..11 {
+            let stored = fs::read(puzzle_store_dir.path().join(day.to_string())).unwrap();
+            assert!(is_encrypted(&stored));
+            assert_eq!(cipher.decrypt(&stored).unwrap(), puzzle_inputs[day - 1]);
+        }
+    }
+
+    #[test]
+    fn test_error_when_encrypting_local_store_without_key() {
+        let puzzle_store_dir = tempdir().unwrap();
+        let session_token_path = NamedTempFile::new().unwrap();
+        let fetcher = PuzzleInputFetcher::create_custom(
+            "http://localhost",
+            puzzle_store_dir.path(),
+            session_token_path.path(),
+        );
+        fs::write(puzzle_store_dir.path().join("1"), "input").unwrap();
+        assert!(fetcher.encrypt_local_store().is_err());
+        assert_eq!(
+            fs::read_to_string(puzzle_store_dir.path().join("1")).unwrap(),
+            "input"
+        );
+    }
+
+    #[test]
+    fn test_forget_puzzle_input() {
+        let puzzle_store_dir = tempdir().unwrap();
+        let mut fetcher = PuzzleInputFetcher::create_custom(
+            "http://localhost",
+            puzzle_store_dir.path(),
+            Path::new("cookie.txt"),
+        )
+        .with_offline(true);
+        let path = fetcher.local_puzzle_input_path(3);
+        assert_eq!(path, puzzle_store_dir.path().join("3"));
+        fs::write(&path, "first").unwrap();
+        assert_eq!(fetcher.fetch_puzzle_input(3).unwrap(), "first");
+        fs::write(&path, "second").unwrap();
+        assert_eq!(fetcher.fetch_puzzle_input(3).unwrap(), "first");
+        fetcher.forget_puzzle_input(3);
+        assert_eq!(fetcher.fetch_puzzle_input(3).unwrap(), "second");
+    }
+
     fn random_puzzle() -> String {
         // Puzzle inputs tend to contain a wide variety of ASCII characters including line feed.
         // They can also be fairly large.
@@ -408,6 +880,11 @@ mod tests {
         random_string("0123456789abcdef", 96)
     }
 
+    fn random_cache_key() -> String {
+        // Cache keys are 256-bit keys written as 64 hex digits
+        random_string("0123456789abcdef", 64)
+    }
+
     fn random_string(charset: &str, length: usize) -> String {
         let mut rng = rand::thread_rng();
         (0..length)
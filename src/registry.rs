@@ -0,0 +1,115 @@
+use crate::puzzle::{DynamicPuzzle, Metadata, ParsedPuzzle};
+use crate::puzzle01::Puzzle01;
+use crate::puzzle02::Puzzle02;
+use crate::puzzle03::Puzzle03;
+use crate::puzzle04::Puzzle04;
+use crate::puzzle05::Puzzle05;
+use crate::puzzle06::Puzzle06;
+use crate::puzzle07::Puzzle07;
+use crate::puzzle08::Puzzle08;
+use crate::puzzle09::Puzzle09;
+use crate::puzzle10::Puzzle10;
+use crate::puzzle11::Puzzle11;
+use crate::puzzle12::Puzzle12;
+use crate::puzzle13::Puzzle13;
+use crate::puzzle14::Puzzle14;
+use crate::puzzle15::Puzzle15;
+use crate::puzzle16::Puzzle16;
+use crate::puzzle17::Puzzle17;
+
+// Every puzzle solved so far, in order of day
+const PUZZLES: [&dyn DynamicPuzzle; 17] = [
+    &Puzzle01, &Puzzle02, &Puzzle03, &Puzzle04, &Puzzle05, &Puzzle06, &Puzzle07, &Puzzle08,
+    &Puzzle09, &Puzzle10, &Puzzle11, &Puzzle12, &Puzzle13, &Puzzle14, &Puzzle15, &Puzzle16,
+    &Puzzle17,
+];
+
+// Returns the days that have a puzzle
+pub fn days() -> Vec<u8> {
+    (1..=PUZZLES.len() as u8).collect()
+}
+
+// Returns the metadata of every puzzle, in order of day
+pub fn puzzles() -> Vec<Metadata> {
+    PUZZLES.iter().map(|puzzle| puzzle.metadata()).collect()
+}
+
+pub fn has_puzzle(day: u8) -> bool {
+    day >= 1 && day as usize <= PUZZLES.len()
+}
+
+// Parses the puzzle input for the given day, returning the puzzle ready to be solved
+pub fn create_puzzle(day: u8, input: &str) -> Result<Box<dyn ParsedPuzzle>> {
+    if !has_puzzle(day) {
+        return Err(format!("Day {} has not been solved yet", day));
+    }
+    PUZZLES[(day - 1) as usize]
+        .create(input)
+        .map_err(|e| format!("Invalid puzzle input for day {}: {}", day, e))
+}
+
+type Result<T> = std::result::Result<T, String>;
+
+#[cfg(test)]
+mod tests {
+    use crate::registry::{create_puzzle, days, has_puzzle, puzzles};
+    use std::fs;
+    use std::path::PathBuf;
+
+    #[test]
+    fn test_puzzles_are_created_for_their_day() {
+        for day in days() {
+            let input = fs::read_to_string(PathBuf::from(format!("resources/tests/{}", day)));
+            let puzzle = create_puzzle(day, &input.unwrap()).unwrap();
+            assert_eq!(puzzle.get_day(), day);
+            assert_eq!(puzzle.metadata().day, day);
+        }
+    }
+
+    #[test]
+    fn test_metadata() {
+        let puzzles = puzzles();
+        assert_eq!(puzzles.len(), days().len());
+        for (puzzle, day) in puzzles.iter().zip(days()) {
+            assert_eq!(puzzle.day, day);
+            assert_eq!(puzzle.year, 2021);
+            assert!(!puzzle.title.is_empty());
+            assert!(!puzzle.part_1_description.is_empty());
+            assert!(!puzzle.part_2_description.is_empty());
+        }
+        assert_eq!(puzzles[15].title, "Packet Decoder");
+    }
+
+    #[test]
+    fn test_invalid_input() {
+        for day in days() {
+            assert!(create_puzzle(day, "x").is_err(), "day {}", day);
+        }
+        assert_eq!(
+            create_puzzle(1, "199\n2OO\n").err().unwrap(),
+            "Invalid puzzle input for day 1: Invalid depth '2OO' at line 2, column 1"
+        );
+    }
+
+    #[test]
+    fn test_windows_line_endings_and_trailing_whitespace() {
+        for day in days() {
+            let input = fs::read_to_string(PathBuf::from(format!("resources/tests/{}", day)));
+            let input = input.unwrap();
+            let windows_input = input.replace('\n', " \r\n") + "\r\n";
+            assert_eq!(
+                create_puzzle(day, &windows_input).unwrap().solve_part_1(),
+                create_puzzle(day, &input).unwrap().solve_part_1(),
+                "day {}",
+                day
+            );
+        }
+    }
+
+    #[test]
+    fn test_no_puzzle_for_unsolved_days() {
+        assert!(!has_puzzle(0));
+        assert!(!has_puzzle(25));
+        assert!(create_puzzle(25, "").is_err());
+    }
+}
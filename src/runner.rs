@@ -0,0 +1,638 @@
+use std::io::{self, IsTerminal};
+use std::path::Path;
+use std::sync::mpsc::{self, RecvTimeoutError};
+use std::sync::Arc;
+use std::thread;
+use std::time::{Duration, Instant};
+
+use serde::Serialize;
+
+use crate::alloc_stats::{self, AllocationStats};
+use crate::benchmark::Stage;
+use crate::config::{Config, OutputFormat};
+use crate::image_export::{self, ImageFormat};
+use crate::puzzle::{Metadata, ParsedPuzzle};
+use crate::puzzle_input_fetcher::PuzzleInputFetcher;
+use crate::registry;
+use crate::solve_context::SolveContext;
+
+// The answers to both parts of a day, where a part that failed or ran out of time has an error
+// instead
+#[derive(Debug, Serialize)]
+pub struct DayResult {
+    #[serde(flatten)]
+    pub metadata: Metadata,
+    #[serde(serialize_with = "serialize_part")]
+    pub part_1: Result<String>,
+    #[serde(serialize_with = "serialize_part")]
+    pub part_2: Result<String>,
+
+    // What parsing and each solved part allocated, when allocations are being counted
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub parse_memory: Option<AllocationStats>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub part_1_memory: Option<AllocationStats>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub part_2_memory: Option<AllocationStats>,
+}
+
+impl DayResult {
+    pub fn is_solved(&self) -> bool {
+        self.part_1.is_ok() && self.part_2.is_ok()
+    }
+}
+
+// Serializes the answer to a part as a string, or as {"error": "..."} if the part wasn't solved
+fn serialize_part<S: serde::Serializer>(
+    part: &Result<String>,
+    serializer: S,
+) -> std::result::Result<S::Ok, S::Error> {
+    match part {
+        Ok(answer) => serializer.serialize_str(answer),
+        Err(e) => serde_json::json!({ "error": e }).serialize(serializer),
+    }
+}
+
+// Solves the puzzles for the given days and prints their answers in the configured format. Days
+// that can't be solved (or only partly) don't stop the other days from being solved, but make the
+// run fail once they all have been.
+pub fn run(fetcher: &mut PuzzleInputFetcher, days: &[u8], config: &Config) -> Result<()> {
+    let output_format = config.output_format;
+    let mut results = Vec::new();
+    let mut unsolved = Vec::new();
+    for &day in days {
+        let result = match solve_day(fetcher, day, config.solve_timeout) {
+            Ok(result) => result,
+            Err(e) => {
+                eprintln!("{}", e);
+                unsolved.push(day);
+                continue;
+            }
+        };
+        if !result.is_solved() {
+            unsolved.push(day);
+        }
+        if output_format == OutputFormat::Text {
+            print_result(&result);
+        }
+        results.push(result);
+    }
+    if output_format == OutputFormat::Json {
+        let json = serde_json::to_string_pretty(&results).map_err(|e| e.to_string())?;
+        println!("{}", json);
+    }
+    match unsolved.as_slice() {
+        [] => Ok(()),
+        days => Err(format!(
+            "Could not solve day {}",
+            days.iter()
+                .map(|day| day.to_string())
+                .collect::<Vec<String>>()
+                .join(", ")
+        )),
+    }
+}
+
+// Prints the answers to both parts, or the error of a part that wasn't solved, and what parsing and
+// each part allocated when allocations are being counted
+pub fn print_result(result: &DayResult) {
+    let metadata = &result.metadata;
+    println!("Day {:02}: {}", metadata.day, metadata.title);
+    if let Some(memory) = result.parse_memory {
+        println!(
+            "Day {:02} Parse memory: {}",
+            metadata.day,
+            format_memory(memory)
+        );
+    }
+    let parts = [
+        (
+            1,
+            metadata.part_1_description,
+            &result.part_1,
+            result.part_1_memory,
+        ),
+        (
+            2,
+            metadata.part_2_description,
+            &result.part_2,
+            result.part_2_memory,
+        ),
+    ];
+    for (part, description, answer, memory) in parts {
+        match answer {
+            Ok(answer) => println!(
+                "Day {:02} Part {} ({}): {}",
+                metadata.day, part, description, answer
+            ),
+            Err(e) => println!("{}", e),
+        }
+        if let Some(memory) = memory {
+            println!(
+                "Day {:02} Part {} memory: {}",
+                metadata.day,
+                part,
+                format_memory(memory)
+            );
+        }
+    }
+}
+
+fn format_memory(memory: AllocationStats) -> String {
+    format!(
+        "{} peak, {} allocations",
+        alloc_stats::format_bytes(memory.peak_bytes),
+        memory.allocations
+    )
+}
+
+#[derive(Debug, Serialize)]
+pub struct DayTime {
+    pub day: u8,
+    pub title: &'static str,
+    #[serde(serialize_with = "serialize_seconds")]
+    pub parse: Duration,
+    #[serde(serialize_with = "serialize_seconds")]
+    pub part_1: Duration,
+    #[serde(serialize_with = "serialize_seconds")]
+    pub part_2: Duration,
+
+    // What parsing and each part allocated, when allocations are being counted
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub memory: Option<[AllocationStats; 3]>,
+}
+
+impl DayTime {
+    pub fn total(&self) -> Duration {
+        self.parse + self.part_1 + self.part_2
+    }
+}
+
+fn serialize_seconds<S: serde::Serializer>(
+    duration: &Duration,
+    serializer: S,
+) -> std::result::Result<S::Ok, S::Error> {
+    serializer.serialize_f64(duration.as_secs_f64())
+}
+
+// Times parsing and solving both parts of a day once, not counting fetching the input
+pub fn time_day(fetcher: &mut PuzzleInputFetcher, day: u8) -> Result<DayTime> {
+    let input = fetcher.fetch_puzzle_input(day)?;
+    let start = Instant::now();
+    let (puzzle, parse_memory) = alloc_stats::measure(|| registry::create_puzzle(day, input));
+    let puzzle = puzzle?;
+    let parse = start.elapsed();
+    let start = Instant::now();
+    let (_, part_1_memory) = alloc_stats::measure(|| puzzle.solve_part_1());
+    let part_1 = start.elapsed();
+    let start = Instant::now();
+    let (_, part_2_memory) = alloc_stats::measure(|| puzzle.solve_part_2());
+    let part_2 = start.elapsed();
+    Ok(DayTime {
+        day,
+        title: puzzle.metadata().title,
+        parse,
+        part_1,
+        part_2,
+        memory: parse_memory
+            .zip(part_1_memory)
+            .zip(part_2_memory)
+            .map(|((parse, part_1), part_2)| [parse, part_1, part_2]),
+    })
+}
+
+// How much of a budget the days take, slowest first
+#[derive(Debug, Serialize)]
+pub struct BudgetReport {
+    #[serde(serialize_with = "serialize_seconds")]
+    pub budget: Duration,
+    #[serde(serialize_with = "serialize_seconds")]
+    pub total: Duration,
+
+    // The budget split evenly between the days
+    #[serde(serialize_with = "serialize_seconds")]
+    pub fair_share: Duration,
+    pub over_budget: bool,
+    pub days: Vec<BudgetDay>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct BudgetDay {
+    #[serde(flatten)]
+    pub time: DayTime,
+    #[serde(serialize_with = "serialize_seconds")]
+    pub total: Duration,
+
+    // The percentage of the budget that the day takes
+    pub share: f64,
+    pub over_fair_share: bool,
+}
+
+impl BudgetReport {
+    pub fn new(mut times: Vec<DayTime>, budget: Duration) -> BudgetReport {
+        times.sort_by_key(|time| std::cmp::Reverse(time.total()));
+        let total = times.iter().map(DayTime::total).sum::<Duration>();
+        let fair_share = budget / times.len().max(1) as u32;
+        let days = times
+            .into_iter()
+            .map(|time| BudgetDay {
+                total: time.total(),
+                share: budget_share(time.total(), budget),
+                over_fair_share: time.total() > fair_share,
+                time,
+            })
+            .collect();
+        BudgetReport {
+            budget,
+            total,
+            fair_share,
+            over_budget: total > budget,
+            days,
+        }
+    }
+}
+
+fn budget_share(duration: Duration, budget: Duration) -> f64 {
+    100.0 * duration.as_secs_f64() / budget.as_secs_f64()
+}
+
+// Times the given days and prints how much of the budget each one takes, slowest first. Days that
+// take more than their share of the budget (the budget split evenly between the days) are flagged.
+// Fails if the days take longer than the budget altogether.
+pub fn run_budget(
+    fetcher: &mut PuzzleInputFetcher,
+    days: &[u8],
+    budget: Duration,
+    output_format: OutputFormat,
+) -> Result<()> {
+    let times = days
+        .iter()
+        .map(|&day| time_day(fetcher, day))
+        .collect::<Result<Vec<DayTime>>>()?;
+    let report = BudgetReport::new(times, budget);
+    match output_format {
+        OutputFormat::Text => {
+            for day in &report.days {
+                println!(
+                    "Day {:02} {:>12.3?} {:>6.1}% {} {}",
+                    day.time.day,
+                    day.total,
+                    day.share,
+                    if day.over_fair_share { '*' } else { ' ' },
+                    day.time.title
+                );
+                // What each stage allocated, as the benchmark shows it
+                let stages = [Stage::Parse, Stage::Part1, Stage::Part2];
+                for (stage, memory) in stages.iter().zip(day.time.memory.iter().flatten()) {
+                    println!(
+                        "  {:<6} {:>12} peak {:>8} allocations",
+                        stage,
+                        alloc_stats::format_bytes(memory.peak_bytes),
+                        memory.allocations
+                    );
+                }
+            }
+            println!(
+                "Total  {:>12.3?} {:>6.1}% of {:?}",
+                report.total,
+                budget_share(report.total, budget),
+                budget
+            );
+            if report.days.iter().any(|day| day.over_fair_share) {
+                println!(
+                    "* Takes more than its share of the budget ({:.3?})",
+                    report.fair_share
+                );
+            }
+        }
+        OutputFormat::Json => {
+            let json = serde_json::to_string_pretty(&report).map_err(|e| e.to_string())?;
+            println!("{}", json);
+        }
+    }
+    if report.over_budget {
+        return Err(format!(
+            "The days took {:.3?}, which is over the budget of {:?}",
+            report.total, budget
+        ));
+    }
+    Ok(())
+}
+
+// Prints the puzzles that have been solved in the given format
+pub fn list(output_format: OutputFormat) -> Result<()> {
+    let puzzles = registry::puzzles();
+    match output_format {
+        OutputFormat::Text => {
+            for puzzle in puzzles {
+                println!("Day {:02}: {} ({})", puzzle.day, puzzle.title, puzzle.year);
+                println!("  Part 1: {}", puzzle.part_1_description);
+                println!("  Part 2: {}", puzzle.part_2_description);
+            }
+        }
+        OutputFormat::Json => {
+            let json = serde_json::to_string_pretty(&puzzles).map_err(|e| e.to_string())?;
+            println!("{}", json);
+        }
+    }
+    Ok(())
+}
+
+pub fn solve_day(
+    fetcher: &mut PuzzleInputFetcher,
+    day: u8,
+    timeout: Option<Duration>,
+) -> Result<DayResult> {
+    let input = fetcher.fetch_puzzle_input(day)?;
+    solve_input(day, input, timeout)
+}
+
+// Solves the puzzle for the given day using the given input rather than the day's puzzle input.
+// Only fails if the input can't be parsed; each part is solved (or fails) on its own.
+pub fn solve_input(day: u8, input: &str, timeout: Option<Duration>) -> Result<DayResult> {
+    let (puzzle, parse_memory) = alloc_stats::measure(|| registry::create_puzzle(day, input));
+    let puzzle: Arc<dyn ParsedPuzzle> = Arc::from(puzzle?);
+    let (part_1, part_1_memory) = solve_part(&puzzle, 1, timeout);
+    let (part_2, part_2_memory) = solve_part(&puzzle, 2, timeout);
+    Ok(DayResult {
+        metadata: puzzle.metadata(),
+        part_1,
+        part_2,
+        parse_memory,
+        part_1_memory,
+        part_2_memory,
+    })
+}
+
+// Solves one part of a puzzle, showing its progress on standard error when it is a terminal. Also
+// returns what the part allocated if it was solved and allocations are being counted.
+fn solve_part(
+    puzzle: &Arc<dyn ParsedPuzzle>,
+    part: u8,
+    timeout: Option<Duration>,
+) -> (Result<String>, Option<AllocationStats>) {
+    let day = puzzle.get_day();
+    let show_progress = io::stderr().is_terminal();
+    let mut ctx = SolveContext::new();
+    if let Some(timeout) = timeout {
+        ctx = ctx.with_timeout(timeout);
+    }
+    if show_progress {
+        ctx = ctx.with_progress(move |fraction| {
+            eprint!("\rDay {:02} Part {}: {:3.0}%", day, part, fraction * 100.0)
+        });
+    }
+    let (answer, memory) = solve_on_thread(puzzle, part, ctx, timeout);
+    if show_progress {
+        // Clears the progress line
+        eprint!("\r\x1b[K");
+    }
+    let answer = answer.map_err(|e| match timeout {
+        Some(timeout) if e == "Timed out" => {
+            format!("Day {:02} Part {} timed out after {:?}", day, part, timeout)
+        }
+        _ => format!("Day {:02} Part {} failed: {}", day, part, e),
+    });
+    let memory = memory.filter(|_| answer.is_ok());
+    (answer, memory)
+}
+
+// Solves one part of a puzzle on its own thread and waits for it until it runs out of time, when
+// it fails with "Timed out". The part is then cancelled, which stops it at its next check; a part
+// that never checks can't be stopped, so its thread is abandoned and carries on until it finishes
+// or the process exits. A part that panics fails with "Panicked".
+pub(crate) fn solve_on_thread(
+    puzzle: &Arc<dyn ParsedPuzzle>,
+    part: u8,
+    ctx: SolveContext,
+    timeout: Option<Duration>,
+) -> (Result<String>, Option<AllocationStats>) {
+    let ctx = Arc::new(ctx);
+    let (sender, receiver) = mpsc::channel();
+    {
+        let puzzle = Arc::clone(puzzle);
+        let ctx = Arc::clone(&ctx);
+        thread::spawn(move || {
+            // Allocations are counted per thread, so they have to be counted on this one
+            let answer = alloc_stats::measure(|| match part {
+                1 => puzzle.solve_part_1_with(&ctx),
+                _ => puzzle.solve_part_2_with(&ctx),
+            });
+            // Nobody is waiting any more if the part timed out
+            let _ = sender.send(answer);
+        });
+    }
+    match timeout {
+        Some(timeout) => match receiver.recv_timeout(timeout) {
+            Ok(answer) => answer,
+            Err(RecvTimeoutError::Timeout) => {
+                ctx.cancel();
+                (Err("Timed out".to_string()), None)
+            }
+            Err(RecvTimeoutError::Disconnected) => (Err("Panicked".to_string()), None),
+        },
+        None => receiver
+            .recv()
+            .unwrap_or_else(|_| (Err("Panicked".to_string()), None)),
+    }
+}
+
+// Writes the images of the puzzles for the given days to the given directory
+pub fn export_images(
+    fetcher: &mut PuzzleInputFetcher,
+    days: &[u8],
+    dir: &Path,
+    format: ImageFormat,
+) -> Result<()> {
+    for &day in days {
+        let input = fetcher.fetch_puzzle_input(day)?;
+        let puzzle = registry::create_puzzle(day, input)?;
+        let images = puzzle.images();
+        if images.is_empty() {
+            eprintln!("Day {:02} has no images", day);
+        }
+        for path in image_export::export_images(day, &images, dir, format)? {
+            eprintln!("Wrote {}", path.display());
+        }
+    }
+    Ok(())
+}
+
+// Returns the days to run: the requested days if there are any, then the configured default days,
+// and otherwise every day that has a puzzle.
+pub fn select_days(requested: &[u8], configured: &[u8]) -> Result<Vec<u8>> {
+    let days = if !requested.is_empty() {
+        requested.to_vec()
+    } else if !configured.is_empty() {
+        configured.to_vec()
+    } else {
+        registry::days()
+    };
+    match days.iter().find(|&&day| !registry::has_puzzle(day)) {
+        Some(day) => Err(format!("Day {} has not been solved yet", day)),
+        None => Ok(days),
+    }
+}
+
+type Result<T> = std::result::Result<T, String>;
+
+#[cfg(test)]
+mod tests {
+    use crate::config::{Config, OutputFormat};
+    use crate::image_export::ImageFormat;
+    use crate::puzzle_input_fetcher::PuzzleInputFetcher;
+    use crate::registry;
+    use crate::runner::{
+        export_images, run, run_budget, select_days, solve_day, time_day, BudgetReport, DayTime,
+    };
+    use std::fs;
+    use std::path::Path;
+    use std::time::Duration;
+    use tempfile::tempdir;
+
+    #[test]
+    fn test_select_days() {
+        assert_eq!(select_days(&[3, 1], &[5]).unwrap(), vec![3, 1]);
+        assert_eq!(select_days(&[], &[5]).unwrap(), vec![5]);
+        assert_eq!(select_days(&[], &[]).unwrap(), registry::days());
+        assert!(select_days(&[25], &[]).is_err());
+        assert!(select_days(&[], &[25]).is_err());
+    }
+
+    #[test]
+    fn test_solve_day() {
+        let mut fetcher = PuzzleInputFetcher::create_custom(
+            "http://localhost",
+            Path::new("resources/tests"),
+            Path::new("cookie.txt"),
+        )
+        .with_offline(true);
+        let result = solve_day(&mut fetcher, 1, None).unwrap();
+        assert_eq!(result.metadata.day, 1);
+        assert_eq!(result.metadata.title, "Sonar Sweep");
+        assert_eq!(result.part_1.unwrap(), "1532");
+        assert_eq!(result.part_2.unwrap(), "1571");
+        let counted = cfg!(feature = "alloc-stats");
+        assert_eq!(result.parse_memory.is_some(), counted);
+        assert_eq!(result.part_1_memory.is_some(), counted);
+        assert_eq!(result.part_2_memory.is_some(), counted);
+    }
+
+    #[test]
+    fn test_solve_day_with_timeout() {
+        let mut fetcher = PuzzleInputFetcher::create_custom(
+            "http://localhost",
+            Path::new("resources/tests"),
+            Path::new("cookie.txt"),
+        )
+        .with_offline(true);
+        let result = solve_day(&mut fetcher, 17, Some(Duration::from_secs(60))).unwrap();
+        assert_eq!(result.part_1.unwrap(), "7626");
+        let result = solve_day(&mut fetcher, 12, Some(Duration::ZERO)).unwrap();
+        assert!(!result.is_solved());
+        assert_eq!(
+            result.part_1.unwrap_err(),
+            "Day 12 Part 1 timed out after 0ns"
+        );
+        assert_eq!(
+            result.part_2.unwrap_err(),
+            "Day 12 Part 2 timed out after 0ns"
+        );
+        assert_eq!(result.part_1_memory, None);
+    }
+
+    #[test]
+    fn test_run_carries_on_after_a_failure() {
+        let dir = tempdir().unwrap();
+        fs::copy("resources/tests/1", dir.path().join("1")).unwrap();
+        fs::write(dir.path().join("2"), "forward x\n").unwrap();
+        let mut fetcher = PuzzleInputFetcher::create_custom(
+            "http://localhost",
+            dir.path(),
+            Path::new("cookie.txt"),
+        )
+        .with_offline(true);
+        let config = Config::default();
+        assert_eq!(
+            run(&mut fetcher, &[2, 1, 3], &config).unwrap_err(),
+            "Could not solve day 2, 3"
+        );
+        assert!(run(&mut fetcher, &[1], &config).is_ok());
+    }
+
+    #[test]
+    fn test_budget() {
+        let mut fetcher = PuzzleInputFetcher::create_custom(
+            "http://localhost",
+            Path::new("resources/tests"),
+            Path::new("cookie.txt"),
+        )
+        .with_offline(true);
+        let time = time_day(&mut fetcher, 6).unwrap();
+        assert_eq!(time.day, 6);
+        assert_eq!(time.title, "Lanternfish");
+        assert_eq!(time.total(), time.parse + time.part_1 + time.part_2);
+        run_budget(
+            &mut fetcher,
+            &[1, 6],
+            Duration::from_secs(60),
+            OutputFormat::Text,
+        )
+        .unwrap();
+        let error = run_budget(&mut fetcher, &[1, 6], Duration::ZERO, OutputFormat::Json);
+        assert!(error
+            .unwrap_err()
+            .ends_with("which is over the budget of 0ns"));
+    }
+
+    #[test]
+    fn test_budget_report() {
+        let time = |day, millis| DayTime {
+            day,
+            title: "",
+            parse: Duration::ZERO,
+            part_1: Duration::from_millis(millis),
+            part_2: Duration::ZERO,
+            memory: None,
+        };
+        let report = BudgetReport::new(vec![time(1, 100), time(2, 300)], Duration::from_secs(1));
+        assert_eq!(report.total, Duration::from_millis(400));
+        assert_eq!(report.fair_share, Duration::from_millis(500));
+        assert!(!report.over_budget);
+        let json = serde_json::to_value(&report).unwrap();
+        assert_eq!(json["budget"], 1.0);
+        assert_eq!(json["days"][0]["day"], 2);
+        assert_eq!(json["days"][0]["total"], 0.3);
+        assert_eq!(json["days"][0]["share"], 30.0);
+        assert_eq!(json["days"][0]["over_fair_share"], false);
+        let report =
+            BudgetReport::new(vec![time(1, 100), time(2, 300)], Duration::from_millis(200));
+        assert!(report.over_budget);
+        let over = report
+            .days
+            .iter()
+            .map(|day| day.over_fair_share)
+            .collect::<Vec<_>>();
+        assert_eq!(over, vec![true, false]);
+    }
+
+    #[test]
+    fn test_export_images() {
+        let mut fetcher = PuzzleInputFetcher::create_custom(
+            "http://localhost",
+            Path::new("resources/tests"),
+            Path::new("cookie.txt"),
+        )
+        .with_offline(true);
+        let dir = tempdir().unwrap();
+        export_images(&mut fetcher, &[1, 9], dir.path(), ImageFormat::Pnm).unwrap();
+        let mut files = fs::read_dir(dir.path())
+            .unwrap()
+            .map(|entry| entry.unwrap().file_name().into_string().unwrap())
+            .collect::<Vec<_>>();
+        files.sort();
+        assert_eq!(files, vec!["day09-heightmap.pgm"]);
+        let heightmap = fs::read_to_string(dir.path().join("day09-heightmap.pgm")).unwrap();
+        assert!(heightmap.starts_with("P2\n100 100\n9\n"));
+    }
+}
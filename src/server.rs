@@ -0,0 +1,279 @@
+use std::sync::Arc;
+use std::thread;
+use std::time::{Duration, Instant};
+
+use lazy_static::lazy_static;
+use regex::Regex;
+use serde::Serialize;
+use serde_json::json;
+use tiny_http::{Header, Method, Request, Response, Server};
+
+use crate::puzzle::{Metadata, ParsedPuzzle};
+use crate::registry;
+use crate::runner;
+use crate::solve_context::SolveContext;
+
+// The number of threads handling requests
+const WORKERS: usize = 4;
+
+// How long a part may take when no solve timeout is configured, so that input that takes forever
+// can't tie up the workers
+pub const DEFAULT_TIMEOUT: Duration = Duration::from_secs(10);
+
+#[derive(Debug, Serialize)]
+struct PartResult {
+    answer: String,
+    time_ms: f64,
+}
+
+#[derive(Debug, Serialize)]
+struct SolveResult {
+    #[serde(flatten)]
+    metadata: Metadata,
+    parse_time_ms: f64,
+    part_1: PartResult,
+    part_2: PartResult,
+}
+
+// A response to a request, before it is turned into an HTTP response
+#[derive(Debug, PartialEq)]
+pub struct Reply {
+    pub status: u16,
+    pub body: serde_json::Value,
+}
+
+impl Reply {
+    fn ok(body: serde_json::Value) -> Reply {
+        Reply { status: 200, body }
+    }
+
+    fn error(status: u16, message: &str) -> Reply {
+        Reply {
+            status,
+            body: json!({ "error": message }),
+        }
+    }
+}
+
+// Starts an HTTP server on the given address that solves puzzles on request, giving up on parts
+// that take longer than the timeout. Doesn't return unless the server fails to start.
+pub fn serve(address: &str, timeout: Duration) -> Result<()> {
+    let server = Server::http(address)
+        .map_err(|e| format!("Failed to start server on {}: {}", address, e))?;
+    println!("Listening on http://{}", server.server_addr());
+    run(Arc::new(server), timeout);
+    Ok(())
+}
+
+fn run(server: Arc<Server>, timeout: Duration) {
+    let workers = (0..WORKERS)
+        .map(|_| {
+            let server = Arc::clone(&server);
+            thread::spawn(move || {
+                for request in server.incoming_requests() {
+                    respond(request, timeout);
+                }
+            })
+        })
+        .collect::<Vec<_>>();
+    for worker in workers {
+        let _ = worker.join();
+    }
+}
+
+fn respond(mut request: Request, timeout: Duration) {
+    let mut body = String::new();
+    let reply = match request.as_reader().read_to_string(&mut body) {
+        Ok(_) => handle(request.method(), request.url(), &body, timeout),
+        Err(_) => Reply::error(400, "Request body is not valid UTF-8"),
+    };
+    let header = Header::from_bytes("Content-Type", "application/json").unwrap();
+    let response = Response::from_string(reply.body.to_string())
+        .with_status_code(reply.status)
+        .with_header(header);
+    // The client may have gone away, in which case there is nobody to tell about it
+    let _ = request.respond(response);
+}
+
+// Routes a request to the handler for its endpoint
+pub fn handle(method: &Method, url: &str, body: &str, timeout: Duration) -> Reply {
+    lazy_static! {
+        static ref SOLVE: Regex = Regex::new(r"^/2021/day/(\d+)/solve$").unwrap();
+    }
+    let path = url.split('?').next().unwrap();
+    if path == "/days" {
+        return match method {
+            Method::Get => list_days(),
+            _ => Reply::error(405, "Method not allowed"),
+        };
+    }
+    if let Some(caps) = SOLVE.captures(path) {
+        return match (method, caps[1].parse::<u8>()) {
+            (Method::Post, Ok(day)) => solve(day, body, timeout),
+            (Method::Post, Err(_)) => Reply::error(404, "Unknown day"),
+            _ => Reply::error(405, "Method not allowed"),
+        };
+    }
+    Reply::error(404, "Not found")
+}
+
+fn list_days() -> Reply {
+    Reply::ok(json!(registry::puzzles()))
+}
+
+fn solve(day: u8, input: &str, timeout: Duration) -> Reply {
+    if !registry::has_puzzle(day) {
+        return Reply::error(404, &format!("Day {} has not been solved yet", day));
+    }
+    let start = Instant::now();
+    let puzzle: Arc<dyn ParsedPuzzle> = match registry::create_puzzle(day, input) {
+        Ok(puzzle) => Arc::from(puzzle),
+        Err(e) => return Reply::error(400, &e),
+    };
+    let parse_time_ms = elapsed_ms(start);
+    let result = solve_part(&puzzle, 1, timeout).and_then(|part_1| {
+        Ok(SolveResult {
+            metadata: puzzle.metadata(),
+            parse_time_ms,
+            part_1,
+            part_2: solve_part(&puzzle, 2, timeout)?,
+        })
+    });
+    match result {
+        Ok(result) => Reply::ok(json!(result)),
+        Err(reply) => reply,
+    }
+}
+
+// Solves a part off the worker thread, so that the worker can give up on it once it runs out of
+// time, or fails with the reply to send instead
+fn solve_part(
+    puzzle: &Arc<dyn ParsedPuzzle>,
+    part: u8,
+    timeout: Duration,
+) -> std::result::Result<PartResult, Reply> {
+    let day = puzzle.get_day();
+    let start = Instant::now();
+    let ctx = SolveContext::new().with_timeout(timeout);
+    match runner::solve_on_thread(puzzle, part, ctx, Some(timeout)).0 {
+        Ok(answer) => Ok(PartResult {
+            answer,
+            time_ms: elapsed_ms(start),
+        }),
+        Err(e) if e == "Timed out" => Err(Reply::error(
+            504,
+            &format!("Day {} part {} took longer than {:?}", day, part, timeout),
+        )),
+        // Input that parses can still be invalid in ways that make a puzzle panic, which must not
+        // take down the server
+        Err(e) if e == "Panicked" => Err(Reply::error(
+            400,
+            &format!("Invalid puzzle input for day {}", day),
+        )),
+        Err(e) => Err(Reply::error(400, &e)),
+    }
+}
+
+fn elapsed_ms(start: Instant) -> f64 {
+    start.elapsed().as_secs_f64() * 1000.0
+}
+
+type Result<T> = std::result::Result<T, String>;
+
+#[cfg(test)]
+mod tests {
+    use crate::registry;
+    use crate::server::{handle, run, Reply, DEFAULT_TIMEOUT};
+    use std::fs;
+    use std::path::PathBuf;
+    use std::sync::Arc;
+    use std::thread;
+    use std::time::Duration;
+    use tiny_http::{Method, Server};
+
+    fn handle_request(method: &Method, url: &str, body: &str) -> Reply {
+        handle(method, url, body, DEFAULT_TIMEOUT)
+    }
+
+    #[test]
+    fn test_list_days() {
+        let reply = handle_request(&Method::Get, "/days", "");
+        assert_eq!(reply.status, 200);
+        let days = reply.body.as_array().unwrap();
+        assert_eq!(days.len(), registry::days().len());
+        assert_eq!(days[0]["day"], 1);
+        assert_eq!(days[0]["year"], 2021);
+        assert_eq!(days[0]["title"], "Sonar Sweep");
+        assert!(days[0]["part_1_description"].is_string());
+    }
+
+    #[test]
+    fn test_solve() {
+        let input = fs::read_to_string(PathBuf::from("resources/tests/1")).unwrap();
+        let reply = handle_request(&Method::Post, "/2021/day/1/solve", &input);
+        assert_eq!(reply.status, 200);
+        assert_eq!(reply.body["day"], 1);
+        assert_eq!(reply.body["title"], "Sonar Sweep");
+        assert_eq!(reply.body["part_1"]["answer"], "1532");
+        assert_eq!(reply.body["part_2"]["answer"], "1571");
+        assert!(reply.body["parse_time_ms"].as_f64().unwrap() >= 0.0);
+        assert!(reply.body["part_1"]["time_ms"].as_f64().unwrap() >= 0.0);
+        assert!(reply.body["part_2"]["time_ms"].as_f64().unwrap() >= 0.0);
+    }
+
+    #[test]
+    fn test_errors() {
+        assert_eq!(handle_request(&Method::Get, "/", "").status, 404);
+        assert_eq!(handle_request(&Method::Post, "/days", "").status, 405);
+        assert_eq!(
+            handle_request(&Method::Get, "/2021/day/1/solve", "").status,
+            405
+        );
+        assert_eq!(
+            handle_request(&Method::Post, "/2021/day/25/solve", "").status,
+            404
+        );
+        assert_eq!(
+            handle_request(&Method::Post, "/2021/day/999/solve", "").status,
+            404
+        );
+        assert_eq!(
+            handle_request(&Method::Post, "/2020/day/1/solve", "").status,
+            404
+        );
+        let reply = handle_request(&Method::Post, "/2021/day/1/solve", "not a depth");
+        assert_eq!(reply.status, 400);
+        assert!(reply.body["error"].is_string());
+    }
+
+    #[test]
+    fn test_timeout() {
+        // Octopuses that never all flash at once, so part 2 never finishes
+        let timeout = Duration::from_millis(100);
+        let reply = handle(&Method::Post, "/2021/day/11/solve", "29\n", timeout);
+        assert_eq!(reply.status, 504);
+        assert_eq!(reply.body["error"], "Day 11 part 2 took longer than 100ms");
+    }
+
+    #[test]
+    fn test_serve_over_http() {
+        let server = Arc::new(Server::http("127.0.0.1:0").unwrap());
+        let address = server.server_addr().to_ip().unwrap();
+        thread::spawn(move || run(server, DEFAULT_TIMEOUT));
+        let client = reqwest::blocking::Client::new();
+        let response = client
+            .post(format!("http://{}/2021/day/6/solve", address))
+            .body("3,4,3,1,2")
+            .send()
+            .unwrap();
+        assert_eq!(response.status(), 200);
+        let body = serde_json::from_str::<serde_json::Value>(&response.text().unwrap()).unwrap();
+        assert_eq!(body["part_1"]["answer"], "5934");
+        assert_eq!(body["part_2"]["answer"], "26984457539");
+        let response = client
+            .get(format!("http://{}/days", address))
+            .send()
+            .unwrap();
+        assert_eq!(response.status(), 200);
+    }
+}
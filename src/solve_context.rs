@@ -0,0 +1,101 @@
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::{Duration, Instant};
+
+type ProgressCallback = Box<dyn Fn(f64) + Send + Sync>;
+
+// Lets long-running solves report how far along they are, and lets the caller stop them. Solves
+// call `check` regularly and stop with its error once the solve is cancelled or out of time.
+#[derive(Default)]
+pub struct SolveContext {
+    cancelled: AtomicBool,
+    deadline: Option<Instant>,
+    progress: Option<ProgressCallback>,
+}
+
+impl SolveContext {
+    pub fn new() -> SolveContext {
+        SolveContext::default()
+    }
+
+    pub fn with_timeout(mut self, timeout: Duration) -> SolveContext {
+        self.deadline = Some(Instant::now() + timeout);
+        self
+    }
+
+    // Calls the callback with the fraction of the solve that is done (from 0 to 1)
+    pub fn with_progress<F>(mut self, callback: F) -> SolveContext
+    where
+        F: Fn(f64) + Send + Sync + 'static,
+    {
+        self.progress = Some(Box::new(callback));
+        self
+    }
+
+    pub fn cancel(&self) {
+        self.cancelled.store(true, Ordering::Relaxed);
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        self.cancelled.load(Ordering::Relaxed)
+    }
+
+    // Returns an error if the solve should stop
+    pub fn check(&self) -> Result<()> {
+        if self.is_cancelled() {
+            return Err("Cancelled".to_string());
+        }
+        match self.deadline {
+            Some(deadline) if Instant::now() >= deadline => Err("Timed out".to_string()),
+            _ => Ok(()),
+        }
+    }
+
+    pub fn report_progress(&self, done: usize, total: usize) {
+        if let Some(progress) = &self.progress {
+            if total > 0 {
+                progress((done as f64 / total as f64).min(1.0));
+            }
+        }
+    }
+}
+
+type Result<T> = std::result::Result<T, String>;
+
+#[cfg(test)]
+mod tests {
+    use crate::solve_context::SolveContext;
+    use std::sync::{Arc, Mutex};
+    use std::thread;
+    use std::time::Duration;
+
+    #[test]
+    fn test_cancel() {
+        let ctx = SolveContext::new();
+        assert!(ctx.check().is_ok());
+        ctx.cancel();
+        assert!(ctx.is_cancelled());
+        assert_eq!(ctx.check().unwrap_err(), "Cancelled");
+    }
+
+    #[test]
+    fn test_timeout() {
+        let ctx = SolveContext::new().with_timeout(Duration::from_millis(20));
+        assert!(ctx.check().is_ok());
+        thread::sleep(Duration::from_millis(30));
+        assert_eq!(ctx.check().unwrap_err(), "Timed out");
+    }
+
+    #[test]
+    fn test_progress() {
+        let reported = Arc::new(Mutex::new(Vec::new()));
+        let ctx = SolveContext::new().with_progress({
+            let reported = Arc::clone(&reported);
+            move |fraction| reported.lock().unwrap().push(fraction)
+        });
+        ctx.report_progress(1, 4);
+        ctx.report_progress(4, 4);
+        ctx.report_progress(1, 0);
+        assert_eq!(*reported.lock().unwrap(), vec![0.25, 1.0]);
+        SolveContext::new().report_progress(1, 2);
+    }
+}
@@ -0,0 +1,550 @@
+use std::borrow::Cow;
+use std::sync::mpsc::{self, Receiver, TryRecvError};
+use std::sync::Arc;
+use std::thread;
+use std::time::{Duration, Instant};
+
+use ratatui::crossterm::event::{self, Event, KeyCode, KeyEventKind};
+use ratatui::layout::{Constraint, Layout};
+use ratatui::style::{Modifier, Style};
+use ratatui::text::Line;
+use ratatui::widgets::{Block, Borders, Paragraph, Row, Table, TableState};
+use ratatui::{DefaultTerminal, Frame};
+
+use crate::puzzle::{Metadata, ParsedPuzzle};
+use crate::puzzle_input_fetcher::PuzzleInputFetcher;
+use crate::registry;
+use crate::solve_context::SolveContext;
+
+const HELP: &str = "↑/↓ select  enter solve  1/2 solve a part  a answers  i input  v pictures  \
+                    pgup/pgdn scroll  esc cancel  q quit";
+
+// How often the worker thread is checked for an answer while a part is being solved
+const POLL_INTERVAL: Duration = Duration::from_millis(50);
+
+// What the pane below the list of days shows for the selected day
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum View {
+    Answers,
+    Input,
+
+    // The picture with this index
+    Image(usize),
+}
+
+// What is known about a day from solving it during this session
+struct DayStatus {
+    metadata: Metadata,
+    answers: [Option<Result<String>>; 2],
+    runtimes: [Option<Duration>; 2],
+}
+
+// A part that is being solved on its own thread, so that the UI keeps responding meanwhile
+struct Solving {
+    // The index of the day in the list of days
+    index: usize,
+    part: u8,
+    ctx: Arc<SolveContext>,
+    start: Instant,
+    receiver: Receiver<Result<String>>,
+}
+
+// The input and pictures of the selected day, kept between redraws since making the pictures means
+// parsing the input
+struct DayCache {
+    day: u8,
+    input: Result<String>,
+
+    // The name and text of each picture, made the first time that they are needed
+    images: Option<Result<Vec<(String, String)>>>,
+}
+
+// An interactive terminal UI that lists the days, solves them on request and shows their input and
+// pictures
+pub struct App<'a> {
+    fetcher: &'a mut PuzzleInputFetcher,
+    timeout: Option<Duration>,
+    days: Vec<DayStatus>,
+    table: TableState,
+    view: View,
+    scroll: u16,
+
+    // The parts (and the indexes of their days) still to be solved, one after the other
+    pending: Vec<(usize, u8)>,
+    solving: Option<Solving>,
+    cache: Option<DayCache>,
+    message: String,
+    quit: bool,
+}
+
+impl<'a> App<'a> {
+    pub fn new(fetcher: &'a mut PuzzleInputFetcher, timeout: Option<Duration>) -> App<'a> {
+        let days = registry::puzzles()
+            .into_iter()
+            .map(|metadata| DayStatus {
+                metadata,
+                answers: [None, None],
+                runtimes: [None, None],
+            })
+            .collect();
+        App {
+            fetcher,
+            timeout,
+            days,
+            table: TableState::default().with_selected(0),
+            view: View::Answers,
+            scroll: 0,
+            pending: Vec::new(),
+            solving: None,
+            cache: None,
+            message: String::new(),
+            quit: false,
+        }
+    }
+
+    pub fn run(&mut self, terminal: &mut DefaultTerminal) -> Result<()> {
+        while !self.quit {
+            self.update_solving();
+            terminal
+                .draw(|frame| self.draw(frame))
+                .map_err(|e| e.to_string())?;
+            // Waits for keys only briefly while solving, to notice when the answer comes in
+            if self.solving.is_some() && !event::poll(POLL_INTERVAL).map_err(|e| e.to_string())? {
+                continue;
+            }
+            if let Event::Key(key) = event::read().map_err(|e| e.to_string())? {
+                if key.kind == KeyEventKind::Press {
+                    self.handle_key(key.code);
+                }
+            }
+        }
+        self.cancel();
+        Ok(())
+    }
+
+    fn selected(&self) -> usize {
+        self.table.selected().unwrap_or(0)
+    }
+
+    fn select(&mut self, index: usize) {
+        self.table.select(Some(index.min(self.days.len() - 1)));
+        self.view = View::Answers;
+        self.scroll = 0;
+        self.cache = None;
+        self.message.clear();
+    }
+
+    fn handle_key(&mut self, key: KeyCode) {
+        match key {
+            KeyCode::Esc | KeyCode::Char('c') if self.solving.is_some() => self.cancel(),
+            KeyCode::Char('q') | KeyCode::Esc => self.quit = true,
+            KeyCode::Up | KeyCode::Char('k') => self.select(self.selected().saturating_sub(1)),
+            KeyCode::Down | KeyCode::Char('j') => self.select(self.selected() + 1),
+            KeyCode::Enter | KeyCode::Char('r') => self.solve_later(&[1, 2]),
+            KeyCode::Char('1') => self.solve_later(&[1]),
+            KeyCode::Char('2') => self.solve_later(&[2]),
+            KeyCode::Char('a') => self.show(View::Answers),
+            KeyCode::Char('i') => self.show(View::Input),
+            KeyCode::Char('v') => self.show_next_image(),
+            KeyCode::PageUp => self.scroll = self.scroll.saturating_sub(10),
+            KeyCode::PageDown => self.scroll = self.scroll.saturating_add(10),
+            _ => {}
+        }
+    }
+
+    fn show(&mut self, view: View) {
+        self.view = view;
+        self.scroll = 0;
+    }
+
+    fn show_next_image(&mut self) {
+        let next = match self.view {
+            View::Image(index) => index + 1,
+            _ => 0,
+        };
+        match self.images().as_ref().map(Vec::len) {
+            Ok(0) => self.message = format!("Day {:02} has no pictures", self.day()),
+            Ok(count) => self.show(View::Image(next % count)),
+            Err(e) => self.message = e.clone(),
+        }
+    }
+
+    fn day(&self) -> u8 {
+        self.days[self.selected()].metadata.day
+    }
+
+    fn solve_later(&mut self, parts: &[u8]) {
+        let index = self.selected();
+        self.pending.extend(parts.iter().map(|&part| (index, part)));
+        self.message.clear();
+    }
+
+    // Starts solving the next pending part once the previous one is done, and records the answer
+    // when it comes in. A part that runs out of time is cancelled and no longer waited for, as the
+    // runner does; one that never checks its context is abandoned rather than stopped.
+    fn update_solving(&mut self) {
+        if let Some(solving) = &self.solving {
+            let answer = match solving.receiver.try_recv() {
+                Ok(answer) => answer,
+                Err(TryRecvError::Disconnected) => Err("Panicked".to_string()),
+                Err(TryRecvError::Empty) => match self.timeout {
+                    Some(timeout) if solving.start.elapsed() > timeout => {
+                        solving.ctx.cancel();
+                        Err("Timed out".to_string())
+                    }
+                    _ => return,
+                },
+            };
+            self.finish(answer);
+        }
+        while self.solving.is_none() && !self.pending.is_empty() {
+            let (index, part) = self.pending.remove(0);
+            if let Err(e) = self.start(index, part) {
+                self.days[index].answers[part as usize - 1] = Some(Err(e));
+            }
+        }
+    }
+
+    fn start(&mut self, index: usize, part: u8) -> Result<()> {
+        let day = self.days[index].metadata.day;
+        // The input may only now be fetched, which the cached input wouldn't show
+        self.cache = None;
+        let input = self.fetcher.fetch_puzzle_input(day)?;
+        let puzzle: Arc<dyn ParsedPuzzle> = Arc::from(registry::create_puzzle(day, input)?);
+        let mut ctx = SolveContext::new();
+        if let Some(timeout) = self.timeout {
+            ctx = ctx.with_timeout(timeout);
+        }
+        let ctx = Arc::new(ctx);
+        let (sender, receiver) = mpsc::channel();
+        {
+            let ctx = Arc::clone(&ctx);
+            thread::spawn(move || {
+                let answer = match part {
+                    1 => puzzle.solve_part_1_with(&ctx),
+                    _ => puzzle.solve_part_2_with(&ctx),
+                };
+                // The UI has stopped waiting if the part was cancelled or timed out
+                let _ = sender.send(answer);
+            });
+        }
+        self.solving = Some(Solving {
+            index,
+            part,
+            ctx,
+            start: Instant::now(),
+            receiver,
+        });
+        Ok(())
+    }
+
+    // Records the answer to the part being solved
+    fn finish(&mut self, answer: Result<String>) {
+        let Some(solving) = self.solving.take() else {
+            return;
+        };
+        let status = &mut self.days[solving.index];
+        let part = solving.part as usize - 1;
+        status.runtimes[part] = answer.is_ok().then(|| solving.start.elapsed());
+        status.answers[part] = Some(answer);
+        if solving.index == self.selected() {
+            self.view = View::Answers;
+        }
+    }
+
+    // Stops solving the current part and drops the parts that were still to be solved
+    fn cancel(&mut self) {
+        self.pending.clear();
+        if let Some(solving) = &self.solving {
+            solving.ctx.cancel();
+            self.finish(Err("Cancelled".to_string()));
+            self.message = "Cancelled".to_string();
+        }
+    }
+
+    fn cache(&mut self) -> &mut DayCache {
+        let day = self.day();
+        if self.cache.as_ref().is_none_or(|cache| cache.day != day) {
+            let input = self.fetcher.fetch_puzzle_input(day).map(str::to_string);
+            self.cache = Some(DayCache {
+                day,
+                input,
+                images: None,
+            });
+        }
+        self.cache.as_mut().unwrap()
+    }
+
+    fn images(&mut self) -> &Result<Vec<(String, String)>> {
+        let cache = self.cache();
+        if cache.images.is_none() {
+            let images = match &cache.input {
+                Ok(input) => registry::create_puzzle(cache.day, input).map(|puzzle| {
+                    puzzle
+                        .images()
+                        .into_iter()
+                        .map(|(name, image)| (name, image.to_text()))
+                        .collect()
+                }),
+                Err(e) => Err(e.clone()),
+            };
+            cache.images = Some(images);
+        }
+        cache.images.as_ref().unwrap()
+    }
+
+    fn draw(&mut self, frame: &mut Frame) {
+        let [list, details, footer] = Layout::vertical([
+            Constraint::Length(self.days.len() as u16 + 3),
+            Constraint::Min(0),
+            Constraint::Length(1),
+        ])
+        .areas(frame.area());
+        let rows = self
+            .days
+            .iter()
+            .map(|status| {
+                let day = status.metadata.day;
+                let cached = self.fetcher.local_puzzle_input_path(day).exists();
+                let runtime = status.runtimes.iter().flatten().sum::<Duration>();
+                Row::new(vec![
+                    format!("{:02}", day),
+                    status.metadata.title.to_string(),
+                    if cached { "cached" } else { "-" }.to_string(),
+                    short_answer(&status.answers[0]),
+                    short_answer(&status.answers[1]),
+                    if status.runtimes.iter().any(Option::is_some) {
+                        format!("{:.3?}", runtime)
+                    } else {
+                        "-".to_string()
+                    },
+                ])
+            })
+            .collect::<Vec<_>>();
+        let table = Table::new(
+            rows,
+            [
+                Constraint::Length(3),
+                Constraint::Length(24),
+                Constraint::Length(6),
+                Constraint::Length(16),
+                Constraint::Length(16),
+                Constraint::Length(12),
+            ],
+        )
+        .header(
+            Row::new(vec!["Day", "Title", "Input", "Part 1", "Part 2", "Runtime"])
+                .style(Style::new().add_modifier(Modifier::BOLD)),
+        )
+        .row_highlight_style(Style::new().add_modifier(Modifier::REVERSED))
+        .block(
+            Block::new()
+                .borders(Borders::ALL)
+                .title("Advent of Code 2021"),
+        );
+        frame.render_stateful_widget(table, list, &mut self.table);
+
+        match self.view {
+            View::Answers => {}
+            View::Input => {
+                self.cache();
+            }
+            View::Image(_) => {
+                self.images();
+            }
+        }
+        let (title, text) = self.details();
+        let details_widget = Paragraph::new(text)
+            .scroll((self.scroll, 0))
+            .block(Block::new().borders(Borders::ALL).title(title));
+        frame.render_widget(details_widget, details);
+
+        let footer_text = match &self.solving {
+            _ if !self.message.is_empty() => self.message.clone(),
+            Some(solving) => format!(
+                "Solving day {:02} part {}... (esc to cancel)",
+                self.days[solving.index].metadata.day, solving.part
+            ),
+            None => HELP.to_string(),
+        };
+        frame.render_widget(Line::from(footer_text), footer);
+    }
+
+    // The title and contents of the pane below the list of days. The input and pictures are taken
+    // from the cache, which `draw` fills first.
+    fn details(&self) -> (String, Cow<'_, str>) {
+        let day = self.day();
+        match self.view {
+            View::Answers => {
+                let status = &self.days[self.selected()];
+                let metadata = &status.metadata;
+                let descriptions = [metadata.part_1_description, metadata.part_2_description];
+                let text = (0..2)
+                    .map(|i| {
+                        let answer = match &status.answers[i] {
+                            Some(Ok(answer)) => answer.clone(),
+                            Some(Err(e)) => format!("failed: {}", e),
+                            None => "not solved yet".to_string(),
+                        };
+                        let runtime = status.runtimes[i]
+                            .map_or(String::new(), |runtime| format!(" ({:.3?})", runtime));
+                        format!(
+                            "Part {} ({}): {}{}",
+                            i + 1,
+                            descriptions[i],
+                            answer,
+                            runtime
+                        )
+                    })
+                    .collect::<Vec<_>>()
+                    .join("\n");
+                (format!("Day {:02}: {}", day, metadata.title), text.into())
+            }
+            View::Input => {
+                let text = match self.cache.as_ref().map(|cache| &cache.input) {
+                    Some(Ok(input) | Err(input)) => input.as_str(),
+                    None => "",
+                };
+                (format!("Day {:02} input", day), text.into())
+            }
+            View::Image(index) => match self.cache.as_ref().and_then(|cache| cache.images.as_ref())
+            {
+                Some(Ok(images)) if index < images.len() => {
+                    let (name, text) = &images[index];
+                    (format!("Day {:02} {}", day, name), text.as_str().into())
+                }
+                Some(Err(e)) => (format!("Day {:02}", day), e.as_str().into()),
+                _ => (format!("Day {:02}", day), "".into()),
+            },
+        }
+    }
+}
+
+// The answer as it fits in the list of days
+fn short_answer(answer: &Option<Result<String>>) -> String {
+    match answer {
+        Some(Ok(answer)) => answer.lines().next().unwrap_or("").to_string(),
+        Some(Err(_)) => "failed".to_string(),
+        None => "-".to_string(),
+    }
+}
+
+// Runs the terminal UI until the user quits
+pub fn run(fetcher: &mut PuzzleInputFetcher, timeout: Option<Duration>) -> Result<()> {
+    let mut terminal = ratatui::init();
+    let result = App::new(fetcher, timeout).run(&mut terminal);
+    ratatui::restore();
+    result
+}
+
+type Result<T> = std::result::Result<T, String>;
+
+#[cfg(test)]
+mod tests {
+    use crate::puzzle_input_fetcher::PuzzleInputFetcher;
+    use crate::tui::{App, View};
+    use ratatui::backend::TestBackend;
+    use ratatui::crossterm::event::KeyCode;
+    use ratatui::Terminal;
+    use std::path::Path;
+    use std::thread;
+    use std::time::Duration;
+
+    fn fetcher() -> PuzzleInputFetcher {
+        PuzzleInputFetcher::create_custom(
+            "http://localhost",
+            Path::new("resources/tests"),
+            Path::new("cookie.txt"),
+        )
+        .with_offline(true)
+    }
+
+    fn screen(app: &mut App) -> String {
+        let mut terminal = Terminal::new(TestBackend::new(100, 40)).unwrap();
+        terminal.draw(|frame| app.draw(frame)).unwrap();
+        let buffer = terminal.backend().buffer();
+        buffer
+            .content()
+            .chunks(buffer.area.width as usize)
+            .map(|row| row.iter().map(|cell| cell.symbol()).collect::<String>())
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+
+    // Waits until every part that was asked for has been solved
+    fn wait(app: &mut App) {
+        app.update_solving();
+        while app.solving.is_some() {
+            thread::sleep(Duration::from_millis(10));
+            app.update_solving();
+        }
+    }
+
+    #[test]
+    fn test_solve() {
+        let mut fetcher = fetcher();
+        let mut app = App::new(&mut fetcher, None);
+        assert!(screen(&mut app).contains("Sonar Sweep"));
+        app.handle_key(KeyCode::Down);
+        app.handle_key(KeyCode::Char('1'));
+        app.update_solving();
+        assert!(screen(&mut app).contains("Solving day 02 part 1..."));
+        wait(&mut app);
+        let screen = screen(&mut app);
+        assert!(screen.contains("Part 1 (Multiply the final position by the final depth): 1604850"));
+        assert!(screen.contains("steering with aim): not solved yet"));
+        assert!(app.days[1].answers[0].is_some());
+        assert!(app.days[1].answers[1].is_none());
+    }
+
+    #[test]
+    fn test_cancel() {
+        let mut fetcher = fetcher();
+        let mut app = App::new(&mut fetcher, None);
+        for _ in 0..11 {
+            app.handle_key(KeyCode::Down);
+        }
+        app.handle_key(KeyCode::Enter);
+        app.update_solving();
+        assert!(app.solving.is_some());
+        app.handle_key(KeyCode::Esc);
+        assert!(!app.quit);
+        assert!(app.solving.is_none() && app.pending.is_empty());
+        assert_eq!(app.days[11].answers[0], Some(Err("Cancelled".to_string())));
+        assert_eq!(app.days[11].answers[1], None);
+        app.handle_key(KeyCode::Esc);
+        assert!(app.quit);
+    }
+
+    #[test]
+    fn test_views() {
+        let mut fetcher = fetcher();
+        let mut app = App::new(&mut fetcher, None);
+        app.handle_key(KeyCode::Char('i'));
+        assert_eq!(app.view, View::Input);
+        app.handle_key(KeyCode::Char('v'));
+        assert_eq!(app.view, View::Input);
+        assert_eq!(app.message, "Day 01 has no pictures");
+        for _ in 0..12 {
+            app.handle_key(KeyCode::Down);
+        }
+        assert_eq!(app.day(), 13);
+        app.handle_key(KeyCode::Char('v'));
+        app.handle_key(KeyCode::Char('v'));
+        assert_eq!(app.view, View::Image(1));
+        assert!(screen(&mut app).contains("Day 13 folded"));
+        // Redrawing uses the cached pictures rather than parsing the input again
+        let cache = app.cache.as_ref().unwrap();
+        assert_eq!(cache.day, 13);
+        assert!(matches!(&cache.images, Some(Ok(images)) if images.len() == 2));
+        app.handle_key(KeyCode::Char('v'));
+        assert_eq!(app.view, View::Image(0));
+        app.handle_key(KeyCode::Down);
+        assert!(app.cache.is_none());
+        app.handle_key(KeyCode::Char('i'));
+        assert!(screen(&mut app).contains("Day 14 input"));
+        assert_eq!(app.cache.as_ref().unwrap().day, 14);
+        app.handle_key(KeyCode::Char('q'));
+        assert!(app.quit);
+    }
+}
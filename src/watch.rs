@@ -0,0 +1,135 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::thread;
+use std::time::{Duration, Instant, SystemTime};
+
+use crate::puzzle_input_fetcher::PuzzleInputFetcher;
+use crate::runner;
+
+// How often the watched files are checked for changes
+const POLL_INTERVAL: Duration = Duration::from_millis(300);
+
+// Notices when files change by comparing their modification times between calls to `changed`. A
+// file that does not exist counts as unchanged until it is created.
+pub struct FileWatcher {
+    paths: Vec<PathBuf>,
+    modified: Vec<Option<SystemTime>>,
+}
+
+impl FileWatcher {
+    pub fn new(paths: Vec<PathBuf>) -> FileWatcher {
+        let modified = paths.iter().map(|path| modified(path)).collect();
+        FileWatcher { paths, modified }
+    }
+
+    // Returns the files that changed since the last call (or since the watcher was created)
+    pub fn changed(&mut self) -> Vec<&Path> {
+        let mut changed = Vec::new();
+        for (path, last_modified) in self.paths.iter().zip(self.modified.iter_mut()) {
+            let modified = modified(path);
+            if modified != *last_modified {
+                *last_modified = modified;
+                changed.push(path.as_path());
+            }
+        }
+        changed
+    }
+}
+
+fn modified(path: &Path) -> Option<SystemTime> {
+    fs::metadata(path).and_then(|m| m.modified()).ok()
+}
+
+// Solves the given day using its puzzle input and the scratch input (if any), then solves it again
+// whenever either of them changes. Runs until the process is stopped.
+pub fn watch(
+    fetcher: &mut PuzzleInputFetcher,
+    day: u8,
+    scratch: Option<&Path>,
+    timeout: Option<Duration>,
+) -> Result<()> {
+    // Makes sure the puzzle input is in the local store before watching it. Without it only the
+    // scratch input can be solved, which is still worth watching.
+    if let Err(e) = fetcher.fetch_puzzle_input(day) {
+        if scratch.is_none() {
+            return Err(e);
+        }
+    }
+    let mut paths = vec![fetcher.local_puzzle_input_path(day)];
+    paths.extend(scratch.map(Path::to_path_buf));
+    let mut watcher = FileWatcher::new(paths);
+    solve(fetcher, day, scratch, timeout);
+    loop {
+        thread::sleep(POLL_INTERVAL);
+        let changed = watcher.changed();
+        if changed.is_empty() {
+            continue;
+        }
+        for path in changed {
+            eprintln!("{} changed", path.display());
+        }
+        fetcher.forget_puzzle_input(day);
+        solve(fetcher, day, scratch, timeout);
+    }
+}
+
+// Solves the day with each input, printing errors rather than returning them so that watching
+// carries on while an input is being edited
+fn solve(
+    fetcher: &mut PuzzleInputFetcher,
+    day: u8,
+    scratch: Option<&Path>,
+    timeout: Option<Duration>,
+) {
+    let puzzle_input = fetcher.fetch_puzzle_input(day).map(str::to_string);
+    let mut inputs = vec![("puzzle input".to_string(), puzzle_input)];
+    if let Some(path) = scratch {
+        let input = fs::read_to_string(path)
+            .map_err(|e| format!("Failed to read {}: {}", path.display(), e));
+        inputs.push((path.display().to_string(), input));
+    }
+    for (name, input) in inputs {
+        println!("== {} ==", name);
+        let start = Instant::now();
+        match input.and_then(|input| runner::solve_input(day, &input, timeout)) {
+            Ok(result) => {
+                runner::print_result(&result);
+                println!("Solved in {:.3?}", start.elapsed());
+            }
+            Err(e) => println!("{}", e),
+        }
+    }
+}
+
+type Result<T> = std::result::Result<T, String>;
+
+#[cfg(test)]
+mod tests {
+    use crate::watch::FileWatcher;
+    use std::fs;
+    use std::time::{Duration, SystemTime};
+    use tempfile::tempdir;
+
+    #[test]
+    fn test_file_watcher() {
+        let dir = tempdir().unwrap();
+        let existing = dir.path().join("existing");
+        let created = dir.path().join("created");
+        fs::write(&existing, "1").unwrap();
+        let mut watcher = FileWatcher::new(vec![existing.clone(), created.clone()]);
+        assert!(watcher.changed().is_empty());
+
+        // Sets the time explicitly, as the file system may not tell apart writes this close together
+        let file = fs::File::options().write(true).open(&existing).unwrap();
+        file.set_modified(SystemTime::now() + Duration::from_secs(10))
+            .unwrap();
+        assert_eq!(watcher.changed(), vec![existing.as_path()]);
+        assert!(watcher.changed().is_empty());
+
+        fs::write(&created, "2").unwrap();
+        assert_eq!(watcher.changed(), vec![created.as_path()]);
+        fs::remove_file(&created).unwrap();
+        assert_eq!(watcher.changed(), vec![created.as_path()]);
+        assert!(watcher.changed().is_empty());
+    }
+}